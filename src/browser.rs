@@ -1,65 +1,794 @@
+use chrono::{DateTime, Local};
 use eframe::egui;
-use quick_xml::events::Event;
-use quick_xml::Reader;
-use rayon::prelude::*;
-use std::collections::BTreeMap;
-use std::fs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use std::sync::{Arc, Mutex};
-use walkdir::WalkDir;
-use crate::settings::AppSettings;
+use crate::database::{SharedDatabase, SharedDefDatabase};
+use crate::command_palette::Command;
+use crate::defs::{def_name_list, find_texture, texture_candidates, AbstractFilter, DefEntry, EntrySort};
+use crate::diff::DefChangeset;
+use crate::export::{def_xpath, entries_to_csv, entries_to_defs_xml};
+use crate::export_preview::Exporter;
+use crate::fs_abstraction::{detect_encoding_info, EncodingInfo};
+use crate::intern::Symbol;
+use crate::list_navigation::{step, ListNavigation, NavKey, NavRow};
+use crate::patches::PatchEntry;
+use crate::merge::{flatten_nodes, parse_xml_nodes, XmlNode};
+use crate::popout::Popouts;
+use crate::renderers::apparel::{apparel_ui, parse_apparel_info};
+use crate::renderers::curves::{curve_chart, find_curves, CHART_SIZE};
+use crate::renderers::def_refs::{referenced_by_ui, xml_with_references, DefReference};
+use crate::renderers::scenario::{parse_scenario_parts, scenario_ui};
+use crate::renderers::xml_table::{xml_table_ui, PathSort};
+use crate::renderers::xml_tree::xml_tree_ui;
+use crate::scanner::ScanRequest;
+use crate::search::{fuzzy_match, FuzzyMatch};
+use crate::search_index::{find_mod_about, mod_name, ModAbout, SearchHit};
+use crate::settings::{editor_command_args, AppSettings, Bookmark, QuickFilter, RecentDefEntry};
+use crate::textures::{decode_image, paint_texture, TextureLoader, TextureState};
+use crate::widgets::{empty_state, highlighted_text, loading_skeleton, FindBar};
+
+/// 列表中描述提示的最大字元數
+const DESCRIPTION_PREVIEW: usize = 200;
+
+/// 左側列表的寬度（視窗很窄時縮小）
+const LIST_WIDTH: f32 = 220.0;
+
+/// 全選超過此數量時需要確認
+const SELECT_ALL_CONFIRM_THRESHOLD: usize = 100;
+
+/// 最近檢視保留的數量
+const RECENT_LIMIT: usize = 20;
+
+/// 最近檢視變更後，停止切換條目這麼久才寫入設置檔，避免鍵盤瀏覽時每一列都寫入一次
+const RECENT_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// 「🕐 最近檢視」區塊顯示的數量
+const RECENT_SHOWN: usize = 5;
+
+/// 全域搜尋最多保留的結果數量
+const GLOBAL_RESULT_LIMIT: usize = 500;
+
+/// 全域搜尋每頁顯示的結果數量
+const GLOBAL_PAGE_SIZE: usize = 50;
 
 pub struct DefBrowserTab {
     base_directory: String,
-    defs: BTreeMap<String, Vec<DefEntry>>, // DefType -> List of entries
-    selected_def_type: Option<String>,
+    database: SharedDatabase,
+    last_generation: u64,  // 上次看到的資料庫版本
+    selected_def_type: Option<Symbol>,
     selected_def_entry: Option<usize>,
-    is_loading: bool,
-    status_message: String,
+    selected_patch: Option<(Symbol, usize)>, // 選取的 PatchOperation（操作類型, 索引），選取 Def 時清除
+    scan_request: ScanRequest,
     settings: Arc<Mutex<AppSettings>>,
     initialized: bool,
-    search_query: String,  // 添加搜索字段
-    auto_scanned: bool,    // 記錄是否已自動掃描
+    filter: BrowserFilter,
+    type_sort: TypeSort, // 掃描後保留
+    grouping: BrowserGrouping,
+    xml_view: XmlView,
+    xml_tree: Option<XmlTreeCache>, // 樹狀與表格檢視的節點，選取的條目改變時重新解析
+    xml_table_sort: PathSort,
+    selected_mod: Option<String>,         // 依模組分組時，點擊的類型標題所在的模組
+    mod_abouts: HashMap<PathBuf, Option<ModAbout>>, // 資料夾 -> 所屬模組的 About.xml 資訊，掃描後清除
+    selected_entries: HashSet<(Symbol, usize)>,        // 多選的條目 (DefType, 索引)
+    selection_anchor: Option<(Symbol, usize)>,         // Shift+點擊範圍選取的起點：上次點擊的條目
+    list_nav: ListNavigation,                          // 列表的鍵盤操作
+    pending_select_all: Option<Vec<(Symbol, usize)>>,  // 等待確認的全選
+    new_preset_name: String,
+    pending_restore: Option<(String, String)>, // 上次結束時選取的 (DefType, defName)，等掃描完成後還原
+    popouts: Popouts,
+    show_changes: bool, // 顯示與上次掃描相比的變更記錄
+    changes_export: Option<String>, // 上次匯出變更記錄的結果
+    csv_export: Option<CsvExportDialog>, // 匯出 CSV 的欄位設定視窗
+    file_info: Option<FileInfoCache>, // 選取條目所在檔案的編碼資訊，展開「檔案資訊」時才讀取
+    reference_popup: Option<DefReference>, // XML 中 defName 參照的預覽
+    xml_find: FindBar, // XML 內容上方的尋找列
+    exporter: Exporter,
+    textures: TextureLoader,
+    recently_viewed: VecDeque<RecentDefEntry>, // 最近檢視的 Defs，最新的在前
+    recorded_selection: Option<(Symbol, usize)>, // 已加入最近檢視的選擇
+    recent_changed_at: Option<Instant>, // 最近檢視尚未寫入設置檔時，最後一次變更的時間
+    bookmarks: Vec<Bookmark>, // 收藏的 Defs，重新掃描後找不到的仍保留
+    entry_sorts: HashMap<String, EntrySort>, // 各 Def 類型下條目的排序方式（保存在設置中）
+    global_query: String,
+    global_search: Option<GlobalSearch>, // 上一次全域搜尋的結果
+    showing_global: bool,                // 左側顯示全域搜尋結果而非類型樹
+}
+
+/// 匯出所選類型為 CSV 的對話框
+struct CsvExportDialog {
+    def_type: Symbol,
+    columns: String,         // 每行一個標籤路徑
+    message: Option<String>, // 上次匯出的結果
+}
+
+/// 已解析的樹狀檢視節點與表格檢視的列
+struct XmlTreeCache {
+    entry: (Symbol, usize),
+    generation: u64,
+    nodes: Vec<XmlNode>,
+    rows: Vec<(String, String)>, // 攤平後的 (標籤路徑, 值)
+}
+
+/// 已讀取的檔案編碼資訊，檔案路徑或修改時間改變時重新讀取
+struct FileInfoCache {
+    path: PathBuf,
+    modified_at: Option<SystemTime>,
+    info: Result<EncodingInfo, String>,
 }
 
-#[derive(Debug, Clone)]
-struct DefEntry {
-    def_name: String,
-    file_path: PathBuf,
-    xml_content: String,
-    def_type: String,
+/// 全域搜尋的結果；選取結果後保留，按 Esc 可回到結果列表
+struct GlobalSearch {
+    query: String,
+    hits: Vec<SearchHit>,
+    total: usize, // 截斷前的結果數量
+    page: usize,
+}
+
+
+/// 跨重新啟動保存的瀏覽器介面狀態
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrowserUiState {
+    selected_def_type: Option<String>,
+    selected_def_name: Option<String>,
+    search_query: String,
+    def_type_pattern: String,
+    path_pattern: String,
+    tag_path: String,
+    abstract_filter: AbstractFilter,
+    fuzzy: bool,
+    type_sort: TypeSort,
+    grouping: BrowserGrouping,
+    xml_view: XmlView,
+}
+
+/// Def 類型列表的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TypeSort {
+    #[default]
+    Name,
+    Count,
+}
+
+impl TypeSort {
+    pub const ALL: [TypeSort; 2] = [TypeSort::Name, TypeSort::Count];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TypeSort::Name => "名稱",
+            TypeSort::Count => "數量",
+        }
+    }
+}
+
+/// 詳細資訊中 XML 的顯示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum XmlView {
+    #[default]
+    Text,
+    Tree,
+    Table,
+}
+
+/// 一個 Def 類型與其下符合篩選的條目索引
+type TypeGroup<'a> = (&'a Symbol, &'a Vec<DefEntry>, Vec<usize>);
+
+/// 左側列表的分組方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BrowserGrouping {
+    #[default]
+    Type,
+    Mod,
+}
+
+impl BrowserGrouping {
+    pub const ALL: [BrowserGrouping; 2] = [BrowserGrouping::Type, BrowserGrouping::Mod];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BrowserGrouping::Type => "Def 類型",
+            BrowserGrouping::Mod => "模組 → Def 類型",
+        }
+    }
+}
+
+/// 瀏覽器的篩選條件
+#[derive(Debug, Clone, Default)]
+struct BrowserFilter {
+    search_query: String,
+    def_type_pattern: String,
+    path_pattern: String,
+    tag_path: String, // 只顯示包含此元素的條目，見 `DefEntry::has_tag_path`
+    abstract_filter: AbstractFilter,
+    fuzzy: bool, // 關鍵字以模糊比對 defName（依序包含所有字元即相符）
+}
+
+impl BrowserFilter {
+    fn is_active(&self) -> bool {
+        !self.search_query.is_empty()
+            || !self.def_type_pattern.is_empty()
+            || !self.path_pattern.is_empty()
+            || !self.tag_path.trim().is_empty()
+            || self.abstract_filter != AbstractFilter::All
+    }
+
+    fn matches(&self, def_type: &str, entry: &DefEntry) -> bool {
+        if !contains_ignore_case(def_type, &self.def_type_pattern) {
+            return false;
+        }
+        if !contains_ignore_case(&entry.file_path.to_string_lossy(), &self.path_pattern) {
+            return false;
+        }
+        if !entry.has_tag_path(&self.tag_path) {
+            return false;
+        }
+        match self.abstract_filter {
+            AbstractFilter::All => {}
+            AbstractFilter::ConcreteOnly if entry.is_abstract => return false,
+            AbstractFilter::AbstractOnly if !entry.is_abstract => return false,
+            _ => {}
+        }
+        // Def 類型本身符合搜索時顯示所有條目；否則只顯示名稱符合的條目
+        contains_ignore_case(def_type, &self.search_query) || self.name_match(&entry.def_name).is_some()
+    }
+
+    /// defName 與關鍵字的相符程度；非模糊模式時包含關鍵字即為 0 分
+    fn name_match(&self, def_name: &str) -> Option<FuzzyMatch> {
+        if self.fuzzy {
+            fuzzy_match(def_name, &self.search_query)
+        } else {
+            contains_ignore_case(def_name, &self.search_query).then(|| FuzzyMatch {
+                score: 0,
+                positions: Vec::new(),
+            })
+        }
+    }
+
+    /// 模糊搜尋中，結果依分數排序並標示相符字元
+    fn is_fuzzy_search(&self) -> bool {
+        self.fuzzy && !self.search_query.trim().is_empty()
+    }
+}
+
+/// 依列表順序合併選取條目的 XML，包在同一個 `<Defs>` 中並註明各自的來源檔案
+fn selected_xml(db: &SharedDefDatabase, selected: &HashSet<(Symbol, usize)>) -> String {
+    let mut keys: Vec<&(Symbol, usize)> = selected.iter().collect();
+    keys.sort();
+    let entries: Vec<&DefEntry> = keys
+        .iter()
+        .filter_map(|(def_type, idx)| db.entries.get(def_type).and_then(|e| e.get(*idx)))
+        .collect();
+    entries_to_defs_xml(&entries, &db.base_path)
+}
+
+/// 檔案大小、編碼、換行與字元數；編碼問題是模組無法解析的常見原因
+fn file_info_ui(ui: &mut egui::Ui, cache: &mut Option<FileInfoCache>, entry: &DefEntry) {
+    let stale = cache
+        .as_ref()
+        .is_none_or(|c| c.path != entry.file_path || c.modified_at != entry.modified_at);
+    if stale {
+        *cache = Some(FileInfoCache {
+            path: entry.file_path.clone(),
+            modified_at: entry.modified_at,
+            info: detect_encoding_info(&entry.file_path).map_err(|e| e.to_string()),
+        });
+    }
+    let Some(cache) = cache else {
+        return;
+    };
+    match &cache.info {
+        Ok(info) => {
+            egui::Grid::new("def_file_info_grid").show(ui, |ui| {
+                ui.label("大小:");
+                ui.label(format!("{} 位元組", info.size));
+                ui.end_row();
+                ui.label("編碼:");
+                ui.label(info.encoding.label());
+                ui.end_row();
+                ui.label("換行:");
+                ui.label(info.line_ending.label());
+                ui.end_row();
+                ui.label("字元數:");
+                ui.label(info.char_count.to_string());
+                ui.end_row();
+            });
+        }
+        Err(e) => {
+            ui.colored_label(egui::Color32::from_rgb(255, 80, 80), format!("無法讀取檔案: {}", e));
+        }
+    }
+}
+
+/// 變更記錄視窗內容；`export_message` 為上次匯出的結果
+fn changes_ui(
+    ui: &mut egui::Ui,
+    changes: &DefChangeset,
+    exporter: &Exporter,
+    export_message: &mut Option<String>,
+) {
+    ui.label(format!(
+        "新增 {}，移除 {}，修改 {}",
+        changes.added.len(),
+        changes.removed.len(),
+        changes.modified.len()
+    ));
+    ui.horizontal(|ui| {
+        if ui.button("📋 複製 Markdown").clicked() {
+            ui.output_mut(|o| o.copied_text = changes.to_markdown());
+        }
+        if ui.button("💾 匯出 Markdown").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Markdown", &["md"])
+                .set_file_name("changelog.md")
+                .save_file()
+            {
+                *export_message = Some(exporter.export("變更記錄", &path, changes.to_markdown().into_bytes()));
+            }
+        }
+    });
+    if let Some(message) = export_message {
+        ui.label(message.as_str());
+    }
+    ui.separator();
+
+    if changes.is_empty() {
+        ui.label("沒有任何變更");
+        return;
+    }
+
+    egui::ScrollArea::vertical()
+        .id_salt("changes_list")
+        .show(ui, |ui| {
+            for (heading, names, color) in [
+                ("➕ 新增", &changes.added, egui::Color32::from_rgb(0, 200, 0)),
+                ("➖ 移除", &changes.removed, egui::Color32::from_rgb(255, 80, 80)),
+                ("✏ 修改", &changes.modified, egui::Color32::from_rgb(255, 200, 0)),
+            ] {
+                if names.is_empty() {
+                    continue;
+                }
+                egui::CollapsingHeader::new(
+                    egui::RichText::new(format!("{} ({})", heading, names.len())).color(color),
+                )
+                .id_salt(heading)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for name in names {
+                        ui.label(name);
+                    }
+                });
+            }
+        });
+}
+
+/// PatchOperation 的詳細資訊；回傳從 XML 中的 defName 參照前往的 Def
+fn patch_detail_ui(
+    ui: &mut egui::Ui,
+    patch: &PatchEntry,
+    db: &SharedDefDatabase,
+    settings: &Mutex<AppSettings>,
+    popup: &mut Option<DefReference>,
+) -> Option<(Symbol, usize)> {
+    ui.label(format!("操作: {}", patch.operation));
+    if let Some(parent) = &patch.parent {
+        ui.label(format!("位於: {}", parent));
+    }
+    if let Some(xpath) = &patch.xpath {
+        ui.horizontal(|ui| {
+            ui.label("xpath:");
+            ui.monospace(xpath);
+            if ui.small_button("📋").on_hover_text("複製 xpath").clicked() {
+                ui.output_mut(|o| o.copied_text = xpath.clone());
+            }
+        });
+    }
+    if !patch.mods.is_empty() {
+        ui.label(format!("模組: {}", patch.mods.join(", ")));
+    }
+    ui.horizontal(|ui| {
+        ui.label("檔案: ");
+        if ui
+            .link(patch.file_path.display().to_string())
+            .on_hover_text(format!("開啟檔案（第 {} 行）", patch.start_line))
+            .clicked()
+        {
+            open_file_at_line(settings, &patch.file_path, patch.start_line);
+        }
+        if ui.small_button("📂").on_hover_text("在檔案總管中顯示").clicked() {
+            reveal_in_file_manager(&patch.file_path);
+        }
+    });
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("XML:");
+        if ui.button("📋 複製 XML").clicked() {
+            ui.output_mut(|o| o.copied_text = patch.xml_content.clone());
+        }
+    });
+    egui::ScrollArea::both()
+        .id_salt("patch_xml_content")
+        .max_height(400.0)
+        .show(ui, |ui| xml_with_references(ui, &patch.xml_content, "", db, popup, None))
+        .inner
+}
+
+/// 欄位設定與匯出；匯出時一併保存欄位到設置
+fn csv_export_ui(
+    ui: &mut egui::Ui,
+    dialog: &mut CsvExportDialog,
+    db: &SharedDefDatabase,
+    settings: &Mutex<AppSettings>,
+    exporter: &Exporter,
+) {
+    let entries = db.entries.get(&dialog.def_type).map(Vec::as_slice).unwrap_or_default();
+    ui.label(format!("{} 個條目，每個一列；defName 與檔案路徑之後依序為以下欄位", entries.len()));
+    ui.label("每行一個標籤路徑，以 / 分隔子標籤（例如 statBases/MarketValue）:");
+    ui.add(
+        egui::TextEdit::multiline(&mut dialog.columns)
+            .code_editor()
+            .desired_rows(6)
+            .desired_width(f32::INFINITY),
+    );
+    ui.weak("只包含 Def 本身寫出的值（不含繼承）；列表以 ; 串接，找不到的標籤為空白");
+
+    if ui.button("💾 匯出").clicked() {
+        let columns: Vec<String> = dialog
+            .columns
+            .lines()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(String::from)
+            .collect();
+        if let Ok(mut settings) = settings.lock() {
+            settings.csv_columns = columns.clone();
+            settings.save();
+        }
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.csv", dialog.def_type))
+            .save_file()
+        {
+            let csv = entries_to_csv(entries, &columns);
+            dialog.message = Some(exporter.export("Def 瀏覽器", &path, csv.into_bytes()));
+        }
+    }
+    if let Some(message) = &dialog.message {
+        ui.label(message.as_str());
+    }
+}
+
+/// 條目的貼圖；貼圖檔的尋找與解碼都在背景執行緒進行
+fn entry_texture(textures: &TextureLoader, entry: &DefEntry) -> TextureState {
+    let Some(tex_path) = entry.tex_path.clone() else {
+        return TextureState::Failed(String::new());
+    };
+    let def_file = entry.file_path.clone();
+    let key = format!("{}|{}", def_file.display(), tex_path);
+    textures.get(&key, move || {
+        let path = find_texture(&def_file, &tex_path).ok_or_else(|| {
+            let candidates = texture_candidates(&def_file, &tex_path);
+            if candidates.is_empty() {
+                format!("找不到貼圖 {}：上層目錄中沒有 Textures 資料夾（本體貼圖位於資源檔中）", tex_path)
+            } else {
+                let tried: Vec<String> = candidates.iter().map(|p| p.display().to_string()).collect();
+                format!("找不到貼圖 {}，已嘗試:\n{}", tex_path, tried.join("\n"))
+            }
+        })?;
+        decode_image(&path)
+    })
+}
+
+/// 超過 `max` 個字元時截斷並加上省略號
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn contains_ignore_case(text: &str, pattern: &str) -> bool {
+    pattern.is_empty() || text.to_lowercase().contains(&pattern.to_lowercase())
 }
 
 impl DefBrowserTab {
-    pub fn new(settings: Arc<Mutex<AppSettings>>) -> Self {
+    pub fn new(
+        settings: Arc<Mutex<AppSettings>>,
+        database: SharedDatabase,
+        scan_request: ScanRequest,
+        popouts: Popouts,
+        textures: TextureLoader,
+        exporter: Exporter,
+    ) -> Self {
+        let recently_viewed = settings
+            .lock()
+            .map(|s| s.recent_defs.iter().take(RECENT_LIMIT).cloned().collect())
+            .unwrap_or_default();
+        let bookmarks = settings.lock().map(|s| s.bookmarks.clone()).unwrap_or_default();
+        let entry_sorts = settings.lock().map(|s| s.entry_sorts.clone()).unwrap_or_default();
         Self {
             base_directory: String::new(),
-            defs: BTreeMap::new(),
+            database,
+            last_generation: 0,
             selected_def_type: None,
             selected_def_entry: None,
-            is_loading: false,
-            status_message: String::new(),
+            selected_patch: None,
+            scan_request,
             settings,
             initialized: false,
-            search_query: String::new(),
-            auto_scanned: false,
+            filter: BrowserFilter::default(),
+            type_sort: TypeSort::default(),
+            grouping: BrowserGrouping::default(),
+            xml_view: XmlView::default(),
+            xml_tree: None,
+            xml_table_sort: PathSort::default(),
+            selected_mod: None,
+            mod_abouts: HashMap::new(),
+            selected_entries: HashSet::new(),
+            selection_anchor: None,
+            list_nav: ListNavigation::default(),
+            pending_select_all: None,
+            new_preset_name: String::new(),
+            pending_restore: None,
+            popouts,
+            show_changes: false,
+            changes_export: None,
+            csv_export: None,
+            file_info: None,
+            reference_popup: None,
+            xml_find: FindBar::default(),
+            exporter,
+            textures,
+            recently_viewed,
+            bookmarks,
+            entry_sorts,
+            recorded_selection: None,
+            recent_changed_at: None,
+            global_query: String::new(),
+            global_search: None,
+            showing_global: false,
+        }
+    }
+
+    /// 保存介面狀態（選取的條目與篩選條件）
+    pub fn save_state(&self) -> BrowserUiState {
+        let selected = self
+            .selected_def_type
+            .as_ref()
+            .and_then(|def_type| {
+                let db = self.database.read().ok()?;
+                let entry = db.entries.get(def_type)?.get(self.selected_def_entry?)?;
+                Some((def_type.to_string(), entry.def_name.to_string()))
+            })
+            .or_else(|| self.pending_restore.clone());
+
+        BrowserUiState {
+            selected_def_type: selected.as_ref().map(|(def_type, _)| def_type.clone()),
+            selected_def_name: selected.map(|(_, def_name)| def_name),
+            search_query: self.filter.search_query.clone(),
+            def_type_pattern: self.filter.def_type_pattern.clone(),
+            path_pattern: self.filter.path_pattern.clone(),
+            tag_path: self.filter.tag_path.clone(),
+            abstract_filter: self.filter.abstract_filter,
+            fuzzy: self.filter.fuzzy,
+            type_sort: self.type_sort,
+            grouping: self.grouping,
+            xml_view: self.xml_view,
+        }
+    }
+
+    /// 還原介面狀態；選取的條目在下一次掃描完成後才還原，不存在時忽略
+    pub fn load_state(&mut self, state: BrowserUiState) {
+        self.filter = BrowserFilter {
+            search_query: state.search_query,
+            def_type_pattern: state.def_type_pattern,
+            path_pattern: state.path_pattern,
+            tag_path: state.tag_path,
+            abstract_filter: state.abstract_filter,
+            fuzzy: state.fuzzy,
+        };
+        self.type_sort = state.type_sort;
+        self.grouping = state.grouping;
+        self.xml_view = state.xml_view;
+        self.pending_restore = state.selected_def_type.zip(state.selected_def_name);
+    }
+
+    /// 選取指定的 Def（快速搜尋、還原狀態）；不存在時忽略
+    pub fn select_def(&mut self, def_type: &str, def_name: &str) {
+        let database = self.database.clone();
+        let Ok(db) = database.read() else {
+            return;
+        };
+        // 分頁尚未看到最新掃描時，避免下一幀因版本變更而清除選擇
+        self.last_generation = db.generation;
+        // 名稱索引只記錄同名中的第一個，類型不同時才逐一尋找
+        let found = match db.name_index.get(def_name) {
+            Some((key, index)) if key.as_str() == def_type => Some((key.clone(), *index)),
+            _ => db.entries.get_key_value(def_type).and_then(|(key, entries)| {
+                let index = entries.iter().position(|e| e.def_name == def_name)?;
+                Some((key.clone(), index))
+            }),
+        };
+        if let Some((key, index)) = found {
+            self.selected_def_type = Some(key);
+            self.selected_def_entry = Some(index);
+        }
+    }
+
+    /// 選取的條目變更時加入最近檢視並儲存
+    fn record_recent(&mut self) {
+        let current = self.selected_def_type.clone().zip(self.selected_def_entry);
+        if current == self.recorded_selection {
+            return;
+        }
+        self.recorded_selection = current.clone();
+        let Some((def_type, idx)) = current else {
+            return;
+        };
+        let recent = {
+            let Ok(db) = self.database.read() else {
+                return;
+            };
+            let Some(entry) = db.entries.get(&def_type).and_then(|e| e.get(idx)) else {
+                return;
+            };
+            RecentDefEntry {
+                def_type: def_type.to_string(),
+                def_name: entry.def_name.to_string(),
+                file_path: entry.file_path.clone(),
+            }
+        };
+
+        self.recently_viewed.retain(|r| r != &recent);
+        self.recently_viewed.push_front(recent);
+        self.recently_viewed.truncate(RECENT_LIMIT);
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.recent_defs = self.recently_viewed.iter().cloned().collect();
+        }
+        self.recent_changed_at = Some(Instant::now());
+    }
+
+    /// 每幀呼叫：最近檢視停止變更一段時間後才寫入設置檔
+    pub fn save_recent_when_idle(&mut self, ctx: &egui::Context) {
+        let Some(changed_at) = self.recent_changed_at else {
+            return;
+        };
+        let elapsed = changed_at.elapsed();
+        if elapsed < RECENT_SAVE_DELAY {
+            ctx.request_repaint_after(RECENT_SAVE_DELAY - elapsed);
+            return;
+        }
+        self.flush_recent();
+    }
+
+    /// 立即寫入尚未儲存的最近檢視（結束程式時）
+    pub fn flush_recent(&mut self) {
+        if self.recent_changed_at.take().is_none() {
+            return;
+        }
+        if let Ok(settings) = self.settings.lock() {
+            settings.save();
+        }
+    }
+
+    fn is_bookmarked(&self, def_type: &str, def_name: &str) -> bool {
+        self.bookmarks.iter().any(|b| b.def_type == def_type && b.def_name == def_name)
+    }
+
+    /// 加入或移除收藏並儲存
+    fn toggle_bookmark(&mut self, def_type: &str, def_name: &str) {
+        if self.is_bookmarked(def_type, def_name) {
+            self.bookmarks.retain(|b| !(b.def_type == def_type && b.def_name == def_name));
+        } else {
+            self.bookmarks.push(Bookmark {
+                def_type: def_type.to_string(),
+                def_name: def_name.to_string(),
+            });
+        }
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.bookmarks = self.bookmarks.clone();
+            settings.save();
+        }
+    }
+
+    fn set_entry_sort(&mut self, def_type: &Symbol, sort: EntrySort) {
+        if sort == EntrySort::DefName {
+            self.entry_sorts.remove(def_type.as_str());
+        } else {
+            self.entry_sorts.insert(def_type.to_string(), sort);
+        }
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.entry_sorts = self.entry_sorts.clone();
+            settings.save();
+        }
+    }
+
+    /// 「⭐ 收藏」區塊；目前的掃描結果中找不到的收藏以灰色顯示，可手動移除
+    fn bookmarks_ui(&mut self, ui: &mut egui::Ui, db: &SharedDefDatabase) {
+        let mut removed = None;
+        egui::CollapsingHeader::new(format!("⭐ 收藏 ({})", self.bookmarks.len()))
+            .id_salt("browser_bookmarks")
+            .default_open(true)
+            .show(ui, |ui| {
+                for bookmark in &self.bookmarks {
+                    let found = db.entries.get_key_value(bookmark.def_type.as_str()).and_then(|(key, entries)| {
+                        let idx = entries.iter().position(|e| e.def_name == bookmark.def_name)?;
+                        Some((key.clone(), idx))
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✕").on_hover_text("移除收藏").clicked() {
+                            removed = Some(bookmark.clone());
+                        }
+                        let label = format!("[{}] {}", bookmark.def_type, bookmark.def_name);
+                        let response = ui
+                            .add_enabled(found.is_some(), egui::SelectableLabel::new(false, label))
+                            .on_disabled_hover_text("目前的掃描結果中沒有此 Def");
+                        if response.clicked() {
+                            if let Some((def_type, idx)) = found {
+                                self.selected_def_type = Some(def_type);
+                                self.selected_def_entry = Some(idx);
+                            }
+                        }
+                    });
+                }
+            });
+        if let Some(bookmark) = removed {
+            self.toggle_bookmark(&bookmark.def_type, &bookmark.def_name);
         }
     }
 
     pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
         // 每次更新時檢查設置是否變更
         if let Ok(settings) = self.settings.lock() {
-            if settings.base_path != self.base_directory {
-                self.base_directory = settings.base_path.clone();
+            let base_path = settings.active_base_path();
+            if base_path != self.base_directory {
+                self.base_directory = base_path;
                 self.initialized = true;
-                self.auto_scanned = false;  // 重置自動掃描標記
             }
         }
 
-        // 首次進入且有目錄時自動掃描
-        if !self.auto_scanned && !self.base_directory.is_empty() && self.defs.is_empty() {
-            self.auto_scanned = true;
-            self.scan_defs();
+        // 選取了 Def（列表、參照連結、收藏等）時不再顯示 PatchOperation
+        if self.selected_def_entry.is_some() {
+            self.selected_patch = None;
+        }
+
+        // 重新掃描完成後，舊的選擇已失效
+        let (generation, refreshing, has_changes) = self
+            .database
+            .read()
+            .map_or((0, false, false), |db| {
+                (db.generation, db.refreshing, db.changes.is_some())
+            });
+        if generation != self.last_generation {
+            self.last_generation = generation;
+            self.selected_def_type = None;
+            self.selected_def_entry = None;
+            self.selected_patch = None;
+            self.selected_entries.clear();
+            self.selection_anchor = None;
+            self.pending_select_all = None;
+            self.selected_mod = None;
+            self.mod_abouts.clear();
+
+            if let Some((def_type, def_name)) = self.pending_restore.take() {
+                self.select_def(&def_type, &def_name);
+            }
+            // 條目索引已變更，以相同的查詢重新搜尋
+            if let Some(query) = self.global_search.as_ref().map(|g| g.query.clone()) {
+                self.run_global_search(&query);
+            }
+        }
+        self.record_recent();
+
+        // Esc：結果列表回到類型樹；檢視結果時回到結果列表
+        if self.global_search.is_some()
+            && !ui.ctx().wants_keyboard_input()
+            && ui.input(|i| i.key_pressed(egui::Key::Escape))
+        {
+            self.showing_global = !self.showing_global;
         }
 
         // 頂部控制面板
@@ -67,32 +796,80 @@ impl DefBrowserTab {
             ui.label("目錄:");
             ui.add_enabled(false, egui::TextEdit::singleline(&mut self.base_directory));
 
-            if ui.button("🔄 掃描 Defs").clicked() && !self.base_directory.is_empty() {
+            if ui
+                .button("🔄 掃描 Defs")
+                .on_hover_text(if refreshing {
+                    "取消進行中的掃描並重新開始"
+                } else {
+                    "重新掃描目錄 (F5)"
+                })
+                .clicked()
+                && !self.base_directory.is_empty()
+            {
                 self.scan_defs();
             }
+            if ui
+                .button("🗑 清除快取")
+                .on_hover_text("刪除掃描快取並重新解析所有檔案（快取的結果看起來不正確時使用）")
+                .clicked()
+                && !self.base_directory.is_empty()
+            {
+                self.scan_request.request_without_cache();
+            }
+
+            if ui
+                .add_enabled(has_changes, egui::Button::new("📋 變更記錄"))
+                .on_hover_text("與同一目錄上一次掃描相比新增、移除、修改的 Defs")
+                .on_disabled_hover_text("重新掃描同一目錄後才會有變更記錄")
+                .clicked()
+            {
+                self.show_changes = true;
+            }
+
+            if let Some(def_type) = &self.selected_def_type {
+                if ui
+                    .button("📊 匯出 CSV")
+                    .on_hover_text(format!("將所有 {} 匯出為 CSV，欄位可自訂", def_type))
+                    .clicked()
+                {
+                    let columns = self.settings.lock().map(|s| s.csv_columns.join("\n")).unwrap_or_default();
+                    self.csv_export = Some(CsvExportDialog {
+                        def_type: def_type.clone(),
+                        columns,
+                        message: None,
+                    });
+                }
+            }
 
-            // 狀態訊息
-            if !self.status_message.is_empty() {
-                ui.colored_label(
-                    if self.is_loading {
-                        egui::Color32::from_rgb(255, 165, 0)
-                    } else {
-                        egui::Color32::from_rgb(0, 200, 0)
-                    },
-                    &self.status_message,
-                );
+            // 背景掃描時仍顯示舊資料
+            if refreshing {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "🔄 更新中…");
             }
         });
 
         ui.separator();
 
+        if generation == 0 {
+            if refreshing {
+                loading_skeleton(ui, LIST_WIDTH);
+            } else {
+                empty_state(ui, &self.settings, refreshing);
+            }
+            return;
+        }
+
+        let database = self.database.clone();
+        let Ok(db) = database.read() else {
+            return;
+        };
+
         // 主要內容區域：左側列表右側詳細資訊
         ui.horizontal_top(|ui| {
             // 左側面板
             let width = if ui.available_width() < 400.0 {
                 200.0
             } else {
-                220.0
+                LIST_WIDTH
             };
             ui.allocate_ui_with_layout(
                 egui::vec2(width, ui.available_height()),
@@ -100,93 +877,247 @@ impl DefBrowserTab {
                 |ui| {
                     ui.horizontal(|ui| {
                         ui.label("🔍");
-                        let response = ui.text_edit_singleline(&mut self.search_query);
-                        if response.changed() {
+                        let response = ui.text_edit_singleline(&mut self.filter.search_query);
+                        let fuzzy = ui
+                            .toggle_value(&mut self.filter.fuzzy, "模糊")
+                            .on_hover_text("依序包含所有字元即相符（例如 gnts 相符 Gun_Test），結果依相符程度排序");
+                        if response.changed() || fuzzy.changed() {
                             self.selected_def_type = None;
                             self.selected_def_entry = None;
                         }
                     });
+
+                    ui.horizontal(|ui| {
+                        ui.label("🌐");
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.global_query)
+                                .hint_text("全域搜尋（Enter）"),
+                        );
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            let query = self.global_query.clone();
+                            self.run_global_search(&query);
+                        }
+                    });
+
+                    egui::CollapsingHeader::new("篩選")
+                        .id_salt("browser_filters")
+                        .show(ui, |ui| {
+                            egui::Grid::new("browser_filter_grid").num_columns(2).show(ui, |ui| {
+                                ui.label("類型:");
+                                ui.text_edit_singleline(&mut self.filter.def_type_pattern);
+                                ui.end_row();
+
+                                ui.label("路徑:");
+                                ui.text_edit_singleline(&mut self.filter.path_pattern);
+                                ui.end_row();
+
+                                ui.label("包含標籤:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.filter.tag_path)
+                                        .hint_text("例如 comps 或 statBases/Mass"),
+                                )
+                                .on_hover_text("只顯示含有此元素的 Defs；可用 / 分隔路徑，以 / 開頭時從 Def 的直屬元素比對");
+                                ui.end_row();
+
+                                ui.label("抽象:");
+                                egui::ComboBox::from_id_salt("browser_abstract_filter")
+                                    .selected_text(self.filter.abstract_filter.label())
+                                    .show_ui(ui, |ui| {
+                                        for option in AbstractFilter::ALL {
+                                            ui.selectable_value(
+                                                &mut self.filter.abstract_filter,
+                                                option,
+                                                option.label(),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+
+                                ui.label("類型排序:");
+                                egui::ComboBox::from_id_salt("browser_type_sort")
+                                    .selected_text(self.type_sort.label())
+                                    .show_ui(ui, |ui| {
+                                        for option in TypeSort::ALL {
+                                            ui.selectable_value(&mut self.type_sort, option, option.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("依名稱，或依（符合篩選的）條目數量由多到少排序");
+                                ui.end_row();
+
+                                ui.label("分組:");
+                                egui::ComboBox::from_id_salt("browser_grouping")
+                                    .selected_text(self.grouping.label())
+                                    .show_ui(ui, |ui| {
+                                        for option in BrowserGrouping::ALL {
+                                            ui.selectable_value(&mut self.grouping, option, option.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("依模組分組時，模組名稱取自 About/About.xml 的 <name>，沒有時使用資料夾名稱");
+                                ui.end_row();
+                            });
+
+                            self.quick_filter_ui(ui);
+                        });
+
+                    // 多選操作
+                    ui.horizontal_wrapped(|ui| {
+                        let select_all_label = if self.filter.is_active() {
+                            "☑ 全選篩選結果"
+                        } else {
+                            "☑ 全選全部"
+                        };
+                        if ui
+                            .button(select_all_label)
+                            .on_hover_text("選取目前篩選條件下的所有條目；沒有篩選時選取全部")
+                            .clicked()
+                        {
+                            let filter = &self.filter;
+                            let matching: Vec<(Symbol, usize)> = db
+                                .entries
+                                .iter()
+                                .flat_map(|(def_type, entries)| {
+                                    entries
+                                        .iter()
+                                        .enumerate()
+                                        .filter(move |(_, e)| filter.matches(def_type, e))
+                                        .map(move |(idx, _)| (def_type.clone(), idx))
+                                })
+                                .collect();
+                            if matching.len() > SELECT_ALL_CONFIRM_THRESHOLD {
+                                self.pending_select_all = Some(matching);
+                            } else {
+                                self.selected_entries = matching.into_iter().collect();
+                            }
+                        }
+
+                        if !self.selected_entries.is_empty() {
+                            ui.label(format!("已選取 {} 個", self.selected_entries.len()));
+                            if ui
+                                .button("📋 複製選取的 XML")
+                                .on_hover_text("包在同一個 <Defs> 中，每個 Def 前以註解標示來源檔案")
+                                .clicked()
+                            {
+                                let xml = selected_xml(&db, &self.selected_entries);
+                                ui.output_mut(|o| o.copied_text = xml);
+                            }
+                            if ui.button("✖ 清除選取").clicked() {
+                                self.selected_entries.clear();
+                            }
+                        }
+                    });
                     ui.separator();
 
-                    egui::ScrollArea::vertical()
+                    if self.showing_global {
+                        self.global_results_ui(ui, &db);
+                        return;
+                    }
+                    if let Some(search) = &self.global_search {
+                        if ui
+                            .small_button(format!("← 返回「{}」的搜尋結果 (Esc)", search.query))
+                            .clicked()
+                        {
+                            self.showing_global = true;
+                        }
+                    }
+
+                    for key in self.list_nav.begin(ui.ctx()) {
+                        self.handle_list_key(key, &db);
+                    }
+                    let list = egui::ScrollArea::vertical()
                         .id_salt("def_type_list")
                         .auto_shrink([false; 2])
                         .show(ui, |ui| {
-                            for (def_type, entries) in &self.defs {
-                                // 檢查 Def 類型或條目名稱是否符合搜索
-                                let type_matches = def_type.to_lowercase().contains(&self.search_query.to_lowercase());
-                                let has_matching_entries = if self.search_query.is_empty() {
-                                    true
-                                } else {
-                                    type_matches || entries.iter().any(|e| 
-                                        e.def_name.to_lowercase().contains(&self.search_query.to_lowercase())
-                                    )
-                                };
-
-                                // 只顯示有符合搜索條目的 Def 類型
-                                if !has_matching_entries {
-                                    continue;
-                                }
-
-                                let is_selected = self.selected_def_type.as_ref() == Some(def_type);
-                                
-                                // 計算要顯示的條目數量
-                                let entry_count = if self.search_query.is_empty() || type_matches {
-                                    entries.len()
-                                } else {
-                                    entries.iter().filter(|e| 
-                                        e.def_name.to_lowercase().contains(&self.search_query.to_lowercase())
-                                    ).count()
-                                };
-
-                                if ui
-                                    .selectable_label(is_selected, format!("{} ({})", def_type, entry_count))
-                                    .clicked()
-                                {
-                                    if is_selected {
-                                        // 點擊已選擇的類型，收起
-                                        self.selected_def_type = None;
-                                        self.selected_def_entry = None;
-                                    } else {
-                                        // 選擇新類型
-                                        self.selected_def_type = Some(def_type.clone());
-                                        self.selected_def_entry = None;
-                                    }
-                                }
+                            if !db.duplicates.is_empty() {
+                                self.duplicates_ui(ui, &db);
+                                ui.separator();
+                            }
 
-                                // 如果此類型被選中，顯示其下的所有條目
-                                if is_selected {
-                                    ui.indent(format!("indent_{}", def_type), |ui| {
-                                        for (idx, entry) in entries.iter().enumerate() {
-                                            // 如果 Def 類型本身符合搜索，顯示所有條目；否則只顯示符合搜索的條目
-                                            if !self.search_query.is_empty() 
-                                                && !type_matches
-                                                && !entry.def_name.to_lowercase().contains(&self.search_query.to_lowercase()) {
-                                                continue;
-                                            }
+                            if !self.bookmarks.is_empty() {
+                                self.bookmarks_ui(ui, &db);
+                                ui.separator();
+                            }
 
-                                            let entry_selected =
-                                                self.selected_def_entry == Some(idx);
-                                            if ui
-                                                .selectable_label(
-                                                    entry_selected,
-                                                    format!("  {}", entry.def_name),
+                            if !self.recently_viewed.is_empty() {
+                                egui::CollapsingHeader::new("🕐 最近檢視")
+                                    .id_salt("browser_recent")
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        for recent in self.recently_viewed.iter().take(RECENT_SHOWN) {
+                                            // 同名的 Def 可能出現在多個檔案，以來源檔案區分
+                                            let found = db
+                                                .entries
+                                                .get_key_value(recent.def_type.as_str())
+                                                .and_then(|(key, entries)| {
+                                                    let idx = entries.iter().position(|e| {
+                                                        e.def_name == recent.def_name
+                                                            && e.file_path == recent.file_path
+                                                    })?;
+                                                    Some((key.clone(), idx))
+                                                });
+                                            let label = format!("[{}] {}", recent.def_type, recent.def_name);
+                                            let response = ui
+                                                .add_enabled(
+                                                    found.is_some(),
+                                                    egui::SelectableLabel::new(false, label),
                                                 )
-                                                .clicked()
-                                            {
-                                                self.selected_def_entry = Some(idx);
+                                                .on_hover_text(recent.file_path.display().to_string())
+                                                .on_disabled_hover_text("目前的掃描結果中沒有此 Def");
+                                            if response.clicked() {
+                                                if let Some((def_type, idx)) = found {
+                                                    self.selected_def_type = Some(def_type);
+                                                    self.selected_def_entry = Some(idx);
+                                                }
                                             }
                                         }
                                     });
+                                ui.separator();
+                            }
+
+                            // 只顯示有符合篩選條目的 Def 類型
+                            let mut types: Vec<TypeGroup> = db
+                                .entries
+                                .iter()
+                                .filter_map(|(def_type, entries)| {
+                                    let matching: Vec<usize> = entries
+                                        .iter()
+                                        .enumerate()
+                                        .filter(|(_, e)| self.filter.matches(def_type, e))
+                                        .map(|(idx, _)| idx)
+                                        .collect();
+                                    (!matching.is_empty()).then_some((def_type, entries, matching))
+                                })
+                                .collect();
+                            if self.type_sort == TypeSort::Count {
+                                // 穩定排序，數量相同時保留名稱順序
+                                types.sort_by_key(|(_, _, matching)| std::cmp::Reverse(matching.len()));
+                            }
+
+                            match self.grouping {
+                                BrowserGrouping::Type => {
+                                    for (def_type, entries, matching) in types {
+                                        self.type_group_ui(ui, None, def_type, entries, matching);
+                                    }
                                 }
+                                BrowserGrouping::Mod => self.mod_groups_ui(ui, types),
+                            }
+
+                            if !db.patches.is_empty() {
+                                ui.separator();
+                                self.patches_ui(ui, &db);
                             }
                         });
+                    self.list_nav.set_rect(list.inner_rect);
+                    self.list_nav.scroll = false;
                 },
             );
 
             ui.separator();
 
             // 右側面板
+            let mut navigate_to = None; // 從 XML 中的 defName 參照前往的 Def
+            let mut toggled_bookmark: Option<(String, String)> = None;
             ui.allocate_ui_with_layout(
                 egui::vec2(ui.available_width(), ui.available_height()),
                 egui::Layout::top_down(egui::Align::Min),
@@ -197,40 +1128,248 @@ impl DefBrowserTab {
                     egui::ScrollArea::vertical()
                         .id_salt("def_detail_main")
                         .show(ui, |ui| {
-                            if let Some(def_type) = &self.selected_def_type {
+                            let selected_patch = self
+                                .selected_patch
+                                .as_ref()
+                                .and_then(|(operation, idx)| db.patches.get(operation)?.get(*idx));
+                            if let Some(patch) = selected_patch {
+                                navigate_to = patch_detail_ui(ui, patch, &db, &self.settings, &mut self.reference_popup);
+                            } else if let Some(def_type) = &self.selected_def_type {
                                 if let Some(entry_idx) = self.selected_def_entry {
-                                    if let Some(entries) = self.defs.get(def_type) {
+                                    if let Some(entries) = db.entries.get(def_type) {
                                         if let Some(entry) = entries.get(entry_idx) {
-                                            ui.label(format!("DefName: {}", entry.def_name));
+                                            ui.horizontal(|ui| {
+                                                ui.label(format!("DefName: {}", entry.def_name));
+                                                let bookmarked = self.is_bookmarked(def_type.as_str(), &entry.def_name);
+                                                let (icon, hint) = if bookmarked {
+                                                    ("⭐", "移除收藏")
+                                                } else {
+                                                    ("☆", "加入收藏")
+                                                };
+                                                if ui.small_button(icon).on_hover_text(hint).clicked() {
+                                                    toggled_bookmark = Some((def_type.to_string(), entry.def_name.to_string()));
+                                                }
+                                            });
                                             ui.label(format!("類型: {}", entry.def_type));
+                                            if entry.name.is_some() || entry.parent_name.is_some() || entry.is_abstract {
+                                                ui.horizontal_wrapped(|ui| {
+                                                    if let Some(name) = &entry.name {
+                                                        ui.label(format!("Name: {}", name));
+                                                    }
+                                                    if let Some(parent) = entry.parent_name.as_deref() {
+                                                        ui.label("ParentName:");
+                                                        // 父類一定是同一類型中帶有此 Name 屬性的 Def
+                                                        match entries.iter().position(|e| e.name.as_deref() == Some(parent)) {
+                                                            Some(index) => {
+                                                                let parent_entry = &entries[index];
+                                                                let link = ui.link(parent).on_hover_text("前往父類").clicked();
+                                                                let button = ui.small_button("⬆ 前往父類").clicked();
+                                                                if link || button {
+                                                                    navigate_to = Some((def_type.clone(), index));
+                                                                }
+                                                                if !self.filter.matches(def_type.as_str(), parent_entry) {
+                                                                    let note = if parent_entry.is_abstract {
+                                                                        "（抽象，目前的篩選未列出）"
+                                                                    } else {
+                                                                        "（目前的篩選未列出）"
+                                                                    };
+                                                                    ui.weak(note);
+                                                                }
+                                                            }
+                                                            // 掃描範圍外或被忽略的類型仍會讀入繼承資料
+                                                            None if db.data.contains_key(parent) => {
+                                                                ui.label(parent);
+                                                                ui.weak("（不在瀏覽器的掃描範圍內）");
+                                                            }
+                                                            None => {
+                                                                ui.colored_label(ui.visuals().error_fg_color, parent)
+                                                                    .on_hover_text("找不到此父類，可能是 ParentName 拼錯或缺少所需的模組");
+                                                            }
+                                                        }
+                                                    }
+                                                    if entry.is_abstract {
+                                                        ui.label("Abstract: True");
+                                                    }
+                                                });
+                                            }
 
                                             // 可點擊的檔案路徑
                                             ui.horizontal(|ui| {
                                                 ui.label("檔案: ");
                                                 if ui
                                                     .link(entry.file_path.display().to_string())
+                                                    .on_hover_text(format!("開啟檔案（第 {} 行）", entry.start_line))
+                                                    .clicked()
+                                                {
+                                                    open_file_at_line(&self.settings, &entry.file_path, entry.start_line);
+                                                }
+                                                if ui
+                                                    .small_button("📂")
+                                                    .on_hover_text("在檔案總管中顯示（可查看同模組的 Textures、About.xml 等）")
                                                     .clicked()
                                                 {
-                                                    open_file_with_default_app(&entry.file_path);
+                                                    reveal_in_file_manager(&entry.file_path);
                                                 }
                                             });
+                                            mod_about_ui(
+                                                ui,
+                                                cached_mod_about(&mut self.mod_abouts, &entry.file_path).as_ref(),
+                                                &entry.file_path,
+                                                &db.base_path,
+                                            );
+
+                                            if let Some(modified_at) = entry.modified_at {
+                                                ui.label(format!(
+                                                    "最後修改: {}",
+                                                    DateTime::<Local>::from(modified_at)
+                                                        .format("%Y-%m-%d %H:%M:%S")
+                                                ));
+                                            }
+
+                                            egui::CollapsingHeader::new("🗎 檔案資訊")
+                                                .id_salt("def_file_info")
+                                                .show(ui, |ui| file_info_ui(ui, &mut self.file_info, entry));
+
+                                            if let Some(tex_path) = &entry.tex_path {
+                                                ui.horizontal(|ui| {
+                                                    let (rect, response) = ui.allocate_exact_size(
+                                                        egui::vec2(128.0, 128.0),
+                                                        egui::Sense::hover(),
+                                                    );
+                                                    let texture = entry_texture(&self.textures, entry);
+                                                    paint_texture(ui, rect, &texture);
+                                                    if let TextureState::Failed(error) = &texture {
+                                                        response.on_hover_text(error);
+                                                    }
+                                                    ui.vertical(|ui| {
+                                                        ui.label(format!("貼圖: {}", tex_path));
+                                                        if let TextureState::Failed(error) = &texture {
+                                                            ui.colored_label(
+                                                                egui::Color32::from_rgb(255, 200, 0),
+                                                                egui::RichText::new(error).small(),
+                                                            );
+                                                        }
+                                                    });
+                                                });
+                                            }
+
+                                            // 曲線以圖表顯示（同名 Def 只取同一檔案中的那個）
+                                            let curves = db
+                                                .data
+                                                .get(&entry.def_name)
+                                                .filter(|d| d.file_path == entry.file_path)
+                                                .map(|d| find_curves(&d.raw_nodes))
+                                                .unwrap_or_default();
+                                            if !curves.is_empty() {
+                                                egui::CollapsingHeader::new(format!(
+                                                    "📈 曲線 ({})",
+                                                    curves.len()
+                                                ))
+                                                .id_salt("def_curves")
+                                                .default_open(true)
+                                                .show(ui, |ui| {
+                                                    ui.horizontal_wrapped(|ui| {
+                                                        for (path, points) in &curves {
+                                                            ui.vertical(|ui| {
+                                                                ui.label(path);
+                                                                curve_chart(ui, points, CHART_SIZE);
+                                                            });
+                                                        }
+                                                    });
+                                                });
+                                            }
 
                                             ui.separator();
 
                                             // 顯示 XML 內容
-                                            ui.label("XML 定義:");
+                                            ui.horizontal(|ui| {
+                                                ui.label("XML 定義:");
+                                                if ui
+                                                    .button("🗗 彈出視窗")
+                                                    .on_hover_text("在獨立視窗中開啟此 XML")
+                                                    .clicked()
+                                                {
+                                                    self.popouts
+                                                        .open(&entry.def_name, &entry.xml_content);
+                                                }
+                                                if ui.button("📋 複製 XML").clicked() {
+                                                    ui.output_mut(|o| o.copied_text = entry.xml_content.clone());
+                                                }
+                                                ui.separator();
+                                                ui.selectable_value(&mut self.xml_view, XmlView::Text, "📄 文字");
+                                                ui.selectable_value(&mut self.xml_view, XmlView::Tree, "🌲 樹狀")
+                                                    .on_hover_text("可收合的元素樹；切換到其他條目前保留收合狀態");
+                                                ui.selectable_value(&mut self.xml_view, XmlView::Table, "▦ 表格")
+                                                    .on_hover_text("每個元素的完整標籤路徑與值，方便比較");
+                                            });
+                                            if self.xml_view == XmlView::Text {
+                                                self.xml_find.ui(ui, &entry.xml_content);
+                                            }
                                             egui::ScrollArea::both()
                                                 .id_salt("def_xml_content")
                                                 .max_height(400.0)
-                                                .show(ui, |ui| {
-                                                    ui.add(
-                                                        egui::TextEdit::multiline(
-                                                            &mut entry.xml_content.as_str(),
-                                                        )
-                                                        .code_editor()
-                                                        .desired_width(f32::INFINITY),
-                                                    );
+                                                .show(ui, |ui| match self.xml_view {
+                                                    XmlView::Text => {
+                                                        navigate_to = xml_with_references(
+                                                            ui,
+                                                            &entry.xml_content,
+                                                            &entry.def_name,
+                                                            &db,
+                                                            &mut self.reference_popup,
+                                                            Some(&mut self.xml_find),
+                                                        );
+                                                    }
+                                                    XmlView::Tree | XmlView::Table => {
+                                                        let key = (def_type.clone(), entry_idx);
+                                                        let cache = self.xml_tree.take().filter(|cache| {
+                                                            cache.entry == key && cache.generation == db.generation
+                                                        });
+                                                        let cache = cache.unwrap_or_else(|| {
+                                                            let nodes = parse_xml_nodes(&entry.xml_content);
+                                                            XmlTreeCache {
+                                                                entry: key,
+                                                                generation: db.generation,
+                                                                rows: flatten_nodes(&nodes),
+                                                                nodes,
+                                                            }
+                                                        });
+                                                        if self.xml_view == XmlView::Tree {
+                                                            let id = egui::Id::new(("def_xml_tree", def_type.as_str(), &entry.def_name));
+                                                            xml_tree_ui(ui, &cache.nodes, id);
+                                                        } else {
+                                                            xml_table_ui(ui, &cache.rows, &mut self.xml_table_sort);
+                                                        }
+                                                        self.xml_tree = Some(cache);
+                                                    }
                                                 });
+                                            if let Some(target) = referenced_by_ui(ui, &entry.def_name, &db) {
+                                                navigate_to = Some(target);
+                                            }
+
+                                            // 服裝的層與覆蓋部位摘要
+                                            if entry.def_type.as_str() == "ThingDef" {
+                                                let apparel = parse_apparel_info(&entry.xml_content);
+                                                if !apparel.is_empty() {
+                                                    ui.separator();
+                                                    ui.strong("👕 服裝");
+                                                    apparel_ui(ui, &apparel);
+                                                }
+                                            }
+
+                                            // 劇本的各部分依分組摘要
+                                            if entry.def_type.as_str() == "ScenarioDef" {
+                                                let parts = parse_scenario_parts(&entry.xml_content);
+                                                if !parts.is_empty() {
+                                                    ui.separator();
+                                                    egui::CollapsingHeader::new(format!(
+                                                        "📜 劇本摘要 ({})",
+                                                        parts.len()
+                                                    ))
+                                                    .id_salt("def_scenario")
+                                                    .default_open(true)
+                                                    .show(ui, |ui| scenario_ui(ui, &parts));
+                                                }
+                                            }
                                         }
                                     }
                                 } else {
@@ -242,309 +1381,707 @@ impl DefBrowserTab {
                         });
                 },
             );
+            if let Some((def_type, index)) = navigate_to {
+                self.selected_def_type = Some(def_type);
+                self.selected_def_entry = Some(index);
+            }
+            if let Some((def_type, def_name)) = toggled_bookmark {
+                self.toggle_bookmark(&def_type, &def_name);
+            }
         });
-    }
-
-    fn scan_defs(&mut self) {
-        self.is_loading = true;
-        self.status_message = "正在掃描 Defs...".to_string();
-        self.defs.clear();
-        self.selected_def_type = None;
-        self.selected_def_entry = None;
-
-        let base_path = PathBuf::from(&self.base_directory);
-
-        // 尋找所有 Defs 目錄下的 XML 檔案
-        let xml_files: Vec<PathBuf> = WalkDir::new(&base_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.path().is_file()
-                    && e.path().extension().and_then(|s| s.to_str()) == Some("xml")
-                    && e.path().to_str().map_or(false, |s| s.contains("Defs"))
-            })
-            .map(|e| e.path().to_path_buf())
-            .collect();
 
-        self.status_message = format!("找到 {} 個 XML 檔案，正在解析...", xml_files.len());
+        // 選取數量過多時先確認，避免剪貼簿塞入大量內容
+        if let Some(pending) = &self.pending_select_all {
+            let mut confirmed = false;
+            let mut cancelled = false;
+            egui::Window::new("確認全選")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("即將選取 {} 個條目，確定嗎？", pending.len()));
+                    ui.separator();
+                    for (def_type, idx) in pending.iter().take(10) {
+                        if let Some(entry) = db.entries.get(def_type).and_then(|e| e.get(*idx)) {
+                            ui.label(format!("{} ({})", entry.def_name, def_type));
+                        }
+                    }
+                    if pending.len() > 10 {
+                        ui.label(format!("…以及其他 {} 個", pending.len() - 10));
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("✅ 確定").clicked() {
+                            confirmed = true;
+                        }
+                        if ui.button("取消").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
 
-        // 使用並行處理解析檔案
-        let parsed_entries: Vec<DefEntry> = xml_files
-            .par_iter()
-            .filter_map(|path| parse_defs_from_file(path).ok())
-            .flatten()
-            .collect();
+            if confirmed {
+                if let Some(pending) = self.pending_select_all.take() {
+                    self.selected_entries = pending.into_iter().collect();
+                }
+            } else if cancelled {
+                self.pending_select_all = None;
+            }
+        }
 
-        // 按 DefType 分組
-        for entry in parsed_entries {
-            self.defs
-                .entry(entry.def_type.clone())
-                .or_insert_with(Vec::new)
-                .push(entry);
+        if self.show_changes {
+            if let Some(changes) = &db.changes {
+                let mut open = true;
+                egui::Window::new("📋 變更記錄")
+                    .open(&mut open)
+                    .default_size([400.0, 400.0])
+                    .show(ui.ctx(), |ui| {
+                        changes_ui(ui, changes, &self.exporter, &mut self.changes_export)
+                    });
+                self.show_changes = open;
+            }
         }
 
-        // 排序每個類型內的條目
-        for entries in self.defs.values_mut() {
-            entries.sort_by(|a, b| a.def_name.cmp(&b.def_name));
+        if let Some(dialog) = &mut self.csv_export {
+            let mut open = true;
+            egui::Window::new(format!("📊 匯出 {} 為 CSV", dialog.def_type))
+                .open(&mut open)
+                .default_width(360.0)
+                .show(ui.ctx(), |ui| {
+                    csv_export_ui(ui, dialog, &db, &self.settings, &self.exporter)
+                });
+            if !open {
+                self.csv_export = None;
+            }
         }
+    }
 
-        let total_defs: usize = self.defs.values().map(|v| v.len()).sum();
-        self.status_message = format!(
-            "掃描完成！找到 {} 種類型，共 {} 個 Defs",
-            self.defs.len(),
-            total_defs
-        );
-        self.is_loading = false;
+    /// 以預先建立的索引搜尋所有 Def 類型，結果取代左側的類型樹
+    fn run_global_search(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            self.global_search = None;
+            self.showing_global = false;
+            return;
+        }
+        let Ok(db) = self.database.read() else {
+            return;
+        };
+        let mut hits = db.search_index.search(query);
+        let total = hits.len();
+        hits.truncate(GLOBAL_RESULT_LIMIT);
+        self.global_search = Some(GlobalSearch {
+            query: query.to_string(),
+            hits,
+            total,
+            page: 0,
+        });
+        self.showing_global = true;
     }
-}
 
-fn parse_defs_from_file(path: &Path) -> Result<Vec<DefEntry>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
-    reader.config_mut().trim_text(true);
-
-    let mut entries = Vec::new();
-    let mut buf = Vec::new();
-    let mut current_def_type: Option<String> = None;
-    let mut current_def_name: Option<String> = None;
-    let mut def_depth = 0;
-    let mut inside_defs = false;
-    let mut inside_defname = false;
-    let mut xml_parts: Vec<String> = Vec::new();
-    let mut capturing = false;
-
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-                if name == "Defs" {
-                    inside_defs = true;
-                } else if inside_defs && def_depth == 0 && name.ends_with("Def") {
-                    // 開始一個新的 Def
-                    current_def_type = Some(name.clone());
-                    current_def_name = None;
-                    def_depth = 1;
-                    xml_parts.clear();
-                    capturing = true;
-                    
-                    // 記錄開始標籤
-                    let attrs: Vec<String> = e.attributes()
-                        .filter_map(|a| a.ok())
-                        .map(|attr| {
-                            format!("{}=\"{}\"",
-                                String::from_utf8_lossy(attr.key.as_ref()),
-                                String::from_utf8_lossy(&attr.value))
-                        })
-                        .collect();
-                    
-                    if attrs.is_empty() {
-                        xml_parts.push(format!("<{}>", name));
-                    } else {
-                        xml_parts.push(format!("<{} {}>", name, attrs.join(" ")));
+    /// 同類型中重複的 defName：點擊名稱選取第一個，點擊路徑開啟檔案
+    fn duplicates_ui(&mut self, ui: &mut egui::Ui, db: &SharedDefDatabase) {
+        egui::CollapsingHeader::new(format!("⚠ 重複 defName ({})", db.duplicates.len()))
+            .id_salt("browser_duplicates")
+            .show(ui, |ui| {
+                for duplicate in &db.duplicates {
+                    let Some(entries) = db.entries.get(&duplicate.def_type) else {
+                        continue;
+                    };
+                    let label = format!("[{}] {}", duplicate.def_type, duplicate.def_name);
+                    if ui.selectable_label(false, label).clicked() {
+                        self.selected_def_type = Some(duplicate.def_type.clone());
+                        self.selected_def_entry = duplicate.indices.first().copied();
                     }
-                } else if def_depth > 0 {
-                    if name == "defName" {
-                        inside_defname = true;
-                    }
-                    def_depth += 1;
-                    
-                    if capturing {
-                        let attrs: Vec<String> = e.attributes()
-                            .filter_map(|a| a.ok())
-                            .map(|attr| {
-                                format!("{}=\"{}\"",
-                                    String::from_utf8_lossy(attr.key.as_ref()),
-                                    String::from_utf8_lossy(&attr.value))
-                            })
-                            .collect();
-                        
-                        if attrs.is_empty() {
-                            xml_parts.push(format!("<{}>", name));
-                        } else {
-                            xml_parts.push(format!("<{} {}>", name, attrs.join(" ")));
+                    ui.indent(("duplicate", &duplicate.def_type, &duplicate.def_name), |ui| {
+                        for &idx in &duplicate.indices {
+                            let entry = &entries[idx];
+                            if ui
+                                .link(format!("{}:{}", entry.file_path.display(), entry.start_line))
+                                .on_hover_text("開啟檔案並跳到此 Def")
+                                .clicked()
+                            {
+                                open_file_at_line(&self.settings, &entry.file_path, entry.start_line);
+                            }
                         }
-                    }
+                    });
                 }
-            }
-            Ok(Event::Empty(ref e)) => {
-                if capturing && def_depth > 0 {
-                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    let attrs: Vec<String> = e.attributes()
-                        .filter_map(|a| a.ok())
-                        .map(|attr| {
-                            format!("{}=\"{}\"",
-                                String::from_utf8_lossy(attr.key.as_ref()),
-                                String::from_utf8_lossy(&attr.value))
+            });
+    }
+
+    /// Patches 資料夾中的操作，依操作類型分組；關鍵字同時比對 xpath 與操作類型
+    fn patches_ui(&mut self, ui: &mut egui::Ui, db: &SharedDefDatabase) {
+        let query = self.filter.search_query.trim();
+        let total: usize = db.patches.values().map(Vec::len).sum();
+        egui::CollapsingHeader::new(format!("🩹 Patches ({})", total))
+            .id_salt("browser_patches")
+            .show(ui, |ui| {
+                for (operation, patches) in &db.patches {
+                    let matching: Vec<usize> = (0..patches.len())
+                        .filter(|&idx| {
+                            contains_ignore_case(operation, query)
+                                || contains_ignore_case(&patches[idx].display_name(), query)
                         })
                         .collect();
-                    
-                    if attrs.is_empty() {
-                        xml_parts.push(format!("<{} />", name));
-                    } else {
-                        xml_parts.push(format!("<{} {} />", name, attrs.join(" ")));
+                    if matching.is_empty() {
+                        continue;
                     }
+                    egui::CollapsingHeader::new(format!("{} ({})", operation, matching.len()))
+                        .id_salt(("browser_patch_type", operation))
+                        .show(ui, |ui| {
+                            for idx in matching {
+                                let patch = &patches[idx];
+                                let key = (operation.clone(), idx);
+                                let selected = self.selected_patch.as_ref() == Some(&key);
+                                let mut response = ui.selectable_label(selected, patch.display_name());
+                                if let Some(parent) = &patch.parent {
+                                    response = response.on_hover_text(format!("位於 {}", parent));
+                                }
+                                if response.clicked() {
+                                    self.selected_patch = Some(key);
+                                    self.selected_def_entry = None;
+                                }
+                            }
+                        });
+                }
+            });
+    }
+
+    /// 處理列表的鍵盤操作：方向鍵移動、←／→／Enter 收起或展開類型、輸入字母跳到開頭相符的條目
+    fn handle_list_key(&mut self, key: NavKey, db: &SharedDefDatabase) {
+        let rows = self.list_nav.rows();
+        let selected = self
+            .selected_def_type
+            .as_ref()
+            .zip(self.selected_def_entry)
+            .and_then(|(def_type, idx)| rows.iter().position(|r| &r.def_type == def_type && r.entry == Some(idx)));
+        let current = selected.or_else(|| {
+            let cursor = self.list_nav.cursor.as_ref()?;
+            rows.iter().position(|r| r == cursor)
+        });
+
+        let target = match key {
+            NavKey::Up | NavKey::Down => step(rows.len(), current, key == NavKey::Down).map(|i| rows[i].clone()),
+            NavKey::Collapse | NavKey::Expand | NavKey::Toggle => {
+                let Some(row) = current.map(|i| rows[i].clone()) else {
+                    return;
+                };
+                let header = NavRow { entry: None, ..row.clone() };
+                let expanded = self.list_nav.is_expanded(&header);
+                let expand = match key {
+                    NavKey::Collapse => false,
+                    NavKey::Expand => true,
+                    _ => !expanded,
+                };
+                if expand == expanded {
+                    // 已經是要求的狀態：在條目上按 → 不動，在標題上按 ← 不動
+                    return;
+                }
+                self.selected_def_entry = None;
+                if expand {
+                    self.selected_def_type = Some(header.def_type.clone());
+                    self.selected_mod = header.group.clone();
+                } else {
+                    self.selected_def_type = None;
                 }
+                Some(header)
             }
-            Ok(Event::Text(e)) => {
-                if inside_defname {
-                    if let Ok(text) = e.unescape() {
-                        current_def_name = Some(text.trim().to_string());
-                    }
+            NavKey::TypeAhead(typed) => {
+                let typed = typed.to_lowercase();
+                let starts = |text: &str| text.to_lowercase().starts_with(&typed);
+                // 優先比對展開類型中的 defName，沒有時比對類型名稱
+                rows.iter()
+                    .find(|r| {
+                        r.entry
+                            .and_then(|idx| db.entries.get(&r.def_type)?.get(idx))
+                            .is_some_and(|entry| starts(&entry.def_name))
+                    })
+                    .or_else(|| rows.iter().find(|r| r.entry.is_none() && starts(r.def_type.as_str())))
+                    .cloned()
+            }
+        };
+        if let Some(row) = target {
+            self.move_to_row(row);
+        }
+    }
+
+    /// 鍵盤移動到列表中的一列：條目直接選取，類型標題只移動游標（展開狀態不變）
+    fn move_to_row(&mut self, row: NavRow) {
+        match row.entry {
+            Some(idx) => {
+                self.selected_def_type = Some(row.def_type.clone());
+                self.selected_def_entry = Some(idx);
+                self.selected_mod = row.group.clone();
+                self.list_nav.cursor = None;
+            }
+            None => {
+                self.selected_def_entry = None;
+                if self.list_nav.is_expanded(&row) {
+                    // 依模組分組時，沒有選取條目的展開類型由 selected_mod 決定
+                    self.selected_mod = row.group.clone();
                 }
-                if capturing {
-                    if let Ok(text) = e.unescape() {
-                        let trimmed = text.trim();
-                        if !trimmed.is_empty() {
-                            xml_parts.push(trimmed.to_string());
-                        }
-                    }
+                self.list_nav.cursor = Some(row);
+            }
+        }
+        self.list_nav.scroll = true;
+    }
+
+    /// Shift+點擊：選取列表中從起點到 `idx` 的所有條目；起點不在同一類型的列表中時只切換此條目
+    fn select_range(&mut self, def_type: &Symbol, matching: &[usize], idx: usize) {
+        let anchor = self
+            .selection_anchor
+            .as_ref()
+            .filter(|(anchor_type, _)| anchor_type == def_type)
+            .and_then(|(_, anchor)| matching.iter().position(|i| i == anchor));
+        let Some(end) = matching.iter().position(|&i| i == idx) else {
+            return;
+        };
+        match anchor {
+            Some(start) => {
+                let range = start.min(end)..=start.max(end);
+                self.selected_entries
+                    .extend(matching[range].iter().map(|&i| (def_type.clone(), i)));
+            }
+            None => {
+                let key = (def_type.clone(), idx);
+                if !self.selected_entries.remove(&key) {
+                    self.selected_entries.insert(key.clone());
+                }
+                self.selection_anchor = Some(key);
+            }
+        }
+    }
+
+    /// Def 類型標題與展開後其下符合篩選的條目；`group` 為依模組分組時所屬的模組
+    fn type_group_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        group: Option<&str>,
+        def_type: &Symbol,
+        entries: &[DefEntry],
+        mut matching: Vec<usize>,
+    ) {
+        let sort = self.entry_sorts.get(def_type.as_str()).copied().unwrap_or_default();
+        if sort != EntrySort::DefName {
+            // 掃描後的條目已依 defName 排序
+            sort.sort(entries, &mut matching);
+        }
+        let fuzzy = self.filter.is_fuzzy_search();
+        if fuzzy {
+            let score = |idx: &usize| {
+                self.filter.name_match(&entries[*idx].def_name).map_or(0, |m| m.score)
+            };
+            matching.sort_by_key(|idx| std::cmp::Reverse(score(idx)));
+        }
+
+        // 依模組分組時同一類型會出現在多個模組下，只展開選取的條目（或點擊的標題）所在的那一個
+        let is_selected = self.selected_def_type.as_ref() == Some(def_type)
+            && group.is_none_or(|group| match self.selected_def_entry {
+                Some(idx) => matching.contains(&idx),
+                None => self.selected_mod.as_deref() == Some(group),
+            });
+
+        let header = NavRow {
+            group: group.map(str::to_string),
+            def_type: def_type.clone(),
+            entry: None,
+        };
+        ui.horizontal(|ui| {
+            let response = ui.selectable_label(is_selected, format!("{} ({})", def_type, matching.len()));
+            if self.selected_def_entry.is_none() && self.list_nav.is_cursor_header(group, def_type) {
+                if self.list_nav.has_focus(ui.ctx()) {
+                    ui.painter().rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+                }
+                if self.list_nav.scroll {
+                    response.scroll_to_me(None);
+                }
+            }
+            if response.clicked() {
+                self.list_nav.cursor = Some(header.clone());
+                if is_selected {
+                    // 點擊已選擇的類型，收起
+                    self.selected_def_type = None;
+                    self.selected_def_entry = None;
+                } else {
+                    // 選擇新類型
+                    self.selected_def_type = Some(def_type.clone());
+                    self.selected_def_entry = None;
+                    self.selected_mod = group.map(str::to_string);
                 }
             }
-            Ok(Event::End(ref e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
 
-                if name == "defName" {
-                    inside_defname = false;
+            if is_selected {
+                let mut selected_sort = sort;
+                egui::ComboBox::from_id_salt(("browser_entry_sort", group, def_type))
+                    .selected_text(format!("⇅ {}", sort.label()))
+                    .width(90.0)
+                    .show_ui(ui, |ui| {
+                        for option in EntrySort::ALL {
+                            ui.selectable_value(&mut selected_sort, option, option.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("此類型下條目的排序方式；模糊搜尋時依相符程度排序");
+                if selected_sort != sort {
+                    self.set_entry_sort(def_type, selected_sort);
                 }
+                // 複製符合篩選的 defName，方便貼入相容性補丁
+                if ui
+                    .small_button("📋 複製 defName")
+                    .on_hover_text("複製此類型下符合篩選的 defName，每行一個")
+                    .clicked()
+                {
+                    let list = def_name_list(entries, &matching, false);
+                    ui.output_mut(|o| o.copied_text = list);
+                }
+                ui.menu_button("▾", |ui| {
+                    if ui.button("複製為 XML 列表（<li>…</li>）").clicked() {
+                        let list = def_name_list(entries, &matching, true);
+                        ui.output_mut(|o| o.copied_text = list);
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
 
-                if def_depth > 0 {
-                    if capturing {
-                        xml_parts.push(format!("</{}>", name));
+        self.list_nav.push(header.clone());
+
+        // 如果此類型被選中，顯示其下符合篩選的條目
+        if is_selected {
+            for &idx in &matching {
+                self.list_nav.push(NavRow {
+                    entry: Some(idx),
+                    ..header.clone()
+                });
+            }
+            ui.indent(("indent", group, def_type), |ui| {
+                // 虛擬化：每列高度固定，只建立捲動範圍內的列，其餘以空白佔位
+                let row_height = ui.spacing().interact_size.y;
+                let pitch = row_height + ui.spacing().item_spacing.y;
+                let top = ui.cursor().top();
+                let clip = ui.clip_rect();
+                // 鍵盤移動到的條目可能不在畫面內（沒有建立），依位置計算要捲到的範圍
+                if self.list_nav.scroll {
+                    let position = self.selected_def_entry.and_then(|idx| matching.iter().position(|&i| i == idx));
+                    if let Some(position) = position {
+                        let rect = egui::Rect::from_min_size(
+                            egui::pos2(ui.cursor().left(), top + position as f32 * pitch),
+                            egui::vec2(1.0, row_height),
+                        );
+                        ui.scroll_to_rect(rect, None);
                     }
-                    
-                    def_depth -= 1;
+                }
+                let last = (((clip.bottom() - top) / pitch).ceil().max(0.0) as usize).min(matching.len());
+                let first = (((clip.top() - top) / pitch).floor().max(0.0) as usize).min(last);
+                if first > 0 {
+                    ui.add_space(first as f32 * pitch);
+                }
+                for &idx in &matching[first..last] {
+                    let entry = &entries[idx];
+                    let key = (def_type.clone(), idx);
+                    ui.horizontal(|ui| {
+                        ui.set_min_height(row_height);
+                        let mut checked = self.selected_entries.contains(&key);
+                        if ui.checkbox(&mut checked, "").changed() {
+                            if checked {
+                                self.selected_entries.insert(key.clone());
+                            } else {
+                                self.selected_entries.remove(&key);
+                            }
+                        }
 
-                    if def_depth == 0 && name.ends_with("Def") {
-                        // Def 結束
-                        if let (Some(def_type), Some(def_name)) =
-                            (&current_def_type, &current_def_name)
+                        // 貼圖縮圖：只載入畫面內的，捲出畫面的會在下一幀取消
+                        if entry.tex_path.is_some() {
+                            let (rect, _) = ui.allocate_exact_size(
+                                egui::vec2(16.0, 16.0),
+                                egui::Sense::hover(),
+                            );
+                            if ui.is_rect_visible(rect) {
+                                let texture = entry_texture(&self.textures, entry);
+                                paint_texture(ui, rect, &texture);
+                            }
+                        }
+
+                        let entry_selected =
+                            self.selected_def_entry == Some(idx);
+                        let label: egui::WidgetText = match self
+                            .filter
+                            .name_match(&entry.def_name)
+                            .filter(|_| fuzzy)
                         {
-                            entries.push(DefEntry {
-                                def_name: def_name.clone(),
-                                file_path: path.to_path_buf(),
-                                xml_content: format_xml(&xml_parts.join("")),
-                                def_type: def_type.clone(),
-                            });
+                            Some(m) => highlighted_text(ui, &entry.def_name, &m.positions).into(),
+                            None => entry.def_name.as_str().into(),
+                        };
+                        let mut response = ui.selectable_label(entry_selected, label);
+                        if let Some(description) = &entry.description {
+                            response = response.on_hover_text(truncate_chars(description, DESCRIPTION_PREVIEW));
                         }
-                        current_def_type = None;
-                        current_def_name = None;
-                        capturing = false;
-                    }
+                        if entry.is_abstract {
+                            ui.label(egui::RichText::new("[A]").small().color(ui.visuals().warn_fg_color))
+                                .on_hover_text("抽象定義（Abstract=\"True\"）：只供其他 Def 以 ParentName 繼承，不會出現在遊戲中");
+                        }
+                        if let Some(label) = &entry.label {
+                            ui.weak(label);
+                        }
+                        if response.clicked() {
+                            let modifiers = ui.input(|i| i.modifiers);
+                            if modifiers.shift {
+                                self.select_range(def_type, &matching, idx);
+                            } else if modifiers.command {
+                                if !self.selected_entries.remove(&key) {
+                                    self.selected_entries.insert(key.clone());
+                                }
+                                self.selection_anchor = Some(key.clone());
+                            } else {
+                                self.selected_def_entry = Some(idx);
+                                self.selection_anchor = Some(key.clone());
+                                self.list_nav.cursor = None;
+                            }
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("📋 複製 defName").clicked() {
+                                ui.output_mut(|o| o.copied_text = entry.def_name.to_string());
+                                ui.close_menu();
+                            }
+                            if ui.button("📋 複製 XML").clicked() {
+                                ui.output_mut(|o| o.copied_text = entry.xml_content.clone());
+                                ui.close_menu();
+                            }
+                            if ui
+                                .button("📋 複製 xpath")
+                                .on_hover_text(def_xpath(entry.def_type.as_str(), &entry.def_name))
+                                .clicked()
+                            {
+                                let xpath = def_xpath(entry.def_type.as_str(), &entry.def_name);
+                                ui.output_mut(|o| o.copied_text = xpath);
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            let text = if self.is_bookmarked(def_type.as_str(), &entry.def_name) {
+                                "☆ 移除收藏"
+                            } else {
+                                "⭐ 加入收藏"
+                            };
+                            if ui.button(text).clicked() {
+                                self.toggle_bookmark(def_type.as_str(), &entry.def_name);
+                                ui.close_menu();
+                            }
+                        });
+                    });
                 }
-
-                if name == "Defs" {
-                    inside_defs = false;
+                if last < matching.len() {
+                    ui.add_space((matching.len() - last) as f32 * pitch);
                 }
+            });
+        }
+    }
+
+    /// 模組 → Def 類型 → 條目；模組名稱從已解析的條目路徑推算，切換分組不需重新掃描
+    fn mod_groups_ui(&mut self, ui: &mut egui::Ui, types: Vec<TypeGroup>) {
+        let mut mods: BTreeMap<String, Vec<TypeGroup>> = BTreeMap::new();
+        for (def_type, entries, matching) in types {
+            let mut by_mod: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+            for idx in matching {
+                by_mod.entry(self.entry_mod(&entries[idx].file_path)).or_default().push(idx);
+            }
+            for (mod_name, indices) in by_mod {
+                mods.entry(mod_name).or_default().push((def_type, entries, indices));
             }
-            Ok(Event::Eof) => break,
-            Err(_) => break,
-            _ => {}
         }
 
-        buf.clear();
+        for (mod_name, mut types) in mods {
+            if self.type_sort == TypeSort::Count {
+                types.sort_by_key(|(_, _, matching)| std::cmp::Reverse(matching.len()));
+            }
+            let total: usize = types.iter().map(|(_, _, matching)| matching.len()).sum();
+            egui::CollapsingHeader::new(format!("📦 {} ({})", mod_name, total))
+                .id_salt(("browser_mod", &mod_name))
+                .show(ui, |ui| {
+                    for (def_type, entries, matching) in types {
+                        self.type_group_ui(ui, Some(&mod_name), def_type, entries, matching);
+                    }
+                });
+        }
     }
 
-    Ok(entries)
-}
+    /// 條目所屬模組的名稱（依所在資料夾快取）：About.xml 的 `<name>`，
+    /// 找不到 About.xml 時使用 `Defs` 的上一層資料夾名稱
+    fn entry_mod(&mut self, file_path: &Path) -> String {
+        cached_mod_about(&mut self.mod_abouts, file_path)
+            .map(|about| about.display_name())
+            .or_else(|| mod_name(file_path))
+            .unwrap_or_else(|| "（未知模組）".to_string())
+    }
 
-// 簡單格式化 XML 使其更易讀
-fn format_xml(xml: &str) -> String {
-    let mut result = String::new();
-    let mut indent_level = 0;
-    let mut chars = xml.chars().peekable();
-    let mut after_text = false; // 追蹤是否剛輸出了文本內容
-    
-    while let Some(ch) = chars.next() {
-        if ch == '<' {
-            // 收集完整的標籤
-            let mut tag = String::from('<');
-            let mut is_closing = false;
-            let mut is_self_closing = false;
-            
-            // 檢查是否是結束標籤
-            if chars.peek() == Some(&'/') {
-                is_closing = true;
-            }
-            
-            // 收集標籤內容
-            while let Some(&next_ch) = chars.peek() {
-                tag.push(chars.next().unwrap());
-                if next_ch == '>' {
-                    // 檢查是否是自閉合標籤
-                    if tag.ends_with("/>") {
-                        is_self_closing = true;
-                    }
-                    break;
-                }
+    /// 全域搜尋結果列表：`[類型] defName (label) — 模組`，分頁顯示
+    fn global_results_ui(&mut self, ui: &mut egui::Ui, db: &SharedDefDatabase) {
+        let Some(search) = &mut self.global_search else {
+            return;
+        };
+        let mut selected = None;
+        let mut close = false;
+
+        ui.horizontal(|ui| {
+            if search.total > search.hits.len() {
+                ui.label(format!("{} 個結果（只列出前 {} 個）", search.total, search.hits.len()));
+            } else {
+                ui.label(format!("{} 個結果", search.total));
             }
-            
-            // 輸出標籤
-            if is_closing {
-                // 結束標籤
-                if after_text {
-                    // 如果前面有文本內容，標籤直接跟在後面（同一行）
-                    result.push_str(&tag);
-                    result.push('\n');
-                    after_text = false;
-                } else {
-                    // 否則，先減少縮排再輸出
-                    if indent_level > 0 {
-                        indent_level -= 1;
+            if ui.small_button("✖").on_hover_text("回到類型列表 (Esc)").clicked() {
+                close = true;
+            }
+        });
+
+        let pages = search.hits.len().div_ceil(GLOBAL_PAGE_SIZE).max(1);
+        search.page = search.page.min(pages - 1);
+        if pages > 1 {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(search.page > 0, egui::Button::new("◀")).clicked() {
+                    search.page -= 1;
+                }
+                ui.label(format!("{} / {}", search.page + 1, pages));
+                if ui.add_enabled(search.page + 1 < pages, egui::Button::new("▶")).clicked() {
+                    search.page += 1;
+                }
+            });
+        }
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("global_results")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                if search.hits.is_empty() {
+                    ui.weak("沒有符合的 Def");
+                }
+                let start = search.page * GLOBAL_PAGE_SIZE;
+                for hit in search.hits.iter().skip(start).take(GLOBAL_PAGE_SIZE) {
+                    let Some(entry) = db.entries.get(&hit.def_type).and_then(|e| e.get(hit.index))
+                    else {
+                        continue;
+                    };
+                    let mut label = format!("[{}] {}", hit.def_type, entry.def_name);
+                    if let Some(def_label) = &entry.label {
+                        label.push_str(&format!(" ({})", def_label));
+                    }
+                    if let Some(mod_name) = mod_name(&entry.file_path) {
+                        label.push_str(&format!(" — {}", mod_name));
+                    }
+                    let is_selected = self.selected_def_type.as_ref() == Some(&hit.def_type)
+                        && self.selected_def_entry == Some(hit.index);
+                    if ui
+                        .selectable_label(is_selected, label)
+                        .on_hover_text(entry.file_path.display().to_string())
+                        .clicked()
+                    {
+                        selected = Some((hit.def_type.clone(), hit.index));
                     }
-                    result.push_str(&"  ".repeat(indent_level));
-                    result.push_str(&tag);
-                    result.push('\n');
                 }
-            } else if is_self_closing {
-                // 自閉合標籤
-                result.push_str(&"  ".repeat(indent_level));
-                result.push_str(&tag);
-                result.push('\n');
-                after_text = false;
-            } else {
-                // 開始標籤
-                result.push_str(&"  ".repeat(indent_level));
-                result.push_str(&tag);
-                
-                // 檢查下一個字符是否是文本內容（不是 '<'）
-                if let Some(&next_ch) = chars.peek() {
-                    if next_ch != '<' {
-                        // 收集文本內容直到下一個標籤
-                        let mut text = String::new();
-                        while let Some(&ch) = chars.peek() {
-                            if ch == '<' {
-                                break;
-                            }
-                            text.push(chars.next().unwrap());
-                        }
-                        
-                        let trimmed = text.trim();
-                        if !trimmed.is_empty() {
-                            result.push_str(trimmed);
-                            after_text = true;
+            });
+
+        if let Some((def_type, index)) = selected {
+            self.selected_def_type = Some(def_type);
+            self.selected_def_entry = Some(index);
+            self.showing_global = false;
+        }
+        if close {
+            self.global_search = None;
+            self.showing_global = false;
+        }
+    }
+
+    /// 篩選預設：選擇時一次套用所有篩選條件，也可將目前條件儲存為新預設
+    fn quick_filter_ui(&mut self, ui: &mut egui::Ui) {
+        let Ok(mut settings) = self.settings.lock() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("預設:");
+            egui::ComboBox::from_id_salt("browser_quick_filters")
+                .selected_text("選擇預設…")
+                .show_ui(ui, |ui| {
+                    if settings.quick_filters.is_empty() {
+                        ui.weak("（尚無預設）");
+                    }
+                    for preset in &settings.quick_filters {
+                        if ui.selectable_label(false, &preset.name).clicked() {
+                            self.filter.def_type_pattern =
+                                preset.def_type_pattern.clone().unwrap_or_default();
+                            self.filter.path_pattern =
+                                preset.path_pattern.clone().unwrap_or_default();
+                            self.filter.tag_path = preset.tag_path.clone().unwrap_or_default();
+                            self.filter.abstract_filter = preset.abstract_filter;
+                            self.selected_def_type = None;
+                            self.selected_def_entry = None;
                         }
-                        // 文本後不增加縮排，因為下一個應該是結束標籤
-                    } else {
-                        // 下一個是標籤，換行並增加縮排
-                        result.push('\n');
-                        indent_level += 1;
-                        after_text = false;
                     }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_preset_name)
+                    .hint_text("預設名稱")
+                    .desired_width(100.0),
+            );
+            if ui.button("💾 儲存為預設").clicked() {
+                let name = if self.new_preset_name.trim().is_empty() {
+                    format!("預設 {}", settings.quick_filters.len() + 1)
                 } else {
-                    result.push('\n');
-                    indent_level += 1;
-                    after_text = false;
-                }
+                    self.new_preset_name.trim().to_string()
+                };
+                let non_empty = |s: &String| (!s.is_empty()).then(|| s.clone());
+                settings.quick_filters.push(QuickFilter {
+                    name,
+                    def_type_pattern: non_empty(&self.filter.def_type_pattern),
+                    path_pattern: non_empty(&self.filter.path_pattern),
+                    tag_path: non_empty(&self.filter.tag_path),
+                    abstract_filter: self.filter.abstract_filter,
+                });
+                settings.save();
+                self.new_preset_name.clear();
             }
-        }
+        });
+    }
+
+    /// 只顯示指定檔案中的 Defs
+    pub fn focus_file(&mut self, path: &Path) {
+        self.filter = BrowserFilter {
+            path_pattern: path.display().to_string(),
+            ..BrowserFilter::default()
+        };
+    }
+
+    /// 請求掃描協調器重新掃描
+    pub fn scan_defs(&mut self) {
+        self.scan_request.request();
+    }
+
+    /// 命令面板中的瀏覽器命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![
+            Command::new("重新掃描 Defs", |tab: &mut Self, _| tab.scan_defs()),
+            Command::new("顯示變更記錄", |tab: &mut Self, _| tab.show_changes = true),
+        ]
     }
-    
-    result
 }
 
+
 // 使用系統預設程式打開檔案
-fn open_file_with_default_app(path: &Path) {
+/// 以設置中的編輯器命令開啟檔案並跳到指定行；沒有設定或無法啟動時以預設程式開啟
+pub fn open_file_at_line(settings: &Mutex<AppSettings>, path: &Path, line: u64) {
+    let command = settings.lock().map(|s| s.editor_command.clone()).unwrap_or_default();
+    if let Some(args) = editor_command_args(&command, path, line) {
+        match std::process::Command::new(&args[0]).args(&args[1..]).spawn() {
+            Ok(_) => return,
+            Err(e) => tracing::warn!(command = %command, error = %e, "無法啟動編輯器，改以預設程式開啟"),
+        }
+    }
+    open_file_with_default_app(path);
+}
+
+pub fn open_file_with_default_app(path: &Path) {
     #[cfg(target_os = "windows")]
     {
         let _ = std::process::Command::new("cmd")
@@ -562,3 +2099,63 @@ fn open_file_with_default_app(path: &Path) {
         let _ = std::process::Command::new("xdg-open").arg(path).spawn();
     }
 }
+
+/// 在檔案總管中顯示檔案：Windows 與 macOS 會選取該檔案，Linux 開啟所在的資料夾
+pub fn reveal_in_file_manager(path: &Path) {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer 需要 `/select,"路徑"` 的形式，不能讓路徑與參數分開加引號
+        let _ = std::process::Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn();
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = std::process::Command::new("open").arg("-R").arg(path).spawn();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(dir) = path.parent() {
+            let _ = std::process::Command::new("xdg-open").arg(dir).spawn();
+        }
+    }
+}
+
+/// 檔案所屬模組的 About.xml 資訊，依檔案所在資料夾快取，同一資料夾中的檔案不重複讀取
+fn cached_mod_about(cache: &mut HashMap<PathBuf, Option<ModAbout>>, file_path: &Path) -> Option<ModAbout> {
+    let dir = file_path.parent().unwrap_or(file_path);
+    cache
+        .entry(dir.to_path_buf())
+        .or_insert_with(|| find_mod_about(file_path))
+        .clone()
+}
+
+/// 詳細資訊中的模組名稱、packageId 與作者；沒有 About.xml 時顯示基礎路徑下的第一層資料夾名稱
+fn mod_about_ui(ui: &mut egui::Ui, about: Option<&ModAbout>, file_path: &Path, base_path: &Path) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("模組:");
+        let Some(about) = about else {
+            let folder = file_path
+                .strip_prefix(base_path)
+                .ok()
+                .and_then(|relative| relative.components().next())
+                .filter(|_| file_path.parent() != Some(base_path))
+                .map(|c| c.as_os_str().to_string_lossy().to_string());
+            match folder {
+                Some(folder) => ui.label(folder).on_hover_text("找不到 About/About.xml，顯示所在的第一層資料夾"),
+                None => ui.weak("（未知模組）"),
+            };
+            return;
+        };
+        ui.strong(about.display_name()).on_hover_text(about.root.display().to_string());
+        if let Some(package_id) = &about.package_id {
+            ui.monospace(package_id);
+        }
+        if let Some(author) = &about.author {
+            ui.weak(format!("作者: {}", author));
+        }
+    });
+}
@@ -0,0 +1,53 @@
+use eframe::egui;
+
+pub use rimworld_xml_tools::VERSION;
+
+/// 完整的更新日誌
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+/// 取出指定版本的段落（`## [版本]` 到下一個 `## ` 之前）；找不到時回傳 None
+fn release_notes(version: &str) -> Option<&'static str> {
+    let heading = format!("## [{}]", version);
+    let start = CHANGELOG.find(&heading)? + heading.len();
+    let rest = &CHANGELOG[start..];
+    let end = rest.find("\n## ").unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+/// 更新後顯示的「更新內容」視窗；按下「知道了」時回傳 true
+pub fn show_whats_new(ctx: &egui::Context) -> bool {
+    let mut acknowledged = false;
+    egui::Window::new(format!("🎉 v{} 更新內容", VERSION))
+        .collapsible(false)
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| match release_notes(VERSION) {
+                    Some(notes) => notes_ui(ui, notes),
+                    None => {
+                        ui.label("此版本沒有更新說明。");
+                    }
+                });
+            ui.separator();
+            if ui.button("知道了").clicked() {
+                acknowledged = true;
+            }
+        });
+    acknowledged
+}
+
+/// 以簡單格式顯示 Markdown 段落：`### ` 為小標題，`- ` 為項目
+fn notes_ui(ui: &mut egui::Ui, notes: &str) {
+    for line in notes.lines() {
+        let line = line.trim_end();
+        if let Some(heading) = line.strip_prefix("### ") {
+            ui.add_space(4.0);
+            ui.strong(heading);
+        } else if let Some(item) = line.strip_prefix("- ") {
+            ui.label(format!("• {}", item));
+        } else if !line.is_empty() {
+            ui.label(line);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use eframe::egui;
+
+use crate::database::SharedDatabase;
+use crate::intern::Symbol;
+use crate::search::fuzzy_score;
+
+/// 最多顯示的結果數量
+const MAX_RESULTS: usize = 50;
+
+/// 記住的最近選擇數量
+const RECENT_LIMIT: usize = 20;
+
+/// 命令的動作，`T` 為命令作用的對象（分頁或整個應用）
+pub type CommandFn<T> = Box<dyn Fn(&mut T, &egui::Context)>;
+
+/// 命令面板中的一個命令
+pub struct Command<T> {
+    pub label: String,
+    run: CommandFn<T>,
+}
+
+impl<T: 'static> Command<T> {
+    pub fn new(label: impl Into<String>, run: impl Fn(&mut T, &egui::Context) + 'static) -> Self {
+        Self {
+            label: label.into(),
+            run: Box::new(run),
+        }
+    }
+
+    /// 將分頁的命令轉為作用於整個應用的命令：標籤加上分頁名稱，執行後再呼叫 `after`
+    pub fn map<U: 'static>(
+        self,
+        prefix: &str,
+        project: fn(&mut U) -> &mut T,
+        after: impl Fn(&mut U) + 'static,
+    ) -> Command<U> {
+        let run = self.run;
+        Command {
+            label: format!("{}: {}", prefix, self.label),
+            run: Box::new(move |target, ctx| {
+                run(project(target), ctx);
+                after(target);
+            }),
+        }
+    }
+}
+
+/// 已註冊的命令；各分頁以 `commands()` 提供自己的命令
+pub struct CommandRegistry<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> Default for CommandRegistry<T> {
+    fn default() -> Self {
+        Self { commands: Vec::new() }
+    }
+}
+
+impl<T: 'static> CommandRegistry<T> {
+    pub fn add(&mut self, label: impl Into<String>, run: impl Fn(&mut T, &egui::Context) + 'static) {
+        self.commands.push(Command::new(label, run));
+    }
+
+    pub fn extend(&mut self, commands: impl IntoIterator<Item = Command<T>>) {
+        self.commands.extend(commands);
+    }
+
+    /// 執行指定索引的命令
+    pub fn run(&self, index: usize, target: &mut T, ctx: &egui::Context) {
+        if let Some(command) = self.commands.get(index) {
+            (command.run)(target, ctx);
+        }
+    }
+}
+
+/// 命令面板選取的項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteChoice {
+    Command(usize),      // 命令在註冊表中的索引
+    Def(Symbol, Symbol), // (DefType, defName)
+}
+
+impl PaletteChoice {
+    /// 用於記住最近選擇的鍵（命令的索引在重新啟動前不會改變）
+    fn recent_key(&self) -> String {
+        match self {
+            PaletteChoice::Command(index) => format!("cmd:{}", index),
+            PaletteChoice::Def(def_type, def_name) => format!("def:{}/{}", def_type, def_name),
+        }
+    }
+}
+
+/// 命令面板（Ctrl+Shift+P 只列出命令，Ctrl+P 也可依名稱跳到任一 Def）
+pub struct CommandPalette {
+    open: bool,
+    commands_only: bool,
+    query: String,
+    results: Vec<(PaletteChoice, String)>, // (項目, 顯示文字)，依分數排序
+    highlighted: usize,
+    searched_generation: u64,
+    recent: Vec<String>, // 最近選擇的項目，最新的在前
+    database: SharedDatabase,
+}
+
+impl CommandPalette {
+    pub fn new(database: SharedDatabase) -> Self {
+        Self {
+            open: false,
+            commands_only: false,
+            query: String::new(),
+            results: Vec::new(),
+            highlighted: 0,
+            searched_generation: 0,
+            recent: Vec::new(),
+            database,
+        }
+    }
+
+    pub fn open<T: 'static>(&mut self, commands_only: bool, registry: &CommandRegistry<T>) {
+        self.open = true;
+        self.commands_only = commands_only;
+        self.query.clear();
+        self.refresh(registry);
+    }
+
+    /// 最近選擇過的項目加分，越近越高
+    fn recent_bonus(&self, choice: &PaletteChoice) -> i32 {
+        let key = choice.recent_key();
+        self.recent
+            .iter()
+            .position(|k| *k == key)
+            .map_or(0, |position| (RECENT_LIMIT - position) as i32 * 2)
+    }
+
+    fn refresh<T: 'static>(&mut self, registry: &CommandRegistry<T>) {
+        self.highlighted = 0;
+
+        let mut scored: Vec<(i32, PaletteChoice, String)> = registry
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                let score = fuzzy_score(&command.label, &self.query)? as i32;
+                Some((score, PaletteChoice::Command(index), format!("⚡ {}", command.label)))
+            })
+            .collect();
+
+        if !self.commands_only {
+            if let Ok(db) = self.database.read() {
+                self.searched_generation = db.generation;
+                // 沒有輸入時只列出命令與最近開啟的 Def，避免列出全部 Def
+                scored.extend(
+                    db.entries
+                        .iter()
+                        .flat_map(|(def_type, entries)| entries.iter().map(move |e| (def_type, e)))
+                        .filter_map(|(def_type, entry)| {
+                            let choice = PaletteChoice::Def(def_type.clone(), entry.def_name.clone());
+                            if self.query.is_empty() && self.recent_bonus(&choice) == 0 {
+                                return None;
+                            }
+                            let score = fuzzy_score(&entry.def_name, &self.query)? as i32;
+                            Some((score, choice, format!("{}  ({})", entry.def_name, def_type)))
+                        }),
+                );
+            }
+        }
+
+        for (score, choice, _) in &mut scored {
+            *score += self.recent_bonus(choice);
+        }
+        scored.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| a.2.len().cmp(&b.2.len()))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+        scored.dedup_by(|a, b| a.1 == b.1);
+        self.results = scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, choice, label)| (choice, label))
+            .collect();
+    }
+
+    fn remember(&mut self, choice: &PaletteChoice) {
+        let key = choice.recent_key();
+        self.recent.retain(|k| *k != key);
+        self.recent.insert(0, key);
+        self.recent.truncate(RECENT_LIMIT);
+    }
+
+    /// 顯示命令面板；選取項目時回傳
+    pub fn show<T: 'static>(
+        &mut self,
+        ctx: &egui::Context,
+        registry: &CommandRegistry<T>,
+    ) -> Option<PaletteChoice> {
+        if !self.open {
+            return None;
+        }
+
+        // 背景掃描完成後更新結果
+        let generation = self.database.read().map_or(0, |db| db.generation);
+        if !self.commands_only && generation != self.searched_generation {
+            self.refresh(registry);
+        }
+
+        let (escape, enter, up, down) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::Escape),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+        if down && self.highlighted + 1 < self.results.len() {
+            self.highlighted += 1;
+        }
+        if up && self.highlighted > 0 {
+            self.highlighted -= 1;
+        }
+
+        let mut chosen = if enter {
+            self.results.get(self.highlighted).map(|(choice, _)| choice.clone())
+        } else {
+            None
+        };
+
+        let (title, hint) = if self.commands_only {
+            ("⚡ 命令", "輸入命令…（↑↓ 選擇，Enter 執行，Esc 關閉）")
+        } else {
+            ("🔎 快速搜尋", "輸入命令或 defName…（↑↓ 選擇，Enter 開啟，Esc 關閉）")
+        };
+
+        let mut query_changed = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .fixed_size([420.0, 360.0])
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(hint)
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                query_changed = response.changed();
+
+                ui.separator();
+
+                if self.results.is_empty() {
+                    ui.weak("沒有符合的項目");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("command_palette_results")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (index, (choice, label)) in self.results.iter().enumerate() {
+                            let is_highlighted = index == self.highlighted;
+                            let response = ui.selectable_label(is_highlighted, label);
+                            if is_highlighted && (up || down) {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                chosen = Some(choice.clone());
+                            }
+                        }
+                    });
+            });
+
+        if query_changed {
+            self.refresh(registry);
+        }
+        if let Some(choice) = &chosen {
+            self.remember(choice);
+            self.open = false;
+        }
+        chosen
+    }
+}
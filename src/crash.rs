@@ -0,0 +1,78 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use crate::settings::AppSettings;
+
+thread_local! {
+    // 目前執行緒是否在可恢復的區塊內（單一檔案解析），此時 panic 不算當機
+    static RECOVERABLE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 當機日誌路徑
+fn crash_log_path() -> Option<PathBuf> {
+    AppSettings::config_dir().ok().map(|dir| dir.join("crash.log"))
+}
+
+/// 上次當機的標記檔
+fn crash_marker_path() -> Option<PathBuf> {
+    AppSettings::config_dir().ok().map(|dir| dir.join("crash.marker"))
+}
+
+/// 安裝 panic hook：將訊息與 backtrace 寫入設置目錄下的當機日誌，並留下標記供下次啟動時提示
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if RECOVERABLE.with(|r| r.get()) {
+            tracing::warn!(%info, "解析時發生 panic，已略過該檔案");
+            return;
+        }
+
+        let backtrace = std::backtrace::Backtrace::force_capture();
+
+        if let (Some(log_path), Some(marker_path)) = (crash_log_path(), crash_marker_path()) {
+            if let Some(parent) = log_path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(mut file) = std::fs::File::create(&log_path) {
+                let _ = writeln!(file, "RimWorld XML Tools {}", crate::VERSION);
+                let _ = writeln!(file, "{}", info);
+                let _ = writeln!(file);
+                let _ = writeln!(file, "{}", backtrace);
+            }
+            let _ = std::fs::write(marker_path, "");
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// 若上次執行當機，移除標記並回傳日誌路徑
+pub fn take_crash_marker() -> Option<PathBuf> {
+    let marker_path = crash_marker_path()?;
+    if !marker_path.exists() {
+        return None;
+    }
+    let _ = std::fs::remove_file(marker_path);
+    crash_log_path().filter(|path| path.exists())
+}
+
+/// 執行可恢復的工作；發生 panic 時回傳 panic 訊息而不終止程式
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    let previous = RECOVERABLE.with(|r| r.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    RECOVERABLE.with(|r| r.set(previous));
+    result.map_err(|payload| panic_message(payload.as_ref()))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知的 panic".to_string()
+    }
+}
@@ -0,0 +1,537 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use walkdir::WalkDir;
+
+use crate::defs::{parse_defs_from_file, DefEntry, DefFile, DefRootRules};
+use crate::crash::catch_panic;
+use crate::diff::DefChangeset;
+use crate::intern::Symbol;
+use crate::merge::{compute_depths, parse_def_data, DefData};
+use crate::patches::{is_patch_file, parse_patches_from_file, PatchEntry};
+use crate::scan_cache::{FileStamp, ScanCache};
+use crate::search_index::SearchIndex;
+use crate::status::StatusSink;
+use crate::xml_parser::{index_tag_values, text_value_ranges, ErrorLog, FileTagIndex, ParseError, ParseErrorKind};
+
+/// 共享的 Def 資料庫，Def 瀏覽器與繼承展開共用同一份掃描結果
+#[derive(Default)]
+pub struct SharedDefDatabase {
+    pub entries: BTreeMap<Symbol, Vec<DefEntry>>, // DefType -> List of entries
+    pub data: HashMap<Symbol, DefData>,           // defName (或 Abstract 的 Name) -> DefData
+    pub generation: u64,                          // 每次掃描後遞增，分頁據此重置選擇
+    pub errors: ErrorLog,                         // 掃描時無法（完整）解析的檔案
+    pub tag_index: HashMap<Symbol, BTreeSet<Symbol>>, // 標籤名稱（小寫）-> 唯一值，供標籤查找器使用
+    pub tag_value_counts: HashMap<String, usize>,      // 標籤名稱（原始大小寫）-> 唯一值數量，供自動完成使用
+    pub xml_count: usize,                         // 上次掃描的 XML 檔案數量
+    pub refreshing: bool,                         // 背景掃描進行中，目前顯示的是舊資料
+    pub base_path: PathBuf,                       // 掃描的目錄
+    pub changes: Option<DefChangeset>,            // 與同一目錄上一次掃描相比的變更
+    pub search_index: SearchIndex,                // 瀏覽器全域搜尋用的 defName / label 索引
+    pub name_index: HashMap<Symbol, (Symbol, usize)>, // defName -> (DefType, 條目索引)，同名時為第一個
+    pub skipped_roots: BTreeMap<String, usize>,   // <Defs> 下依規則略過的元素名稱 -> 出現次數
+    pub ignored_def_types: usize,                 // 依設置忽略的 Def 類型數量
+    pub excluded_files: usize,                    // 依排除樣式略過的 XML 檔案數量
+    pub duplicates: Vec<DuplicateDef>,            // 同類型中 defName 重複的 Defs
+    pub referenced_by: HashMap<Symbol, Vec<(Symbol, usize)>>, // defName -> 參照它的條目 (DefType, 條目索引)，依類型、defName 排序
+    pub patches: BTreeMap<Symbol, Vec<PatchEntry>>, // 操作類型 -> Patches 資料夾中的操作，依檔案與行號排序
+}
+
+/// 同一類型中出現多次的 defName（RimWorld 載入時會報錯），不論是否在同一檔案或模組
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDef {
+    pub def_type: Symbol,
+    pub def_name: Symbol,
+    pub indices: Vec<usize>, // 在 `entries[def_type]` 中的索引
+}
+
+pub type SharedDatabase = Arc<RwLock<SharedDefDatabase>>;
+
+/// 瀏覽器收錄哪些 XML 檔案中的 Def 條目；繼承資料與標籤索引一律包含所有 XML
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanMode {
+    #[default]
+    DefsDirectoryOnly,   // 路徑中含有 `Defs` 的檔案
+    AllXmlFiles,         // 所有 XML，只要根元素是 <Defs>（例如直接放在模組根目錄或 data/ 中）
+    Custom(Vec<String>), // 所在目錄的任一層名稱符合其中一個樣式（不分大小寫，可用 `*`）
+}
+
+impl ScanMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScanMode::DefsDirectoryOnly => "只掃描 Defs 目錄",
+            ScanMode::AllXmlFiles => "所有 XML 檔案",
+            ScanMode::Custom(_) => "自訂目錄名稱",
+        }
+    }
+
+    /// 此檔案是否要解析為瀏覽器條目
+    pub fn includes(&self, path: &Path) -> bool {
+        match self {
+            ScanMode::DefsDirectoryOnly => path.to_str().is_some_and(|s| s.contains("Defs")),
+            ScanMode::AllXmlFiles => true,
+            ScanMode::Custom(patterns) => path.parent().is_some_and(|dir| {
+                dir.components().any(|c| {
+                    let name = c.as_os_str().to_string_lossy().to_lowercase();
+                    patterns
+                        .iter()
+                        .map(|p| p.trim().to_lowercase())
+                        .any(|p| !p.is_empty() && matches_pattern(&name, &p))
+                })
+            }),
+        }
+    }
+}
+
+/// 掃描的額外選項
+#[derive(Default)]
+pub struct ScanOptions<'a> {
+    pub cancel: Option<&'a AtomicBool>, // 被設定時盡快停止
+    pub cache_dir: Option<&'a Path>,    // 掃描快取的目錄，見 `ScanCache`
+    pub exclude: &'a [String],          // 排除樣式，見 `is_excluded`
+}
+
+/// 檔案是否符合任一排除樣式（不分大小寫）；`relative` 為相對於基礎目錄的路徑
+///
+/// 含 `/` 的樣式從基礎目錄比對整個路徑，`**` 代表任意層目錄（例如 `**/Source/**`）；
+/// 不含 `/` 的樣式只要符合路徑中任一層的名稱即排除（例如 `*backup*`）；`*` 不跨越 `/`
+pub fn is_excluded(relative: &Path, patterns: &[String]) -> bool {
+    let segments: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    patterns
+        .iter()
+        .map(|p| p.trim().replace('\\', "/").to_lowercase())
+        .filter(|p| !p.is_empty())
+        .any(|pattern| {
+            if pattern.contains('/') {
+                let parts: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+                matches_segments(&parts, &segments)
+            } else {
+                segments.iter().any(|segment| matches_pattern(segment, &pattern))
+            }
+        })
+}
+
+/// 逐層比對路徑，`**` 可對應零到多層
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..])),
+        Some((part, rest)) => path
+            .split_first()
+            .is_some_and(|(segment, tail)| matches_pattern(segment, part) && matches_segments(rest, tail)),
+    }
+}
+
+/// 簡單的萬用字元比對，`*` 代表任意數量的字元
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // 沒有 `*`，必須完全相符
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// 以 defName 尋找瀏覽器條目（跨所有類型）；同名的 Def 回傳依類型名稱排序後的第一個，
+/// 沒有此 defName 時才找只有 `Name` 的抽象基底
+pub fn find_def<'a>(db: &'a SharedDefDatabase, name: &str) -> Option<&'a DefEntry> {
+    let (def_type, index) = db.name_index.get(name)?;
+    db.entries.get(def_type)?.get(*index)
+}
+
+/// `Name` 與 defName 是不同的命名空間：先收錄有 defName 的條目，抽象基底的 `Name` 只補上沒人用的名稱
+fn build_name_index(entries: &BTreeMap<Symbol, Vec<DefEntry>>) -> HashMap<Symbol, (Symbol, usize)> {
+    let mut index = HashMap::new();
+    for has_def_name in [true, false] {
+        for (def_type, entries) in entries {
+            for (i, entry) in entries.iter().enumerate().filter(|(_, e)| e.has_def_name == has_def_name) {
+                index
+                    .entry(entry.def_name.clone())
+                    .or_insert_with(|| (def_type.clone(), i));
+            }
+        }
+    }
+    index
+}
+
+/// 反向參照索引：條目 XML 中某個文字節點（或 `<li>` 內容）完全等於另一個條目的 defName 即算參照，
+/// 描述等長文字中出現的名稱不算；每個條目對同一名稱只記一次，不包含參照自己。
+/// 只有 `Name` 的抽象基底只能經由 ParentName 繼承，不會被文字參照
+pub fn build_reverse_references(
+    entries: &BTreeMap<Symbol, Vec<DefEntry>>,
+    name_index: &HashMap<Symbol, (Symbol, usize)>,
+) -> HashMap<Symbol, Vec<(Symbol, usize)>> {
+    // 取用索引中已駐留的名稱
+    let def_name = |value: &str| {
+        let (name, (def_type, i)) = name_index.get_key_value(value)?;
+        entries.get(def_type)?.get(*i)?.has_def_name.then_some(name)
+    };
+    let references: Vec<(Symbol, Symbol, usize)> = entries
+        .iter()
+        .flat_map(|(def_type, entries)| entries.iter().enumerate().map(move |(i, e)| (def_type, i, e)))
+        .collect::<Vec<_>>()
+        .par_iter()
+        .flat_map_iter(|(def_type, index, entry)| {
+            let xml = entry.xml_content.as_str();
+            let names: BTreeSet<&Symbol> = text_value_ranges(xml)
+                .into_iter()
+                .map(|range| &xml[range])
+                .filter(|value| *value != entry.def_name.as_str())
+                .filter_map(def_name)
+                .collect();
+            names
+                .into_iter()
+                .map(|name| (name.clone(), (*def_type).clone(), *index))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut index: HashMap<Symbol, Vec<(Symbol, usize)>> = HashMap::new();
+    for (name, def_type, i) in references {
+        index.entry(name).or_default().push((def_type, i));
+    }
+    for referencing in index.values_mut() {
+        referencing.sort();
+    }
+    index
+}
+
+/// 找出同一類型中 defName 重複的條目；條目需已依 defName 排序，同名的條目相鄰。
+/// 只有 `Name` 的抽象基底不算，與同名的 defName 並存是合法的
+pub fn find_duplicate_defs(entries: &BTreeMap<Symbol, Vec<DefEntry>>) -> Vec<DuplicateDef> {
+    let mut duplicates = Vec::new();
+    for (def_type, entries) in entries {
+        let mut start = 0;
+        while start < entries.len() {
+            let def_name = &entries[start].def_name;
+            let end = start + entries[start..].iter().take_while(|e| &e.def_name == def_name).count();
+            let indices: Vec<usize> = (start..end).filter(|&i| entries[i].has_def_name).collect();
+            if indices.len() > 1 {
+                duplicates.push(DuplicateDef {
+                    def_type: def_type.clone(),
+                    def_name: def_name.clone(),
+                    indices,
+                });
+            }
+            start = end;
+        }
+    }
+    duplicates
+}
+
+/// 狀態列中掃描工作的名稱
+pub const SCAN_TASK: &str = "掃描 Defs";
+
+impl SharedDefDatabase {
+    /// 瀏覽器條目總數
+    pub fn total_entries(&self) -> usize {
+        self.entries.values().map(|v| v.len()).sum()
+    }
+
+    /// 標籤查找器的結果：標籤（不分大小寫）的所有唯一值，依字串排序
+    ///
+    /// 掃描與索引一律保留原始大小寫（RimWorld 的 Def 參照區分大小寫）；
+    /// `case_fold` 為 true 時才在彙整結果時不分大小寫合併，例如 `Steel` 與 `steel` 算一個，
+    /// 顯示第一個遇到的寫法（索引依字串排序，所以是排序最前面的寫法，大寫優先）
+    pub fn tag_values(&self, tag_name: &str, case_fold: bool) -> Vec<String> {
+        let Some(values) = self.tag_index.get(tag_name.trim().to_lowercase().as_str()) else {
+            return Vec::new();
+        };
+        if !case_fold {
+            return values.iter().map(|value| value.to_string()).collect();
+        }
+        let mut folded: HashMap<String, String> = HashMap::new();
+        for value in values {
+            folded.entry(value.to_lowercase()).or_insert_with(|| value.to_string());
+        }
+        let mut results: Vec<String> = folded.into_values().collect();
+        results.sort();
+        results
+    }
+
+    /// 狀態訊息的警告後綴（有檔案被略過或 defName 重複時）
+    pub fn skipped_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if self.ignored_def_types > 0 {
+            suffix.push_str(&format!("，{} 種類型已忽略", self.ignored_def_types));
+        }
+        if self.excluded_files > 0 {
+            suffix.push_str(&format!("，{} 個檔案依排除樣式略過", self.excluded_files));
+        }
+        if !self.errors.is_empty() {
+            suffix.push_str(&format!("，⚠ {} 個檔案有解析錯誤", self.errors.len()));
+        }
+        if !self.duplicates.is_empty() {
+            suffix.push_str(&format!("，⚠ {} 個重複的 defName", self.duplicates.len()));
+        }
+        suffix
+    }
+
+    /// 掃描目錄並重建所有視圖與標籤索引，回傳找到的 XML 檔案數量
+    pub fn scan(&mut self, base_path: &Path, rules: &DefRootRules, mode: &ScanMode, status: &StatusSink) -> usize {
+        self.scan_cancellable(base_path, rules, mode, status, &ScanOptions::default())
+            .unwrap_or_default()
+    }
+
+    /// 與 `scan` 相同，但 `options.cancel` 被設定時盡快停止並回傳 None（資料庫內容不完整，應捨棄）
+    ///
+    /// 指定 `cache_dir` 時，修改時間與大小未變更的檔案沿用上次的解析結果，掃描後更新快取；
+    /// 符合 `exclude` 的檔案完全不解析（也不列入繼承資料與標籤索引）
+    pub fn scan_cancellable(
+        &mut self,
+        base_path: &Path,
+        rules: &DefRootRules,
+        mode: &ScanMode,
+        status: &StatusSink,
+        options: &ScanOptions,
+    ) -> Option<usize> {
+        let not_cancelled = AtomicBool::new(false);
+        let cancel = options.cancel.unwrap_or(&not_cancelled);
+        self.entries.clear();
+        self.data.clear();
+        self.errors.clear();
+        self.tag_index.clear();
+        self.tag_value_counts.clear();
+        self.skipped_roots.clear();
+        self.patches.clear();
+        self.ignored_def_types = rules.ignored.iter().filter(|t| !t.trim().is_empty()).count();
+
+        // 只走訪一次目錄
+        let started = Instant::now();
+        let xml_files: Vec<PathBuf> = WalkDir::new(base_path)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.path().is_file()
+                    && e.path().extension().and_then(|s| s.to_str()) == Some("xml")
+            })
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        let (excluded, xml_files): (Vec<PathBuf>, Vec<PathBuf>) = xml_files.into_iter().partition(|path| {
+            let relative = path.strip_prefix(base_path).unwrap_or(path);
+            is_excluded(relative, options.exclude)
+        });
+        self.excluded_files = excluded.len();
+
+        let total = xml_files.len();
+        tracing::info!(xml_files = total, elapsed_ms = started.elapsed().as_millis() as u64, "目錄走訪完成");
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if total == 0 {
+            tracing::warn!(path = %base_path.display(), "工作目錄中沒有找到任何 XML 檔案");
+        }
+
+        let started = Instant::now();
+        let processed = AtomicUsize::new(0);
+
+        // 先取出快取中未變更的檔案，保留走訪順序（同名的 Def 以後出現的為準）
+        let mut cache = options.cache_dir.map(|dir| ScanCache::load(dir, base_path, rules, mode));
+        let sources: Vec<(PathBuf, Option<FileStamp>, Option<ParsedFile>)> = xml_files
+            .into_iter()
+            .map(|path| {
+                let stamp = FileStamp::read(&path);
+                let cached = cache.as_mut().zip(stamp).and_then(|(cache, stamp)| cache.take(&path, stamp));
+                (path, stamp, cached)
+            })
+            .collect();
+        let reused = sources.iter().filter(|(_, _, cached)| cached.is_some()).count();
+
+        // 並行解析
+        let parsed: Vec<(PathBuf, Option<FileStamp>, ParsedFile)> = sources
+            .into_par_iter()
+            .map(|(path, stamp, cached)| {
+                // 已取消時略過剩下的檔案
+                if cancel.load(Ordering::Relaxed) {
+                    return (path, stamp, ParsedFile::default());
+                }
+                let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Ok(mut status) = status.lock() {
+                    status.set_progress(SCAN_TASK, done, total);
+                }
+                if let Some(parsed) = cached {
+                    return (path, stamp, parsed);
+                }
+
+                // 單一檔案的 panic 只略過該檔案，不影響整個掃描
+                let parsed = catch_panic(|| parse_file(&path, rules, mode)).unwrap_or_else(|message| ParsedFile {
+                    errors: vec![ParseError {
+                        kind: ParseErrorKind::Panic,
+                        file: path.clone(),
+                        message: format!("解析時發生錯誤: {}", message),
+                        line: None,
+                    }],
+                    ..ParsedFile::default()
+                });
+                (path, stamp, parsed)
+            })
+            .collect();
+        tracing::info!(elapsed_ms = started.elapsed().as_millis() as u64, reused, "解析完成");
+        if cancel.load(Ordering::Relaxed) {
+            tracing::info!("掃描已取消");
+            return None;
+        }
+        if let Some(cache) = &cache {
+            if let Err(e) = cache.save(&parsed) {
+                tracing::warn!(error = %e, "無法寫入掃描快取");
+            }
+        }
+
+        let started = Instant::now();
+        let mut tag_names: HashMap<Symbol, Symbol> = HashMap::new();
+        for (_, _, file) in parsed {
+            for error in file.errors {
+                tracing::warn!(file = %error.file.display(), error = %error.message, "檔案無法解析，已略過");
+                self.errors.push(error);
+            }
+            for name in file.skipped_roots {
+                *self.skipped_roots.entry(name).or_default() += 1;
+            }
+            for patch in file.patches {
+                self.patches.entry(patch.operation.clone()).or_default().push(patch);
+            }
+            // 按 DefType 分組
+            for entry in file.entries {
+                self.entries
+                    .entry(entry.def_type.clone())
+                    .or_default()
+                    .push(entry);
+            }
+            for def_data in file.data {
+                self.data.insert(def_data.def_name.clone(), def_data);
+            }
+            for (path, values) in file.tags.values {
+                // 路徑上的每個標籤都能查到這些文本
+                let mut tags: Vec<&str> = path.split('/').collect();
+                tags.sort_unstable();
+                tags.dedup();
+                for tag in tags {
+                    self.tag_index.entry(Symbol::new(tag)).or_default().extend(values.iter().cloned());
+                }
+            }
+            for (tag, name) in file.tags.names {
+                tag_names.entry(tag).or_insert(name);
+            }
+        }
+
+        // 只計算有文本值的標籤
+        for (tag, values) in &self.tag_index {
+            let name = tag_names.remove(tag).unwrap_or_else(|| tag.clone());
+            self.tag_value_counts.insert(name.to_string(), values.len());
+        }
+
+        compute_depths(&mut self.data);
+
+        // 排序每個類型內的條目
+        for entries in self.entries.values_mut() {
+            entries.sort_by(|a, b| a.def_name.cmp(&b.def_name));
+        }
+        for patches in self.patches.values_mut() {
+            patches.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        }
+        self.search_index = SearchIndex::build(&self.entries);
+        self.name_index = build_name_index(&self.entries);
+        self.duplicates = find_duplicate_defs(&self.entries);
+        self.referenced_by = build_reverse_references(&self.entries, &self.name_index);
+
+        tracing::info!(elapsed_ms = started.elapsed().as_millis() as u64, "彙整完成");
+
+        self.base_path = base_path.to_path_buf();
+        self.xml_count = total;
+        Some(total)
+    }
+}
+
+/// 解析單一檔案：瀏覽器只處理掃描範圍內的檔案，繼承資料與標籤索引則包含所有 XML
+fn parse_file(path: &Path, rules: &DefRootRules, mode: &ScanMode) -> ParsedFile {
+    let mut errors = Vec::new();
+    let DefFile {
+        entries,
+        skipped_roots,
+    } = if mode.includes(path) {
+        collect_error(parse_defs_from_file(path, rules), &mut errors)
+    } else {
+        DefFile::default()
+    };
+    // 已被判定為無法解析的檔案不再重複解析
+    let data = if errors.is_empty() {
+        collect_error(parse_def_data(path, rules), &mut errors)
+    } else {
+        Vec::new()
+    };
+    let mut tags = if errors.is_empty() {
+        collect_error(index_tag_values(path), &mut errors)
+    } else {
+        FileTagIndex::default()
+    };
+    let patches = if errors.is_empty() && is_patch_file(path) {
+        collect_error(parse_patches_from_file(path), &mut errors)
+    } else {
+        Vec::new()
+    };
+    // 標籤索引完整讀過整個檔案，以它回報語法錯誤；錯誤之前的 Defs 仍保留
+    if let Some(error) = tags.syntax_error.take() {
+        errors.push(ParseError {
+            kind: ParseErrorKind::Syntax,
+            file: path.to_path_buf(),
+            message: format!("XML 語法錯誤（第 {} 行第 {} 欄）: {}", error.line, error.column, error.message),
+            line: Some(error.line),
+        });
+    }
+    ParsedFile {
+        entries,
+        skipped_roots,
+        data,
+        tags,
+        patches,
+        errors,
+    }
+}
+
+/// 單一檔案的解析結果；有錯誤的檔案不寫入掃描快取
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct ParsedFile {
+    entries: Vec<DefEntry>,
+    skipped_roots: Vec<String>,
+    data: Vec<DefData>,
+    tags: FileTagIndex,
+    patches: Vec<PatchEntry>,
+    #[serde(skip)]
+    pub(crate) errors: Vec<ParseError>,
+}
+
+/// 取出解析結果；若為 `ParseError` 則記錄下來，其他錯誤照舊忽略
+fn collect_error<T: Default>(
+    result: Result<T, Box<dyn std::error::Error>>,
+    errors: &mut Vec<ParseError>,
+) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            if let Ok(error) = e.downcast::<ParseError>() {
+                errors.push(*error);
+            }
+            T::default()
+        }
+    }
+}
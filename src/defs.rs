@@ -0,0 +1,611 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::intern::Symbol;
+use crate::xml_parser::read_xml_file;
+
+/// Def 瀏覽器中的一個條目（有 defName 或 `Name` 屬性的 Def）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEntry {
+    pub def_name: Symbol,                // defName；沒有時（抽象基底）為 `Name` 屬性
+    pub name: Option<Symbol>,            // 開始標籤的 `Name` 屬性
+    pub parent_name: Option<Symbol>,     // 開始標籤的 `ParentName` 屬性
+    pub has_def_name: bool,              // 有 <defName>；沒有時 def_name 取自 `Name`，不屬於 defName 的命名空間
+    pub file_path: PathBuf,
+    pub xml_content: String,
+    pub def_type: Symbol,
+    pub is_abstract: bool,
+    pub modified_at: Option<SystemTime>, // 檔案最後修改時間（掃描時讀取一次）
+    pub tex_path: Option<String>,        // 第一個 <texPath> 的值，用於貼圖預覽
+    pub label: Option<String>,           // Def 直屬的 <label>
+    pub description: Option<String>,     // Def 直屬的 <description>
+    pub tag_paths: Vec<Symbol>,          // Def 內所有元素相對於 Def 的路徑（例如 comps/li），排序且不重複
+    pub start_line: u64,                 // Def 開始標籤所在行（從 1 起算）
+    pub end_line: u64,                   // Def 結束標籤所在行
+}
+
+impl DefEntry {
+    /// Def 是否包含指定的元素（不分大小寫）
+    ///
+    /// `path` 為標籤名稱或以 `/` 分隔的路徑，可位於任何深度：`techLevel`、`statBases/Mass`、`comps/li`；
+    /// 以 `/` 開頭時只比對 Def 的直屬元素起算的路徑
+    pub fn has_tag_path(&self, path: &str) -> bool {
+        let path = path.trim();
+        let (anchored, path) = match path.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, path),
+        };
+        let query: Vec<&str> = path.split('/').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if query.is_empty() {
+            return true;
+        }
+        self.tag_paths.iter().any(|tag_path| {
+            let segments: Vec<&str> = tag_path.split('/').collect();
+            if anchored && segments.len() != query.len() {
+                return false;
+            }
+            segments.len() >= query.len()
+                && segments[segments.len() - query.len()..]
+                    .iter()
+                    .zip(&query)
+                    .all(|(segment, q)| segment.eq_ignore_ascii_case(q))
+        })
+    }
+}
+
+/// Abstract 篩選
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AbstractFilter {
+    #[default]
+    All,
+    ConcreteOnly,
+    AbstractOnly,
+}
+
+impl AbstractFilter {
+    pub const ALL: [AbstractFilter; 3] = [
+        AbstractFilter::All,
+        AbstractFilter::ConcreteOnly,
+        AbstractFilter::AbstractOnly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AbstractFilter::All => "全部",
+            AbstractFilter::ConcreteOnly => "僅具體",
+            AbstractFilter::AbstractOnly => "僅抽象",
+        }
+    }
+}
+
+/// 瀏覽器中一個類型下條目的排序方式（只影響顯示，不需重新掃描）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EntrySort {
+    #[default]
+    DefName,
+    Label,
+    FilePath,
+    Modified,
+}
+
+impl EntrySort {
+    pub const ALL: [EntrySort; 4] = [
+        EntrySort::DefName,
+        EntrySort::Label,
+        EntrySort::FilePath,
+        EntrySort::Modified,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EntrySort::DefName => "defName",
+            EntrySort::Label => "label",
+            EntrySort::FilePath => "檔案路徑",
+            EntrySort::Modified => "修改時間",
+        }
+    }
+
+    /// 依此方式排序條目索引（穩定排序）
+    ///
+    /// 沒有 label 的排在最後；檔案路徑相同時依所在行；修改時間由新到舊，相同時依 defName
+    pub fn sort(self, entries: &[DefEntry], indices: &mut [usize]) {
+        match self {
+            EntrySort::DefName => indices.sort_by(|&a, &b| entries[a].def_name.cmp(&entries[b].def_name)),
+            EntrySort::Label => indices.sort_by_cached_key(|&i| {
+                let label = entries[i].label.as_ref().map(|l| l.to_lowercase());
+                (label.is_none(), label)
+            }),
+            EntrySort::FilePath => {
+                indices.sort_by(|&a, &b| {
+                    let (a, b) = (&entries[a], &entries[b]);
+                    (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line))
+                })
+            }
+            EntrySort::Modified => indices.sort_by(|&a, &b| {
+                let (a, b) = (&entries[a], &entries[b]);
+                b.modified_at.cmp(&a.modified_at).then_with(|| a.def_name.cmp(&b.def_name))
+            }),
+        }
+    }
+}
+
+/// 條目的 defName，每行一個；`as_xml` 時包在 `<li>` 中，可直接貼入列表。
+/// 同名的 Def 只列一次，保留第一次出現的位置（依非 defName 排序時同名條目不一定相鄰）
+pub fn def_name_list(entries: &[DefEntry], indices: &[usize], as_xml: bool) -> String {
+    let mut seen = HashSet::new();
+    indices
+        .iter()
+        .map(|&idx| entries[idx].def_name.as_str())
+        .filter(|name| seen.insert(*name))
+        .map(|name| if as_xml { format!("<li>{}</li>", name) } else { name.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 判斷 `<Defs>` 下的元素是否為 Def：預設為名稱以 `Def` 結尾，另可明確允許或排除特定名稱
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefRootRules {
+    pub allow: Vec<String>, // 不以 Def 結尾但仍視為 Def 的元素名稱
+    pub deny: Vec<String>,  // 以 Def 結尾但不是 Def 的元素名稱
+    #[serde(skip)]
+    pub ignored: Vec<String>, // 使用者忽略的 Def 類型（來自設置的 ignored_def_types），不列入略過的根元素
+}
+
+impl DefRootRules {
+    pub fn is_def_root(&self, name: &str) -> bool {
+        !listed(&self.deny, name)
+            && !self.is_ignored(name)
+            && (name.ends_with("Def") || listed(&self.allow, name))
+    }
+
+    pub fn is_ignored(&self, name: &str) -> bool {
+        listed(&self.ignored, name)
+    }
+}
+
+fn listed(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.trim() == name)
+}
+
+/// 單一檔案的瀏覽器條目，以及 `<Defs>` 下因規則而略過的元素名稱
+#[derive(Debug, Clone, Default)]
+pub struct DefFile {
+    pub entries: Vec<DefEntry>,
+    pub skipped_roots: Vec<String>,
+}
+
+pub fn parse_defs_from_file(
+    path: &Path,
+    rules: &DefRootRules,
+) -> Result<DefFile, Box<dyn std::error::Error>> {
+    let _span = tracing::debug_span!("parse_defs_from_file", file = %path.display()).entered();
+    tracing::debug!("開始解析");
+
+    let content = read_xml_file(path)?;
+    let modified_at = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    Ok(parse_defs_from_str(&content, path, modified_at, rules))
+}
+
+/// 從 XML 字串解析 Defs；`path` 只用於記錄來源（貼上的內容可為任意值）
+pub fn parse_defs_from_str(
+    content: &str,
+    path: &Path,
+    modified_at: Option<SystemTime>,
+    rules: &DefRootRules,
+) -> DefFile {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+    let newlines = newline_index(content);
+
+    let mut entries = Vec::new();
+    let mut skipped_roots = Vec::new();
+    let mut buf = Vec::new();
+    let mut current_def_type: Option<Symbol> = None;
+    let mut current_def_name: Option<Symbol> = None;
+    let mut current_is_abstract = false;
+    let mut current_name: Option<Symbol> = None;
+    let mut current_parent_name: Option<Symbol> = None;
+    let mut def_depth = 0;
+    let mut inside_defs = false;
+    let mut inside_defname = false;
+    let mut inside_tex_path = false;
+    let mut current_tex_path: Option<String> = None;
+    let mut inside_label = false;
+    let mut current_label: Option<String> = None;
+    let mut inside_description = false;
+    let mut current_description: Option<String> = None;
+    let mut element_path: Vec<String> = Vec::new();
+    let mut current_tag_paths: BTreeSet<Symbol> = BTreeSet::new();
+    let mut xml_parts: Vec<String> = Vec::new();
+    let mut capturing = false;
+    let mut current_start_line = 0;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+
+                if name == "Defs" {
+                    inside_defs = true;
+                } else if inside_defs && def_depth == 0 && !rules.is_def_root(&name) {
+                    // 不是 Def 的元素：略過整個子樹，使用者忽略的類型以外記錄名稱
+                    let end = e.to_end().into_owned();
+                    let _ = reader.read_to_end_into(end.name(), &mut Vec::new());
+                    if !rules.is_ignored(&name) {
+                        skipped_roots.push(name);
+                    }
+                } else if inside_defs && def_depth == 0 {
+                    // 開始一個新的 Def
+                    current_def_type = Some(Symbol::new(&name));
+                    current_def_name = None;
+                    current_tex_path = None;
+                    current_label = None;
+                    current_description = None;
+                    element_path.clear();
+                    current_tag_paths.clear();
+                    current_is_abstract = false;
+                    current_name = None;
+                    current_parent_name = None;
+                    for attr in e.attributes().filter_map(|a| a.ok()) {
+                        let value = || Symbol::new(&String::from_utf8_lossy(&attr.value));
+                        match attr.key.as_ref() {
+                            b"Abstract" => current_is_abstract = attr.value.as_ref() == b"True",
+                            b"Name" => current_name = Some(value()),
+                            b"ParentName" => current_parent_name = Some(value()),
+                            _ => {}
+                        }
+                    }
+                    def_depth = 1;
+                    // 事件讀取後的位置在 '>' 之後，退一個位元組即為開始標籤的結尾
+                    current_start_line =
+                        line_at(&newlines, reader.buffer_position().saturating_sub(1));
+                    xml_parts.clear();
+                    capturing = true;
+                    
+                    // 記錄開始標籤
+                    let attrs: Vec<String> = e.attributes()
+                        .filter_map(|a| a.ok())
+                        .map(|attr| {
+                            format!("{}=\"{}\"",
+                                String::from_utf8_lossy(attr.key.as_ref()),
+                                String::from_utf8_lossy(&attr.value))
+                        })
+                        .collect();
+                    
+                    if attrs.is_empty() {
+                        xml_parts.push(format!("<{}>", name));
+                    } else {
+                        xml_parts.push(format!("<{} {}>", name, attrs.join(" ")));
+                    }
+                } else if def_depth > 0 {
+                    if name == "defName" {
+                        inside_defname = true;
+                    } else if name == "texPath" {
+                        inside_tex_path = true;
+                    } else if name == "label" && def_depth == 1 {
+                        inside_label = true;
+                    } else if name == "description" && def_depth == 1 {
+                        inside_description = true;
+                    }
+                    def_depth += 1;
+                    element_path.push(name.clone());
+                    current_tag_paths.insert(Symbol::new(&element_path.join("/")));
+                    
+                    if capturing {
+                        let attrs: Vec<String> = e.attributes()
+                            .filter_map(|a| a.ok())
+                            .map(|attr| {
+                                format!("{}=\"{}\"",
+                                    String::from_utf8_lossy(attr.key.as_ref()),
+                                    String::from_utf8_lossy(&attr.value))
+                            })
+                            .collect();
+                        
+                        if attrs.is_empty() {
+                            xml_parts.push(format!("<{}>", name));
+                        } else {
+                            xml_parts.push(format!("<{} {}>", name, attrs.join(" ")));
+                        }
+                    }
+                }
+            }
+            Ok(Event::Empty(ref e)) if capturing && def_depth > 0 => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                element_path.push(name.clone());
+                current_tag_paths.insert(Symbol::new(&element_path.join("/")));
+                element_path.pop();
+                let attrs: Vec<String> = e.attributes()
+                    .filter_map(|a| a.ok())
+                    .map(|attr| {
+                        format!("{}=\"{}\"",
+                            String::from_utf8_lossy(attr.key.as_ref()),
+                            String::from_utf8_lossy(&attr.value))
+                    })
+                    .collect();
+                
+                if attrs.is_empty() {
+                    xml_parts.push(format!("<{} />", name));
+                } else {
+                    xml_parts.push(format!("<{} {} />", name, attrs.join(" ")));
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if inside_defname {
+                    if let Ok(text) = e.unescape() {
+                        current_def_name = Some(Symbol::new(text.trim()));
+                    }
+                }
+                if inside_tex_path && current_tex_path.is_none() {
+                    if let Ok(text) = e.unescape() {
+                        current_tex_path = Some(text.trim().to_string());
+                    }
+                }
+                if inside_label {
+                    if let Ok(text) = e.unescape() {
+                        current_label = Some(text.trim().to_string());
+                    }
+                }
+                if inside_description {
+                    if let Ok(text) = e.unescape() {
+                        current_description = Some(text.trim().to_string());
+                    }
+                }
+                if capturing {
+                    if let Ok(text) = e.unescape() {
+                        let trimmed = text.trim();
+                        if !trimmed.is_empty() {
+                            xml_parts.push(trimmed.to_string());
+                        }
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+
+                if name == "defName" {
+                    inside_defname = false;
+                } else if name == "texPath" {
+                    inside_tex_path = false;
+                } else if name == "label" {
+                    inside_label = false;
+                } else if name == "description" {
+                    inside_description = false;
+                }
+
+                if def_depth > 0 {
+                    if capturing {
+                        xml_parts.push(format!("</{}>", name));
+                    }
+                    
+                    def_depth -= 1;
+                    element_path.pop();
+
+                    if def_depth == 0 {
+                        // Def 結束；沒有 defName 的抽象基底以 Name 屬性為名稱
+                        let has_def_name = current_def_name.is_some();
+                        let def_name = current_def_name.take().or_else(|| current_name.clone());
+                        if let (Some(def_type), Some(def_name)) = (&current_def_type, def_name) {
+                            entries.push(DefEntry {
+                                def_name,
+                                name: current_name.take(),
+                                parent_name: current_parent_name.take(),
+                                has_def_name,
+                                file_path: path.to_path_buf(),
+                                xml_content: format_xml(&xml_parts.join("")),
+                                def_type: def_type.clone(),
+                                is_abstract: current_is_abstract,
+                                modified_at,
+                                tex_path: current_tex_path.take(),
+                                label: current_label.take(),
+                                description: current_description.take(),
+                                tag_paths: std::mem::take(&mut current_tag_paths).into_iter().collect(),
+                                start_line: current_start_line,
+                                end_line: line_at(
+                                    &newlines,
+                                    reader.buffer_position().saturating_sub(1),
+                                ),
+                            });
+                        }
+                        current_def_type = None;
+                        current_def_name = None;
+                        capturing = false;
+                    }
+                }
+
+                if name == "Defs" {
+                    inside_defs = false;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!(position = reader.buffer_position(), error = %e, "XML 解析錯誤");
+                break;
+            }
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    DefFile {
+        entries,
+        skipped_roots,
+    }
+}
+
+/// 內容中每個 `\n` 的位元組位置，用於將讀取位置轉為行號
+fn newline_index(content: &str) -> Vec<u64> {
+    content
+        .bytes()
+        .enumerate()
+        .filter(|(_, b)| *b == b'\n')
+        .map(|(i, _)| i as u64)
+        .collect()
+}
+
+/// 位元組位置所在的行號（從 1 起算）
+fn line_at(newlines: &[u64], position: u64) -> u64 {
+    newlines.partition_point(|&nl| nl < position) as u64 + 1
+}
+
+/// 尋找 texPath 對應的貼圖檔：由 Def 檔案所在目錄往上尋找 Textures 目錄，
+/// 依序嘗試單一貼圖、多方向貼圖（_south）與隨機貼圖資料夾中的第一張
+///
+/// 本體的貼圖打包在資源檔中，只有模組的貼圖找得到
+pub fn find_texture(def_file: &Path, tex_path: &str) -> Option<PathBuf> {
+    texture_dirs(def_file).find_map(|textures| {
+        let base = textures.join(tex_path);
+        if let Some(file) = texture_files(&base).into_iter().find(|file| file.is_file()) {
+            return Some(file);
+        }
+        let mut pngs: Vec<PathBuf> = std::fs::read_dir(&base)
+            .ok()?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("png"))
+            .collect();
+        pngs.sort();
+        pngs.into_iter().next()
+    })
+}
+
+/// `find_texture` 嘗試過的路徑，找不到貼圖時列給使用者檢查拼字；
+/// 只包含確實存在的 Textures 目錄，隨機貼圖資料夾以 `*.png` 表示
+pub fn texture_candidates(def_file: &Path, tex_path: &str) -> Vec<PathBuf> {
+    texture_dirs(def_file)
+        .flat_map(|textures| {
+            let base = textures.join(tex_path);
+            let mut files = texture_files(&base);
+            files.push(base.join("*.png"));
+            files
+        })
+        .collect()
+}
+
+/// Def 檔案上層目錄中的 Textures 目錄，由近而遠
+fn texture_dirs(def_file: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    const MAX_LEVELS: usize = 6;
+
+    def_file
+        .ancestors()
+        .skip(1)
+        .take(MAX_LEVELS)
+        .map(|dir| dir.join("Textures"))
+        .filter(|dir| dir.is_dir())
+}
+
+/// 單一貼圖與多方向貼圖的檔名
+fn texture_files(base: &Path) -> Vec<PathBuf> {
+    ["", "_south", "_north", "_east"]
+        .iter()
+        .map(|suffix| {
+            let mut file = base.as_os_str().to_os_string();
+            file.push(format!("{}.png", suffix));
+            PathBuf::from(file)
+        })
+        .collect()
+}
+
+// 簡單格式化 XML 使其更易讀
+pub fn format_xml(xml: &str) -> String {
+    let mut result = String::new();
+    let mut indent_level: usize = 0;
+    let mut chars = xml.chars().peekable();
+    let mut after_text = false; // 追蹤是否剛輸出了文本內容
+    
+    while let Some(ch) = chars.next() {
+        if ch == '<' {
+            // 收集完整的標籤
+            let mut tag = String::from('<');
+            let mut is_closing = false;
+            let mut is_self_closing = false;
+            
+            // 檢查是否是結束標籤
+            if chars.peek() == Some(&'/') {
+                is_closing = true;
+            }
+            
+            // 收集標籤內容；引號內的屬性值可能包含未跳脫的 '>'，不視為標籤結尾
+            let mut quote: Option<char> = None;
+            while let Some(&next_ch) = chars.peek() {
+                tag.push(chars.next().unwrap());
+                match quote {
+                    Some(q) if next_ch == q => quote = None,
+                    Some(_) => continue,
+                    None if next_ch == '"' || next_ch == '\'' => quote = Some(next_ch),
+                    None => {}
+                }
+                if quote.is_none() && next_ch == '>' {
+                    // 檢查是否是自閉合標籤
+                    if tag.ends_with("/>") {
+                        is_self_closing = true;
+                    }
+                    break;
+                }
+            }
+            
+            // 輸出標籤
+            if is_closing {
+                // 結束標籤
+                if after_text {
+                    // 如果前面有文本內容，標籤直接跟在後面（同一行）
+                    result.push_str(&tag);
+                    result.push('\n');
+                    after_text = false;
+                } else {
+                    // 否則，先減少縮排再輸出
+                    indent_level = indent_level.saturating_sub(1);
+                    result.push_str(&"  ".repeat(indent_level));
+                    result.push_str(&tag);
+                    result.push('\n');
+                }
+            } else if is_self_closing {
+                // 自閉合標籤
+                result.push_str(&"  ".repeat(indent_level));
+                result.push_str(&tag);
+                result.push('\n');
+                after_text = false;
+            } else {
+                // 開始標籤
+                result.push_str(&"  ".repeat(indent_level));
+                result.push_str(&tag);
+                
+                // 檢查下一個字符是否是文本內容（不是 '<'）
+                if let Some(&next_ch) = chars.peek() {
+                    if next_ch != '<' {
+                        // 收集文本內容直到下一個標籤
+                        let mut text = String::new();
+                        while let Some(&ch) = chars.peek() {
+                            if ch == '<' {
+                                break;
+                            }
+                            text.push(chars.next().unwrap());
+                        }
+                        
+                        let trimmed = text.trim();
+                        if !trimmed.is_empty() {
+                            result.push_str(trimmed);
+                            after_text = true;
+                        }
+                        // 文本後不增加縮排，因為下一個應該是結束標籤
+                    } else {
+                        // 下一個是標籤，換行並增加縮排
+                        result.push('\n');
+                        indent_level += 1;
+                        after_text = false;
+                    }
+                } else {
+                    result.push('\n');
+                    indent_level += 1;
+                    after_text = false;
+                }
+            }
+        }
+    }
+    
+    result
+}
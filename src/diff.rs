@@ -0,0 +1,78 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::defs::DefEntry;
+use crate::intern::Symbol;
+
+/// 兩次掃描之間的 Def 變更；名稱格式為 `DefType/defName`
+#[derive(Debug, Clone, Default)]
+pub struct DefChangeset {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>, // 兩次都存在但 XML 內容不同
+}
+
+impl DefChangeset {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// 匯出為 Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for (heading, names) in [
+            ("Added", &self.added),
+            ("Removed", &self.removed),
+            ("Modified", &self.modified),
+        ] {
+            markdown.push_str(&format!("## {}\n", heading));
+            if names.is_empty() {
+                markdown.push_str("_（無）_\n");
+            }
+            for name in names {
+                markdown.push_str(&format!("- {}\n", name));
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+}
+
+/// 比較兩次掃描的瀏覽器條目
+pub fn compute_diff(
+    before: &BTreeMap<Symbol, Vec<DefEntry>>,
+    after: &BTreeMap<Symbol, Vec<DefEntry>>,
+) -> DefChangeset {
+    let before = index_entries(before);
+    let after = index_entries(after);
+    let mut changeset = DefChangeset::default();
+
+    for (key, xml) in &after {
+        match before.get(key) {
+            None => changeset.added.push(key.clone()),
+            Some(old_xml) if old_xml != xml => changeset.modified.push(key.clone()),
+            Some(_) => {}
+        }
+    }
+    for key in before.keys() {
+        if !after.contains_key(key) {
+            changeset.removed.push(key.clone());
+        }
+    }
+
+    changeset.added.sort();
+    changeset.removed.sort();
+    changeset.modified.sort();
+    changeset
+}
+
+/// `DefType/defName` -> XML；同名的 Def 以最後一個為準（與遊戲載入時的覆蓋行為一致）
+fn index_entries(entries: &BTreeMap<Symbol, Vec<DefEntry>>) -> HashMap<String, &str> {
+    entries
+        .iter()
+        .flat_map(|(def_type, entries)| {
+            entries
+                .iter()
+                .map(move |e| (format!("{}/{}", def_type, e.def_name), e.xml_content.as_str()))
+        })
+        .collect()
+}
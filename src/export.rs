@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::defs::DefEntry;
+use crate::intern::Symbol;
+use crate::merge::{node_to_xml, parse_xml_nodes, DefData, XmlNode};
+
+/// 將繼承關係輸出為 Graphviz DOT 格式
+///
+/// 每個 Def 為一個節點，依 `def_type` 分群；邊由父類指向子類，抽象定義以菱形表示
+pub fn defs_to_dot(all_defs: &HashMap<Symbol, DefData>) -> String {
+    let mut by_type: BTreeMap<&str, Vec<&DefData>> = BTreeMap::new();
+    for def in all_defs.values() {
+        by_type.entry(def.def_type.as_str()).or_default().push(def);
+    }
+
+    let mut dot = String::from("digraph inheritance {\n");
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str("  node [shape=box, fontsize=10];\n\n");
+
+    for (index, (def_type, defs)) in by_type.iter_mut().enumerate() {
+        defs.sort_by(|a, b| a.def_name.cmp(&b.def_name));
+
+        dot.push_str(&format!("  subgraph cluster_{} {{\n", index));
+        dot.push_str(&format!("    label=\"{}\";\n", escape_dot(def_type)));
+        for def in defs.iter() {
+            if def.is_abstract {
+                dot.push_str(&format!("    \"{}\" [shape=diamond];\n", escape_dot(&def.def_name)));
+            } else {
+                dot.push_str(&format!("    \"{}\";\n", escape_dot(&def.def_name)));
+            }
+        }
+        dot.push_str("  }\n\n");
+    }
+
+    let mut edges: Vec<(&str, &str)> = all_defs
+        .values()
+        .filter_map(|def| {
+            def.parent_name
+                .as_deref()
+                .map(|parent| (parent, def.def_name.as_str()))
+        })
+        .collect();
+    edges.sort();
+    for (parent, child) in edges {
+        dot.push_str(&format!("  \"{}\" -> \"{}\";\n", escape_dot(parent), escape_dot(child)));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// 選取 Def 的 xpath，例如 `Defs/ThingDef[defName="Gun_Revolver"]`，可直接貼到 PatchOperation 的 `<xpath>`
+pub fn def_xpath(def_type: &str, def_name: &str) -> String {
+    format!("Defs/{}[defName=\"{}\"]", def_type, def_name)
+}
+
+/// 將多個 Def 合併為一份可直接存檔的 XML：包在同一個 `<Defs>` 中，每個 Def 前以註解標示來源檔案
+///
+/// 來源路徑相對於 `base_path`（不在其下時為完整路徑）
+pub fn entries_to_defs_xml(entries: &[&DefEntry], base_path: &Path) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<Defs>\n");
+    for entry in entries {
+        let file = entry.file_path.strip_prefix(base_path).unwrap_or(&entry.file_path);
+        // 註解中不能出現 `--`
+        let file = file.display().to_string().replace("--", "- -");
+        xml.push_str(&format!("  <!-- {} -->\n", file));
+        for line in entry.xml_content.lines() {
+            xml.push_str("  ");
+            xml.push_str(line);
+            xml.push('\n');
+        }
+    }
+    xml.push_str("</Defs>\n");
+    xml
+}
+
+/// 產生將節點加入 Def 的 `PatchOperationAdd`
+///
+/// `node_path` 為節點所在的父節點路徑（Def 根元素之下，例如 `["comps"]`），空的時候加在 Def 根元素下
+pub fn node_to_patch_add(def_name: &str, def_type: &str, node_path: &[String], node: &XmlNode) -> String {
+    let mut xpath = format!("/{}", def_xpath(def_type, def_name));
+    for tag in node_path {
+        xpath.push('/');
+        xpath.push_str(tag);
+    }
+    let value: Vec<String> = node_to_xml(node).lines().map(|line| format!("    {}", line)).collect();
+    format!(
+        "<Operation Class=\"PatchOperationAdd\">\n  <xpath>{}</xpath>\n  <value>\n{}\n  </value>\n</Operation>",
+        xpath,
+        value.join("\n")
+    )
+}
+
+/// 將 Def 輸出為 CSV：每個條目一列，欄位為 defName、檔案路徑與 `columns` 中各標籤路徑的值
+///
+/// 標籤路徑以 `/` 分隔子標籤（例如 `statBases/MarketValue`），只取 Def 本身寫出的值（不含繼承）；
+/// 列表標籤以 `;` 串接各 `<li>` 的值，找不到的標籤為空白
+pub fn entries_to_csv(entries: &[DefEntry], columns: &[String]) -> String {
+    let mut header = vec!["defName".to_string(), "file".to_string()];
+    header.extend(columns.iter().cloned());
+    let mut csv = csv_line(&header);
+
+    for entry in entries {
+        let nodes = parse_xml_nodes(&entry.xml_content);
+        let def_children = nodes.first().map(|root| root.children.as_slice()).unwrap_or_default();
+        let mut fields = vec![entry.def_name.to_string(), entry.file_path.display().to_string()];
+        fields.extend(columns.iter().map(|column| tag_path_value(def_children, column)));
+        csv.push_str(&csv_line(&fields));
+    }
+    csv
+}
+
+fn tag_path_value(nodes: &[XmlNode], path: &str) -> String {
+    let mut current = nodes;
+    let mut found = None;
+    for tag in path.split('/').map(str::trim).filter(|t| !t.is_empty()) {
+        let Some(node) = current.iter().find(|node| node.tag == tag) else {
+            return String::new();
+        };
+        found = Some(node);
+        current = &node.children;
+    }
+    let Some(node) = found else {
+        return String::new();
+    };
+    match &node.text {
+        Some(text) => text.to_string(),
+        None => node
+            .children
+            .iter()
+            .filter(|child| child.tag == "li")
+            .filter_map(|li| li.text.as_deref())
+            .collect::<Vec<_>>()
+            .join(";"),
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    format!("{}\n", fields.join(","))
+}
+
+/// CSV 欄位：含逗號、引號或換行時以引號包住，內部的引號重複一次
+pub fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
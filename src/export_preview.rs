@@ -0,0 +1,127 @@
+use eframe::egui;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::file_writer::{export_file, PlannedWrite, SharedWriter};
+use crate::settings::AppSettings;
+use crate::status::StatusSink;
+
+/// 等待確認的一個輸出檔案
+struct PendingWrite {
+    source: String, // 發出匯出的分頁，寫入後的通知以此為標題
+    planned: PlannedWrite,
+    content: Vec<u8>,
+}
+
+/// 所有分頁共用的匯出入口
+///
+/// 開啟「僅預覽」或以 `--dry-run` 啟動時，匯出先加入確認視窗，列出將寫入的檔案與大小，使用者確認後才寫入
+#[derive(Clone)]
+pub struct Exporter {
+    writer: SharedWriter,
+    settings: Arc<Mutex<AppSettings>>,
+    status: StatusSink,
+    pending: Arc<Mutex<Vec<PendingWrite>>>,
+}
+
+impl Exporter {
+    pub fn new(writer: SharedWriter, settings: Arc<Mutex<AppSettings>>, status: StatusSink) -> Self {
+        Self {
+            writer,
+            settings,
+            status,
+            pending: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.writer.is_dry_run()
+    }
+
+    /// 匯出一個檔案並回傳狀態列訊息；需要確認時加入確認視窗，回傳等待確認的訊息
+    pub fn export(&self, source: &str, path: &Path, content: Vec<u8>) -> String {
+        let preview = self.is_dry_run() || self.settings.lock().is_ok_and(|s| s.preview_exports);
+        if !preview {
+            return export_file(self.writer.as_ref(), path, &content);
+        }
+        let planned = PlannedWrite::new(path, &content);
+        if let Ok(mut pending) = self.pending.lock() {
+            // 同一個檔案再次匯出時以新的內容為準
+            pending.retain(|p| p.planned.path != planned.path);
+            pending.push(PendingWrite {
+                source: source.to_string(),
+                planned,
+                content,
+            });
+        }
+        format!("等待確認：將匯出至 {}", path.display())
+    }
+
+    /// 確認視窗：有等待確認的檔案時顯示，每幀呼叫
+    pub fn show(&self, ctx: &egui::Context) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut approved = None;
+        egui::Window::new("💾 確認匯出")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if self.is_dry_run() {
+                    ui.label("試執行模式：確認後只會在終端列出這些檔案，不會實際寫入");
+                } else {
+                    ui.label("以下檔案將被寫入，確認後才會實際寫入:");
+                }
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("export_preview_grid").striped(true).show(ui, |ui| {
+                        for write in pending.iter() {
+                            ui.label(write.planned.path.display().to_string());
+                            ui.label(format!("{} 位元組", write.planned.size));
+                            if write.planned.exists {
+                                ui.colored_label(ui.visuals().warn_fg_color, "覆寫");
+                            } else {
+                                ui.label("新建");
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                let total: usize = pending.iter().map(|w| w.planned.size).sum();
+                ui.label(format!("共 {} 個檔案，{} 位元組", pending.len(), total));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 寫入").clicked() {
+                        approved = Some(true);
+                    }
+                    if ui.button("取消").clicked() {
+                        approved = Some(false);
+                    }
+                });
+            });
+
+        match approved {
+            Some(true) => {
+                for write in pending.drain(..) {
+                    let message = export_file(self.writer.as_ref(), &write.planned.path, &write.content);
+                    if let Ok(mut status) = self.status.lock() {
+                        status.notify(&write.source, message);
+                    }
+                }
+            }
+            Some(false) => {
+                let count = pending.len();
+                pending.clear();
+                if let Ok(mut status) = self.status.lock() {
+                    status.notify("匯出", format!("已取消匯出 {} 個檔案", count));
+                }
+            }
+            None => {}
+        }
+    }
+}
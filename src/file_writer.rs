@@ -0,0 +1,99 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 寫入檔案的方式：實際寫入，或只列出將寫入的檔案（`--dry-run`）
+pub trait FileWriter: Send + Sync {
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()>;
+
+    /// 是否為試執行（不實際寫入）
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+}
+
+/// 所有分頁共用的寫入方式
+pub type SharedWriter = Arc<dyn FileWriter>;
+
+/// 依命令列參數選擇寫入方式
+pub fn shared_writer(dry_run: bool) -> SharedWriter {
+    if dry_run {
+        Arc::new(DryRunWriter::default())
+    } else {
+        Arc::new(RealWriter)
+    }
+}
+
+/// 直接寫入檔案
+#[derive(Debug, Default)]
+pub struct RealWriter;
+
+impl FileWriter for RealWriter {
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        std::fs::write(path, content)
+    }
+}
+
+/// 試執行時將寫入的一個檔案
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedWrite {
+    pub path: PathBuf,
+    pub size: usize,
+    pub exists: bool, // 檔案已存在（會被覆寫）
+}
+
+impl PlannedWrite {
+    pub fn new(path: &Path, content: &[u8]) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size: content.len(),
+            exists: path.exists(),
+        }
+    }
+}
+
+/// 不寫入任何檔案，只將路徑與大小輸出到標準輸出並記錄下來
+#[derive(Debug, Default)]
+pub struct DryRunWriter {
+    planned: Mutex<Vec<PlannedWrite>>,
+}
+
+impl DryRunWriter {
+    /// 目前為止將寫入的檔案，依呼叫順序
+    pub fn planned(&self) -> Vec<PlannedWrite> {
+        self.planned.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+}
+
+impl FileWriter for DryRunWriter {
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let planned = PlannedWrite::new(path, content);
+        println!(
+            "[dry-run] {} {} ({} bytes)",
+            if planned.exists { "modify" } else { "create" },
+            planned.path.display(),
+            planned.size
+        );
+        if let Ok(mut list) = self.planned.lock() {
+            list.push(planned);
+        }
+        Ok(())
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+/// 匯出單一檔案並回傳狀態列訊息
+pub fn export_file(writer: &dyn FileWriter, path: &Path, content: &[u8]) -> String {
+    match writer.write(path, content) {
+        Ok(()) if writer.is_dry_run() => format!(
+            "試執行：將匯出至 {}（{} 位元組），未實際寫入",
+            path.display(),
+            content.len()
+        ),
+        Ok(()) => format!("✅ 已匯出至 {}", path.display()),
+        Err(e) => format!("錯誤: 匯出失敗: {}", e),
+    }
+}
@@ -1,168 +1,324 @@
 use eframe::egui;
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
-};
-use walkdir::WalkDir;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 
+use crate::database::SharedDatabase;
+use crate::command_palette::Command;
+use crate::scanner::ScanRequest;
 use crate::settings::AppSettings;
-use crate::xml_parser::extract_tag_values;
+use crate::status::StatusSink;
+use crate::widgets::empty_state;
 
-pub struct SearchResult {
-    pub values: Vec<String>,
-    pub xml_count: usize,
+/// 跨重新啟動保存的標籤查找器介面狀態
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FinderUiState {
+    tag_name: String,
+    case_fold_results: bool,
 }
 
+#[derive(Clone)]
 pub struct TagFinderTab {
     tag_name: String,
     search_path: String,
     results: Vec<String>,
-    status_message: String,
-    is_searching: bool,
+    status: StatusSink,
+    database: SharedDatabase,
+    scan_request: ScanRequest,
+    last_generation: u64, // 上次查詢時的資料庫版本
+    has_searched: bool, // 是否執行過搜尋（用於顯示「沒有找到結果」）
     last_tag_name: String,
     last_search_path: String,
-    search_results: Arc<Mutex<Option<SearchResult>>>,
-    cancel_flag: Arc<AtomicBool>,
     settings: Arc<Mutex<AppSettings>>,
     initialized: bool,
+    pinned_searches: Vec<TagFinderTabSnapshot>, // 釘選的查詢結果，方便比較不同標籤
+    range_min: String,       // 數值範圍篩選的下限（空白表示不限）
+    range_max: String,       // 數值範圍篩選的上限（空白表示不限）
+    show_non_numeric: bool,  // 範圍篩選時是否保留非數值的結果
+    case_fold_results: bool, // 不分大小寫合併結果（例如 Steel 與 steel 算一個）
+}
+
+/// 結果中超過此比例可解析為數值時，顯示範圍篩選並依數值排序
+const NUMERIC_RATIO: f64 = 0.5;
+
+/// 結果是否以數值為主
+fn is_mostly_numeric(results: &[String]) -> bool {
+    let numeric = results.iter().filter(|v| v.trim().parse::<f64>().is_ok()).count();
+    !results.is_empty() && numeric as f64 / results.len() as f64 > NUMERIC_RATIO
+}
+
+/// 釘選時凍結的查詢結果
+#[derive(Clone)]
+pub struct TagFinderTabSnapshot {
+    tag_name: String,
+    search_path: String,
+    results: Vec<String>,
+}
+
+impl TagFinderTabSnapshot {
+    fn capture(tab: &TagFinderTab) -> Self {
+        // 複製整個分頁狀態後只保留查詢相關的部分，不包含其他釘選
+        let TagFinderTab {
+            tag_name,
+            search_path,
+            results,
+            ..
+        } = tab.clone();
+        Self {
+            tag_name,
+            search_path,
+            results,
+        }
+    }
 }
 
 impl TagFinderTab {
-    pub fn new(settings: Arc<Mutex<AppSettings>>) -> Self {
+    pub fn new(
+        settings: Arc<Mutex<AppSettings>>,
+        database: SharedDatabase,
+        status: StatusSink,
+        scan_request: ScanRequest,
+    ) -> Self {
         Self {
             tag_name: String::new(),
             search_path: String::new(),
             results: Vec::new(),
-            status_message: String::new(),
-            is_searching: false,
+            status,
+            database,
+            scan_request,
+            last_generation: 0,
+            has_searched: false,
             last_tag_name: String::new(),
             last_search_path: String::new(),
-            search_results: Arc::new(Mutex::new(None)),
-            cancel_flag: Arc::new(AtomicBool::new(false)),
             settings,
             initialized: false,
+            pinned_searches: Vec::new(),
+            range_min: String::new(),
+            range_max: String::new(),
+            show_non_numeric: false,
+            case_fold_results: false,
         }
     }
 
-    pub fn search_xml_files(&mut self, ctx: egui::Context) {
-        // 取消之前的搜尋
-        self.cancel_flag.store(true, Ordering::Relaxed);
+    /// 保存介面狀態
+    pub fn save_state(&self) -> FinderUiState {
+        FinderUiState {
+            tag_name: self.tag_name.clone(),
+            case_fold_results: self.case_fold_results,
+        }
+    }
+
+    /// 還原介面狀態；掃描完成後會自動重新查詢
+    pub fn load_state(&mut self, state: FinderUiState) {
+        self.last_tag_name = state.tag_name.clone();
+        self.tag_name = state.tag_name;
+        self.case_fold_results = state.case_fold_results;
+    }
 
+    /// 從共享掃描產生的標籤索引中查詢
+    pub fn search_xml_files(&mut self) {
         self.results.clear();
-        self.status_message = "搜尋中...".to_string();
-        self.is_searching = true;
+        self.has_searched = true;
 
         if self.tag_name.is_empty() {
-            self.status_message = "錯誤: 請輸入標籤名稱".to_string();
-            self.is_searching = false;
+            self.set_status("錯誤: 請輸入標籤名稱");
             return;
         }
 
         if self.search_path.is_empty() {
-            self.status_message = "錯誤: 請選擇搜尋路徑".to_string();
-            self.is_searching = false;
+            self.set_status("錯誤: 請選擇搜尋路徑");
             return;
         }
 
-        let path = PathBuf::from(&self.search_path);
-        if !path.exists() {
-            self.status_message = format!("錯誤: 路徑不存在: {}", self.search_path);
-            self.is_searching = false;
+        let Ok(db) = self.database.read() else {
             return;
-        }
-
-        let tag_name = self.tag_name.clone();
-        let search_results = self.search_results.clone();
-
-        // 創建新的取消旗標
-        let cancel_flag = Arc::new(AtomicBool::new(false));
-        self.cancel_flag = cancel_flag.clone();
-
-        // 在後台執行緒中執行搜尋
-        std::thread::spawn(move || {
-            // 收集所有 XML 檔案路徑
-            let xml_files: Vec<PathBuf> = WalkDir::new(&path)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.file_type().is_file()
-                        && e.path().extension().map_or(false, |ext| ext == "xml")
-                })
-                .map(|e| e.path().to_path_buf())
-                .collect();
-
-            let xml_count = xml_files.len();
-
-            // 使用 rayon 平行處理 XML 檔案，並檢查取消旗標
-            let values: HashSet<String> = xml_files
-                .par_iter()
-                .filter(|_| !cancel_flag.load(Ordering::Relaxed))
-                .filter_map(|path| extract_tag_values(path, &tag_name).ok())
-                .flatten()
-                .collect();
-
-            // 如果被取消，不儲存結果
-            if cancel_flag.load(Ordering::Relaxed) {
-                return;
+        };
+        self.last_generation = db.generation;
+
+        // 索引尚未建立，等掃描完成後自動重新查詢
+        if db.generation == 0 {
+            drop(db);
+            if let Ok(mut status) = self.status.lock() {
+                status.set_loading("標籤查找器", "搜尋中...");
             }
+            return;
+        }
 
-            // 排序結果
-            let mut sorted_values: Vec<String> = values.into_iter().collect();
-            sorted_values.sort();
+        self.results = db.tag_values(&self.tag_name, self.case_fold_results);
+        let xml_count = db.xml_count;
+        drop(db);
+
+        // 數值依大小排序（字串排序會讓 1000.5 排在 900 前面），非數值放在最後
+        if is_mostly_numeric(&self.results) {
+            self.results.sort_by(|a, b| {
+                match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a.total_cmp(&b),
+                    (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+                    (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                }
+            });
+        }
 
-            // 儲存結果
-            if let Ok(mut result) = search_results.lock() {
-                *result = Some(SearchResult {
-                    values: sorted_values,
-                    xml_count,
-                });
-            }
+        self.set_status(format!(
+            "掃描了 {} 個 XML 檔案，找到 {} 個唯一值",
+            xml_count,
+            self.results.len()
+        ));
+    }
 
-            // 請求重繪 UI
-            ctx.request_repaint();
-        });
+    /// 套用數值範圍篩選後的結果；非數值的結果依 `show_non_numeric` 保留或隱藏
+    fn filtered_results(&self) -> Vec<&String> {
+        let min = self.range_min.trim().parse::<f64>().ok();
+        let max = self.range_max.trim().parse::<f64>().ok();
+        if (min.is_none() && max.is_none()) || !is_mostly_numeric(&self.results) {
+            return self.results.iter().collect();
+        }
+        self.results
+            .iter()
+            .filter(|value| match value.trim().parse::<f64>() {
+                Ok(v) => min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max),
+                Err(_) => self.show_non_numeric,
+            })
+            .collect()
     }
 
-    fn check_search_results(&mut self) {
-        if let Ok(mut result) = self.search_results.lock() {
-            if let Some(search_result) = result.take() {
-                self.results = search_result.values;
-                self.status_message = format!(
-                    "掃描了 {} 個 XML 檔案，找到 {} 個唯一值",
-                    search_result.xml_count,
-                    self.results.len()
+    /// 數值範圍篩選的輸入列
+    fn range_ui(&mut self, ui: &mut egui::Ui) {
+        let invalid = |text: &str| !text.trim().is_empty() && text.trim().parse::<f64>().is_err();
+        ui.horizontal(|ui| {
+            ui.label("數值範圍:");
+            for (text, hint) in [(&mut self.range_min, "最小值"), (&mut self.range_max, "最大值")] {
+                let is_invalid = invalid(text);
+                let response = ui.add(
+                    egui::TextEdit::singleline(text)
+                        .hint_text(hint)
+                        .desired_width(80.0),
                 );
-                self.is_searching = false;
+                if is_invalid {
+                    response.on_hover_text("不是有效的數值，將被忽略");
+                }
+            }
+            ui.checkbox(&mut self.show_non_numeric, "顯示非數值");
+            if (!self.range_min.is_empty() || !self.range_max.is_empty())
+                && ui.small_button("✖").on_hover_text("清除範圍").clicked()
+            {
+                self.range_min.clear();
+                self.range_max.clear();
             }
+        });
+    }
+
+    /// 請求掃描協調器重新掃描，完成後會自動重新查詢
+    pub fn rescan(&mut self) {
+        self.scan_request.request();
+    }
+
+    /// 命令面板中的標籤查找器命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![Command::new("重新掃描 Defs", |tab: &mut Self, _| tab.rescan())]
+    }
+
+    /// 右側的釘選列表；點擊時將該次結果還原到分頁中
+    fn pinned_ui(&mut self, ui: &mut egui::Ui) {
+        let mut restore = None;
+        let mut remove = None;
+        egui::SidePanel::right("finder_pinned")
+            .resizable(true)
+            .default_width(180.0)
+            .show_inside(ui, |ui| {
+                ui.strong("📌 釘選的結果");
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_salt("finder_pinned_list")
+                    .show(ui, |ui| {
+                        for (index, pinned) in self.pinned_searches.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                let selected = pinned.tag_name == self.tag_name
+                                    && pinned.results == self.results;
+                                let label = format!("{} ({})", pinned.tag_name, pinned.results.len());
+                                if ui
+                                    .selectable_label(selected, label)
+                                    .on_hover_text(&pinned.search_path)
+                                    .clicked()
+                                {
+                                    restore = Some(index);
+                                }
+                                if ui.small_button("✖").on_hover_text("取消釘選").clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                        }
+                    });
+            });
+
+        if let Some(pinned) = restore.and_then(|index| self.pinned_searches.get(index)) {
+            self.tag_name = pinned.tag_name.clone();
+            self.last_tag_name = pinned.tag_name.clone();
+            self.results = pinned.results.clone();
+            self.has_searched = true;
+        }
+        if let Some(index) = remove {
+            self.pinned_searches.remove(index);
+        }
+    }
+
+    /// 包含目前輸入的標籤名稱（不含完全相符者），依名稱排序
+    fn suggestions(&self) -> Vec<(String, usize)> {
+        const MAX_SUGGESTIONS: usize = 10;
+
+        let query = self.tag_name.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
         }
+        let Ok(db) = self.database.read() else {
+            return Vec::new();
+        };
+
+        let mut suggestions: Vec<(String, usize)> = db
+            .tag_value_counts
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.to_lowercase();
+                name != query && name.contains(&query)
+            })
+            .map(|(name, count)| (name.clone(), *count))
+            .collect();
+        // 前綴相符者優先
+        suggestions.sort_by_key(|(name, _)| (!name.to_lowercase().starts_with(&query), name.clone()));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+    fn set_status(&self, message: impl Into<String>) {
+        if let Ok(mut status) = self.status.lock() {
+            status.set_message("標籤查找器", message);
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
         // 每次更新時檢查設置是否變更
-        let mut should_search = false;
         if let Ok(settings) = self.settings.lock() {
-            if settings.base_path != self.search_path {
-                self.search_path = settings.base_path.clone();
+            let base_path = settings.active_base_path();
+            if base_path != self.search_path {
+                self.search_path = base_path;
                 self.last_search_path = self.search_path.clone();
                 self.initialized = true;
-                // 如果有標籤名稱,標記需要重新搜尋
-                if !self.tag_name.is_empty() && !self.search_path.is_empty() {
-                    should_search = true;
-                }
             }
         }
-        
-        // 在鎖釋放後執行搜尋
-        if should_search {
-            self.search_xml_files(ctx.clone());
+
+        // 資料庫更新後重新查詢
+        let (generation, refreshing) = self
+            .database
+            .read()
+            .map_or((0, false), |db| (db.generation, db.refreshing));
+        if generation != self.last_generation && !self.tag_name.is_empty() {
+            self.search_xml_files();
         }
 
-        // 檢查後台搜尋結果
-        self.check_search_results();
+        if !self.pinned_searches.is_empty() {
+            self.pinned_ui(ui);
+        }
 
         // 頂部控制面板
         ui.horizontal(|ui| {
@@ -171,64 +327,124 @@ impl TagFinderTab {
             // 檢測輸入變化 - 設為唯讀
             ui.add_enabled(false, egui::TextEdit::singleline(&mut self.search_path));
 
-            // 狀態訊息
-            if !self.status_message.is_empty() {
-                ui.colored_label(
-                    if self.is_searching {
-                        egui::Color32::from_rgb(255, 165, 0)
-                    } else {
-                        egui::Color32::from_rgb(0, 200, 0)
-                    },
-                    &self.status_message,
-                );
+            // 背景掃描時仍顯示舊結果
+            if refreshing {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "🔄 更新中…");
             }
         });
 
         ui.separator();
 
+        let mut picked: Option<String> = None;
         ui.horizontal(|ui| {
             ui.label("🔍");
             let response = ui.text_edit_singleline(&mut self.tag_name);
 
+            // 自動完成：依已掃描的標籤名稱提示，並顯示唯一值數量
+            let suggestions = self.suggestions();
+            let popup_id = ui.make_persistent_id("tag_autocomplete");
+            if response.has_focus() && !suggestions.is_empty() {
+                ui.memory_mut(|m| m.open_popup(popup_id));
+            }
+            egui::popup_below_widget(
+                ui,
+                popup_id,
+                &response,
+                egui::PopupCloseBehavior::CloseOnClickOutside,
+                |ui| {
+                    ui.set_min_width(200.0);
+                    for (name, count) in &suggestions {
+                        if ui
+                            .selectable_label(false, format!("{} ({} 個唯一值)", name, count))
+                            .clicked()
+                        {
+                            picked = Some(name.clone());
+                            ui.memory_mut(|m| m.close_popup());
+                        }
+                    }
+                },
+            );
+
             // 檢測輸入變化
             if response.changed() && self.tag_name != self.last_tag_name {
                 self.last_tag_name = self.tag_name.clone();
                 if !self.tag_name.is_empty() && !self.search_path.is_empty() {
-                    self.search_xml_files(ctx.clone());
+                    self.search_xml_files();
                 }
             }
+
+            if ui
+                .checkbox(&mut self.case_fold_results, "不分大小寫")
+                .on_hover_text("將只有大小寫不同的值（例如 Steel 與 steel）合併為一個，顯示排序最前面的寫法\nRimWorld 的 Def 參照區分大小寫，合併只影響這裡的結果")
+                .changed()
+                && !self.tag_name.is_empty()
+                && !self.search_path.is_empty()
+            {
+                self.search_xml_files();
+            }
         });
+
+        if let Some(name) = picked {
+            self.tag_name = name;
+            self.last_tag_name = self.tag_name.clone();
+            if !self.search_path.is_empty() {
+                self.search_xml_files();
+            }
+        }
         
         ui.separator();
 
+        if generation == 0 {
+            empty_state(ui, &self.settings, refreshing);
+            return;
+        }
+
         // 結果顯示區域
         if !self.results.is_empty() {
+            if is_mostly_numeric(&self.results) {
+                self.range_ui(ui);
+            }
+            let results: Vec<String> = self.filtered_results().into_iter().cloned().collect();
+            let excluded = self.results.len() - results.len();
+
             // 複製按鈕
             ui.horizontal(|ui| {
                 ui.label(format!("找到 {} 個唯一值:", self.results.len()));
+                if excluded > 0 {
+                    ui.weak(format!("（範圍外 {} 個已隱藏）", excluded));
+                }
                 
                 if ui.button("📋 複製結果").clicked() {
-                    ui.output_mut(|o| o.copied_text = self.results.join(", "));
+                    ui.output_mut(|o| o.copied_text = results.join(", "));
+                }
+                if ui
+                    .button("📌 釘選")
+                    .on_hover_text("保留目前的結果，之後可在右側切換回來比較")
+                    .clicked()
+                {
+                    let snapshot = TagFinderTabSnapshot::capture(self);
+                    self.pinned_searches.retain(|p| p.tag_name != snapshot.tag_name);
+                    self.pinned_searches.push(snapshot);
                 }
             });
 
             ui.separator();
 
             const MAX_DISPLAY: usize = 100;
-            let display_results = if self.results.len() > MAX_DISPLAY {
-                &self.results[..MAX_DISPLAY]
+            let display_results = if results.len() > MAX_DISPLAY {
+                &results[..MAX_DISPLAY]
             } else {
-                &self.results[..]
+                &results[..]
             };
 
-            let comma_separated = if self.results.len() > MAX_DISPLAY {
+            let comma_separated = if results.len() > MAX_DISPLAY {
                 format!("{}, ...", display_results.join(", "))
             } else {
                 display_results.join(", ")
             };
 
-            if self.results.len() > MAX_DISPLAY {
-                ui.label(format!("（顯示前 {} 項，共 {} 項）", MAX_DISPLAY, self.results.len()));
+            if results.len() > MAX_DISPLAY {
+                ui.label(format!("（顯示前 {} 項，共 {} 項）", MAX_DISPLAY, results.len()));
             }
 
             // 顯示逗號分隔的結果
@@ -237,8 +453,10 @@ impl TagFinderTab {
                 .show(ui, |ui| {
                     ui.label(&comma_separated);
                 });
-        } else if !self.is_searching && !self.status_message.is_empty() {
+        } else if self.has_searched {
             ui.label("沒有找到結果");
+        } else {
+            ui.weak("輸入標籤名稱（例如 defName、label、thingCategories）查詢所有出現過的值");
         }
     }
 }
@@ -0,0 +1,116 @@
+use std::io;
+use std::path::Path;
+
+/// 以開頭位元組判斷的文字編碼
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8Bom,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Unknown, // 沒有 BOM 且不是合法的 UTF-8（例如 Latin-1）
+}
+
+impl TextEncoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8Bom => "UTF-8（含 BOM）",
+            TextEncoding::Utf8 => "UTF-8（無 BOM）",
+            TextEncoding::Utf16Le => "UTF-16 LE",
+            TextEncoding::Utf16Be => "UTF-16 BE",
+            TextEncoding::Unknown => "未知（不是合法的 UTF-8）",
+        }
+    }
+}
+
+/// 檔案使用的換行字元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Crlf,
+    Lf,
+    Cr,
+    Mixed,
+    None, // 只有一行
+}
+
+impl LineEnding {
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Crlf => "CRLF（Windows）",
+            LineEnding::Lf => "LF（Unix）",
+            LineEnding::Cr => "CR（舊版 Mac）",
+            LineEnding::Mixed => "混合",
+            LineEnding::None => "無換行",
+        }
+    }
+}
+
+/// 檔案的大小、編碼、換行與字元數，用於排查無法解析的檔案
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingInfo {
+    pub size: u64,
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    pub char_count: usize, // 依偵測到的編碼解碼後的字元數（無法解碼的位元組各算一個）
+}
+
+/// 讀取檔案並偵測編碼資訊
+pub fn detect_encoding_info(path: &Path) -> io::Result<EncodingInfo> {
+    Ok(encoding_info(&std::fs::read(path)?))
+}
+
+/// 依前 4 個位元組判斷編碼（BOM，或 UTF-16 無 BOM 時的 `<?`），再計算換行與字元數
+pub fn encoding_info(bytes: &[u8]) -> EncodingInfo {
+    let encoding = match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => TextEncoding::Utf8Bom,
+        [0xFF, 0xFE, ..] | [b'<', 0, b'?', 0, ..] => TextEncoding::Utf16Le,
+        [0xFE, 0xFF, ..] | [0, b'<', 0, b'?', ..] => TextEncoding::Utf16Be,
+        _ if std::str::from_utf8(bytes).is_ok() => TextEncoding::Utf8,
+        _ => TextEncoding::Unknown,
+    };
+
+    let text: String = match encoding {
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let units = bytes.chunks_exact(2).map(|pair| match encoding {
+                TextEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    };
+    // BOM 不算字元
+    let char_count = text.chars().filter(|c| *c != '\u{FEFF}').count();
+
+    EncodingInfo {
+        size: bytes.len() as u64,
+        encoding,
+        line_ending: line_ending(&text),
+        char_count,
+    }
+}
+
+fn line_ending(text: &str) -> LineEnding {
+    let (mut crlf, mut lf, mut cr) = (0, 0, 0);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                crlf += 1;
+            }
+            '\r' => cr += 1,
+            '\n' => lf += 1,
+            _ => {}
+        }
+    }
+    match (crlf > 0, lf > 0, cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Crlf,
+        (false, true, false) => LineEnding::Lf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    }
+}
@@ -0,0 +1,296 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use walkdir::WalkDir;
+
+use crate::defs::DefRootRules;
+use crate::intern::Symbol;
+use crate::merge::{expand_def, parse_def_data, DefData, MergeOrder, XmlNode};
+use crate::xml_parser::extract_tag_values;
+
+/// 可選擇啟用的 DLC（packageId, 名稱）
+pub const DLC_PACKAGES: [(&str, &str); 5] = [
+    ("ludeon.rimworld.royalty", "Royalty"),
+    ("ludeon.rimworld.ideology", "Ideology"),
+    ("ludeon.rimworld.biotech", "Biotech"),
+    ("ludeon.rimworld.anomaly", "Anomaly"),
+    ("ludeon.rimworld.odyssey", "Odyssey"),
+];
+
+/// 本體的 packageId，永遠視為已啟用
+const CORE_PACKAGE: &str = "ludeon.rimworld";
+
+/// 最終載入結果的設定：載入順序與啟用的 DLC
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameViewConfig {
+    pub load_order: Vec<String>, // 依載入順序排列的本體、DLC 與模組目錄
+    pub dlcs: Vec<String>,       // 啟用的 DLC packageId
+}
+
+impl GameViewConfig {
+    /// 快取鍵：設定與 Def 根元素規則都相同時可直接沿用上次建置的結果
+    pub fn cache_key(&self, rules: &DefRootRules) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        rules.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// 遊戲最終載入的一個 Def 與其來源
+#[derive(Debug, Clone)]
+pub struct LoadedDef {
+    pub def_name: String,
+    pub def_type: Symbol,
+    pub xml: String,             // 展開繼承並套用 MayRequire 後的 XML
+    pub defined_by: String,      // 最後定義此 Def 的來源（生效的定義）
+    pub overridden: Vec<String>, // 被覆蓋的較早定義，依載入順序
+    pub patches: Vec<PathBuf>,   // xpath 指向此 Def 的 Patch 檔案（尚未套用）
+    pub file_path: PathBuf,
+}
+
+/// 依設定建置的最終載入結果
+#[derive(Debug, Default)]
+pub struct GameView {
+    pub cache_key: u64,
+    pub defs: BTreeMap<Symbol, Vec<LoadedDef>>, // DefType -> 依 defName 排序的 Defs
+    pub removed_by_may_require: usize,          // 因 MayRequire 不成立而未載入的 Def 數量
+}
+
+impl GameView {
+    pub fn total(&self) -> usize {
+        self.defs.values().map(|v| v.len()).sum()
+    }
+}
+
+/// 載入順序中的一個來源（本體、DLC 或模組目錄）
+struct Source {
+    name: String,
+    def_files: Vec<PathBuf>,
+    patch_files: Vec<PathBuf>,
+}
+
+impl Source {
+    fn read(root: &Path) -> Self {
+        let about = root.join("About").join("About.xml");
+        let name = extract_tag_values(&about, "name")
+            .ok()
+            .and_then(|v| v.into_iter().next())
+            .or_else(|| root.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| root.display().to_string());
+
+        let mut def_files = Vec::new();
+        let mut patch_files = Vec::new();
+        for entry in WalkDir::new(root).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("xml") {
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            let in_folder = |folder: &str| relative.components().any(|c| c.as_os_str() == folder);
+            if in_folder("Defs") {
+                def_files.push(path.to_path_buf());
+            } else if in_folder("Patches") {
+                patch_files.push(path.to_path_buf());
+            }
+        }
+        def_files.sort();
+        patch_files.sort();
+
+        Self {
+            name,
+            def_files,
+            patch_files,
+        }
+    }
+
+    fn package_id(root: &Path) -> Option<String> {
+        let about = root.join("About").join("About.xml");
+        extract_tag_values(&about, "packageId").ok()?.into_iter().next()
+    }
+}
+
+/// 目錄下所有含 About/About.xml 的子目錄，依 Core、官方 DLC、其他模組（依名稱）排列
+pub fn default_load_order(base_path: &Path) -> Vec<String> {
+    let Ok(read_dir) = std::fs::read_dir(base_path) else {
+        return Vec::new();
+    };
+    let mut roots: Vec<(usize, PathBuf)> = read_dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join("About").join("About.xml").is_file())
+        .map(|root| {
+            let rank = match Source::package_id(&root).map(|id| id.to_lowercase()) {
+                Some(id) if id == CORE_PACKAGE => 0,
+                Some(id) => DLC_PACKAGES
+                    .iter()
+                    .position(|(dlc, _)| *dlc == id)
+                    .map_or(DLC_PACKAGES.len() + 1, |i| i + 1),
+                None => DLC_PACKAGES.len() + 1,
+            };
+            (rank, root)
+        })
+        .collect();
+    roots.sort();
+    roots.into_iter().map(|(_, root)| root.display().to_string()).collect()
+}
+
+/// 依設定計算遊戲最終載入的所有 Def：後載入的定義覆蓋先前的、展開繼承、
+/// 移除 MayRequire 不成立的 Def 與節點，並列出指向每個 Def 的 Patch
+///
+/// 取消時回傳 None；`progress` 以 (已處理, 總數) 回報檔案解析進度
+pub fn build_game_view(
+    config: &GameViewConfig,
+    rules: &DefRootRules,
+    cancel: &AtomicBool,
+    progress: impl Fn(usize, usize) + Sync,
+) -> Option<GameView> {
+    let roots: Vec<PathBuf> = config.load_order.iter().map(PathBuf::from).collect();
+    let sources: Vec<Source> = roots.iter().map(|root| Source::read(root)).collect();
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    // 載入清單中的模組都視為已啟用
+    let mut active: HashSet<String> = config.dlcs.iter().map(|id| id.to_lowercase()).collect();
+    active.insert(CORE_PACKAGE.to_string());
+    active.extend(
+        roots
+            .iter()
+            .filter_map(|root| Source::package_id(root))
+            .map(|id| id.to_lowercase()),
+    );
+
+    let files: Vec<(usize, &PathBuf)> = sources
+        .iter()
+        .enumerate()
+        .flat_map(|(index, source)| source.def_files.iter().map(move |file| (index, file)))
+        .collect();
+    let total = files.len();
+    let processed = AtomicUsize::new(0);
+
+    let parsed: Vec<(usize, Vec<DefData>)> = files
+        .par_iter()
+        .map(|(index, file)| {
+            if cancel.load(Ordering::Relaxed) {
+                return (*index, Vec::new());
+            }
+            let defs = parse_def_data(file, rules).unwrap_or_default();
+            progress(processed.fetch_add(1, Ordering::Relaxed) + 1, total);
+            (*index, defs)
+        })
+        .collect();
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    // 依載入順序（par_iter 的 collect 保留順序）合併，後定義的覆蓋先前的
+    let mut all_defs: HashMap<Symbol, DefData> = HashMap::new();
+    let mut definitions: HashMap<Symbol, Vec<usize>> = HashMap::new();
+    let mut removed_by_may_require = 0;
+    for (index, defs) in parsed {
+        for mut def in defs {
+            if !attributes_satisfied(&def.attributes, &active) {
+                removed_by_may_require += 1;
+                continue;
+            }
+            def.raw_nodes = filter_nodes(&def.raw_nodes, &active);
+            definitions.entry(def.def_name.clone()).or_default().push(index);
+            all_defs.insert(def.def_name.clone(), def);
+        }
+    }
+
+    let patch_targets = patch_targets(&sources);
+
+    let mut view = GameView {
+        cache_key: config.cache_key(rules),
+        removed_by_may_require,
+        ..GameView::default()
+    };
+    for def in all_defs.values().filter(|d| !d.is_abstract) {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        let sources_of = definitions.get(&def.def_name).map(Vec::as_slice).unwrap_or_default();
+        let Some((&last, earlier)) = sources_of.split_last() else {
+            continue;
+        };
+        view.defs.entry(def.def_type.clone()).or_default().push(LoadedDef {
+            def_name: def.def_name.to_string(),
+            def_type: def.def_type.clone(),
+            xml: expand_def(def, &all_defs, MergeOrder::RootFirst).xml,
+            defined_by: sources[last].name.clone(),
+            overridden: earlier.iter().map(|&i| sources[i].name.clone()).collect(),
+            patches: patch_targets.get(def.def_name.as_str()).cloned().unwrap_or_default(),
+            file_path: def.file_path.clone(),
+        });
+    }
+    for defs in view.defs.values_mut() {
+        defs.sort_by(|a, b| a.def_name.cmp(&b.def_name));
+    }
+    Some(view)
+}
+
+/// MayRequire（全部需要）與 MayRequireAnyOf（任一即可）是否成立
+fn attributes_satisfied(attributes: &[(Symbol, Symbol)], active: &HashSet<String>) -> bool {
+    let ids = |value: &str| -> Vec<String> {
+        value
+            .split(',')
+            .map(|id| id.trim().to_lowercase())
+            .filter(|id| !id.is_empty())
+            .collect()
+    };
+    attributes.iter().all(|(key, value)| match key.as_str() {
+        "MayRequire" => ids(value).iter().all(|id| active.contains(id)),
+        "MayRequireAnyOf" => ids(value).iter().any(|id| active.contains(id)),
+        _ => true,
+    })
+}
+
+/// 移除 MayRequire 不成立的節點
+fn filter_nodes(nodes: &[XmlNode], active: &HashSet<String>) -> Vec<XmlNode> {
+    nodes
+        .iter()
+        .filter(|node| attributes_satisfied(&node.attributes, active))
+        .map(|node| XmlNode {
+            children: filter_nodes(&node.children, active),
+            ..node.clone()
+        })
+        .collect()
+}
+
+/// defName -> 指向它的 Patch 檔案；只比對 xpath 中的 `defName="…"`，不實際套用 Patch
+fn patch_targets(sources: &[Source]) -> HashMap<String, Vec<PathBuf>> {
+    let mut targets: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for file in sources.iter().flat_map(|s| &s.patch_files) {
+        let Ok(xpaths) = extract_tag_values(file, "xpath") else {
+            continue;
+        };
+        let mut names: Vec<String> = xpaths.iter().flat_map(|xpath| xpath_def_names(xpath)).collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            targets.entry(name).or_default().push(file.clone());
+        }
+    }
+    targets
+}
+
+/// 從 xpath 中取出 `defName="X"` 或 `defName='X'` 的所有 X
+fn xpath_def_names(xpath: &str) -> Vec<String> {
+    let compact: String = xpath.chars().filter(|c| !c.is_whitespace()).collect();
+    compact
+        .split("defName=")
+        .skip(1)
+        .filter_map(|rest| {
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let rest = &rest[1..];
+            rest.find(quote).map(|end| rest[..end].to_string())
+        })
+        .collect()
+}
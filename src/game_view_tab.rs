@@ -0,0 +1,405 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::browser::open_file_with_default_app;
+use crate::command_palette::Command;
+use crate::defs::DefRootRules;
+use crate::game_view::{build_game_view, default_load_order, GameView, GameViewConfig, DLC_PACKAGES};
+use crate::intern::Symbol;
+use crate::settings::AppSettings;
+use crate::status::StatusSink;
+
+/// 狀態列中建置工作的名稱
+const BUILD_TASK: &str = "建置最終載入結果";
+
+/// 保留的建置結果數量（依設定快取）
+const CACHE_LIMIT: usize = 4;
+
+/// 進行中的建置；完成時結果為 Some(None) 表示已取消
+struct BuildJob {
+    cancel: Arc<AtomicBool>,
+    result: Arc<Mutex<Option<Option<GameView>>>>,
+}
+
+/// 最終載入結果分頁：依載入順序與 DLC 模擬遊戲實際載入的 Defs
+pub struct GameViewTab {
+    settings: Arc<Mutex<AppSettings>>,
+    status: StatusSink,
+    build: Option<BuildJob>,
+    cache: HashMap<u64, Arc<GameView>>, // 設定的快取鍵 -> 建置結果
+    cache_order: Vec<u64>,              // 最舊的在前
+    view: Option<Arc<GameView>>,
+    search_query: String,
+    selected_def_type: Option<Symbol>,
+    selected_def: Option<usize>,
+}
+
+impl GameViewTab {
+    pub fn new(settings: Arc<Mutex<AppSettings>>, status: StatusSink) -> Self {
+        Self {
+            settings,
+            status,
+            build: None,
+            cache: HashMap::new(),
+            cache_order: Vec::new(),
+            view: None,
+            search_query: String::new(),
+            selected_def_type: None,
+            selected_def: None,
+        }
+    }
+
+    fn config(&self) -> (GameViewConfig, DefRootRules) {
+        self.settings
+            .lock()
+            .map(|s| (s.game_view.clone(), s.def_root_rules()))
+            .unwrap_or_default()
+    }
+
+    fn set_status(&self, message: impl Into<String>) {
+        if let Ok(mut status) = self.status.lock() {
+            status.notify("最終載入結果", message);
+        }
+    }
+
+    fn show_view(&mut self, view: Arc<GameView>) {
+        self.view = Some(view);
+        self.selected_def_type = None;
+        self.selected_def = None;
+    }
+
+    /// 建置目前設定的結果；設定與快取中的相同時直接沿用
+    pub fn build(&mut self, ctx: &egui::Context) {
+        if self.build.is_some() {
+            return;
+        }
+        let (config, rules) = self.config();
+        if config.load_order.is_empty() {
+            self.set_status("⚠ 載入順序是空的，請先加入本體、DLC 或模組目錄");
+            return;
+        }
+        if let Some(view) = self.cache.get(&config.cache_key(&rules)).cloned() {
+            self.show_view(view);
+            self.set_status("設定未變更，使用快取的結果");
+            return;
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            status.begin_task(BUILD_TASK);
+            status.set_loading("最終載入結果", "正在建置...");
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        let result = Arc::new(Mutex::new(None));
+        self.build = Some(BuildJob {
+            cancel: cancel.clone(),
+            result: result.clone(),
+        });
+
+        let status = self.status.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let view = build_game_view(&config, &rules, &cancel, |done, total| {
+                if let Ok(mut status) = status.lock() {
+                    status.set_progress(BUILD_TASK, done, total);
+                }
+            });
+            if let Ok(mut status) = status.lock() {
+                status.end_task(BUILD_TASK);
+            }
+            if let Ok(mut result) = result.lock() {
+                *result = Some(view);
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// 命令面板中的最終載入結果命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![
+            Command::new("建置", |tab: &mut Self, ctx| tab.build(ctx)),
+            Command::new("取消建置", |tab: &mut Self, _| tab.cancel()),
+        ]
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(build) = &self.build {
+            build.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn collect_build(&mut self) {
+        let Some(build) = &self.build else {
+            return;
+        };
+        let Some(result) = build.result.lock().ok().and_then(|mut r| r.take()) else {
+            return;
+        };
+        self.build = None;
+
+        let Some(view) = result else {
+            self.set_status("已取消建置");
+            return;
+        };
+        let view = Arc::new(view);
+        self.set_status(format!(
+            "✅ 建置完成：{} 個 Defs，{} 個因 MayRequire 未載入",
+            view.total(),
+            view.removed_by_may_require
+        ));
+
+        self.cache.insert(view.cache_key, view.clone());
+        self.cache_order.retain(|key| *key != view.cache_key);
+        self.cache_order.push(view.cache_key);
+        if self.cache_order.len() > CACHE_LIMIT {
+            let oldest = self.cache_order.remove(0);
+            self.cache.remove(&oldest);
+        }
+        self.show_view(view);
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        self.collect_build();
+
+        let (config, rules) = self.config();
+        let up_to_date = self.view.as_ref().is_some_and(|v| v.cache_key == config.cache_key(&rules));
+
+        egui::CollapsingHeader::new("⚙ 載入設定")
+            .id_salt("game_view_config")
+            .default_open(self.view.is_none())
+            .show(ui, |ui| self.config_ui(ui));
+
+        ui.horizontal(|ui| {
+            if self.build.is_some() {
+                ui.add(egui::Spinner::new());
+                if ui.button("⏹ 取消").clicked() {
+                    self.cancel();
+                }
+            } else if ui
+                .button("🔨 建置")
+                .on_hover_text("依載入順序解析所有 Defs；設定未變更時使用快取")
+                .clicked()
+            {
+                self.build(ctx);
+            }
+            if up_to_date
+                && self.build.is_none()
+                && ui
+                    .button("🔄 重新建置")
+                    .on_hover_text("忽略快取重新解析（檔案內容有變更時使用）")
+                    .clicked()
+            {
+                self.cache.remove(&config.cache_key(&rules));
+                self.build(ctx);
+            }
+            if self.view.is_some() && !up_to_date {
+                ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "⚠ 設定已變更，請重新建置");
+            }
+        });
+
+        ui.separator();
+
+        let Some(view) = self.view.clone() else {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() * 0.3);
+                ui.heading("尚未建置");
+                ui.weak("設定載入順序與 DLC 後按「🔨 建置」，模擬遊戲最終載入的 Defs");
+            });
+            return;
+        };
+        self.view_ui(ui, &view);
+    }
+
+    fn config_ui(&mut self, ui: &mut egui::Ui) {
+        let Ok(mut settings) = self.settings.lock() else {
+            return;
+        };
+        let base_path = settings.active_base_path();
+        let config = &mut settings.game_view;
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("DLC:");
+            for (package_id, name) in DLC_PACKAGES {
+                let mut enabled = config.dlcs.iter().any(|id| id == package_id);
+                if ui.checkbox(&mut enabled, name).changed() {
+                    config.dlcs.retain(|id| id != package_id);
+                    if enabled {
+                        config.dlcs.push(package_id.to_string());
+                    }
+                    changed = true;
+                }
+            }
+        });
+
+        ui.label("載入順序（後載入的定義覆蓋先前的）:");
+        let mut move_up = None;
+        let mut remove = None;
+        for (index, path) in config.load_order.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}.", index + 1));
+                if ui.add_enabled(index > 0, egui::Button::new("⬆").small()).clicked() {
+                    move_up = Some(index);
+                }
+                if ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+                ui.label(path);
+            });
+        }
+        if let Some(index) = move_up {
+            config.load_order.swap(index - 1, index);
+            changed = true;
+        }
+        if let Some(index) = remove {
+            config.load_order.remove(index);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("➕ 加入目錄").clicked() {
+                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                    config.load_order.push(path.display().to_string());
+                    changed = true;
+                }
+            }
+            if ui
+                .add_enabled(!base_path.is_empty(), egui::Button::new("📂 從工作目錄加入"))
+                .on_hover_text("加入工作目錄下所有含 About/About.xml 的資料夾（Core、DLC、其他模組依序排列）")
+                .clicked()
+            {
+                for path in default_load_order(Path::new(&base_path)) {
+                    if !config.load_order.contains(&path) {
+                        config.load_order.push(path);
+                    }
+                }
+                changed = true;
+            }
+            if ui.button("🗑 清除").clicked() {
+                config.load_order.clear();
+                changed = true;
+            }
+        });
+
+        if changed {
+            settings.save();
+        }
+    }
+
+    fn view_ui(&mut self, ui: &mut egui::Ui, view: &GameView) {
+        ui.horizontal_top(|ui| {
+            ui.allocate_ui_with_layout(
+                egui::vec2(240.0, ui.available_height()),
+                egui::Layout::top_down(egui::Align::Min),
+                |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("🔍");
+                        ui.text_edit_singleline(&mut self.search_query);
+                    });
+                    ui.label(format!("共 {} 個 Defs", view.total()));
+                    ui.separator();
+
+                    let query = self.search_query.to_lowercase();
+                    egui::ScrollArea::vertical()
+                        .id_salt("game_view_list")
+                        .auto_shrink([false; 2])
+                        .show(ui, |ui| {
+                            for (def_type, defs) in &view.defs {
+                                let matching: Vec<usize> = defs
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(_, d)| {
+                                        query.is_empty() || d.def_name.to_lowercase().contains(&query)
+                                    })
+                                    .map(|(index, _)| index)
+                                    .collect();
+                                if matching.is_empty() {
+                                    continue;
+                                }
+
+                                let is_selected = self.selected_def_type.as_ref() == Some(def_type);
+                                if ui
+                                    .selectable_label(is_selected, format!("{} ({})", def_type, matching.len()))
+                                    .clicked()
+                                {
+                                    self.selected_def_type =
+                                        if is_selected { None } else { Some(def_type.clone()) };
+                                    self.selected_def = None;
+                                }
+                                if is_selected {
+                                    ui.indent(("game_view_indent", def_type.as_str()), |ui| {
+                                        for index in matching {
+                                            let selected = self.selected_def == Some(index);
+                                            if ui
+                                                .selectable_label(selected, &defs[index].def_name)
+                                                .clicked()
+                                            {
+                                                self.selected_def = Some(index);
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                        });
+                },
+            );
+
+            ui.separator();
+
+            ui.vertical(|ui| {
+                let selected = self
+                    .selected_def_type
+                    .as_ref()
+                    .and_then(|def_type| view.defs.get(def_type))
+                    .and_then(|defs| defs.get(self.selected_def?));
+                let Some(def) = selected else {
+                    ui.label("請選擇一個 Def");
+                    return;
+                };
+
+                ui.heading(&def.def_name);
+
+                // 來源：生效的定義、被覆蓋的定義、相關的 Patch
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("📦 定義於:");
+                    ui.strong(&def.defined_by);
+                    if !def.overridden.is_empty() {
+                        ui.separator();
+                        ui.label("覆蓋:");
+                        for name in &def.overridden {
+                            ui.label(egui::RichText::new(name).strikethrough().weak());
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("檔案:");
+                    if ui.link(def.file_path.display().to_string()).clicked() {
+                        open_file_with_default_app(&def.file_path);
+                    }
+                });
+                if !def.patches.is_empty() {
+                    ui.collapsing(format!("🩹 相關 Patch ({}，未套用)", def.patches.len()), |ui| {
+                        for patch in &def.patches {
+                            if ui.link(patch.display().to_string()).clicked() {
+                                open_file_with_default_app(patch);
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+                egui::ScrollArea::both()
+                    .id_salt("game_view_xml")
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut def.xml.as_str())
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+            });
+        });
+    }
+}
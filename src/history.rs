@@ -0,0 +1,161 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+
+use crate::journal::{Journal, JournalEntry, RestoreWarning};
+use crate::status::StatusSink;
+
+/// 操作記錄視窗：列出寫入檔案的操作，可復原任一筆
+pub struct HistoryWindow {
+    open: bool,
+    journal: Option<Journal>,
+    entries: Vec<JournalEntry>,
+    warnings: Vec<RestoreWarning>, // 上一次復原時略過的檔案
+    status: StatusSink,
+}
+
+impl HistoryWindow {
+    pub fn new(status: StatusSink) -> Self {
+        Self {
+            open: false,
+            journal: Journal::open_default(),
+            entries: Vec::new(),
+            warnings: Vec::new(),
+            status,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.reload();
+    }
+
+    fn reload(&mut self) {
+        self.entries = self.journal.as_ref().map(Journal::entries).unwrap_or_default();
+    }
+
+    /// 復原最新的一筆操作
+    pub fn undo_last(&mut self) {
+        self.reload();
+        match self.entries.first().map(|e| e.id.clone()) {
+            Some(id) => self.restore(&id),
+            None => self.notify("沒有可復原的操作".to_string()),
+        }
+    }
+
+    fn restore(&mut self, id: &str) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let Some(entry) = self.entries.iter().find(|e| e.id == id) else {
+            return;
+        };
+        let operation = entry.operation.clone();
+        let total = entry.files.len();
+
+        let message = match journal.restore(id) {
+            Ok(warnings) if warnings.is_empty() => {
+                format!("已復原「{}」（{} 個檔案）", operation, total)
+            }
+            Ok(warnings) => {
+                let message = format!(
+                    "⚠ 已復原「{}」，{} 個檔案因已被修改而略過",
+                    operation,
+                    warnings.len()
+                );
+                self.warnings = warnings;
+                self.open = true;
+                message
+            }
+            Err(e) => format!("錯誤: 無法復原「{}」: {}", operation, e),
+        };
+        self.notify(message);
+        self.reload();
+    }
+
+    fn notify(&self, message: String) {
+        if let Ok(mut status) = self.status.lock() {
+            status.notify("操作記錄", message);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        let mut restore_id = None;
+        egui::Window::new("🕘 操作記錄")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                if self.journal.is_none() {
+                    ui.label("無法取得設置目錄，操作記錄無法使用");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!self.entries.is_empty(), egui::Button::new("↩ 復原上一次操作"))
+                        .clicked()
+                    {
+                        restore_id = self.entries.first().map(|e| e.id.clone());
+                    }
+                    if ui.button("🔄 重新整理").clicked() {
+                        self.reload();
+                    }
+                });
+
+                if !self.warnings.is_empty() {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "以下檔案未復原:");
+                    for warning in &self.warnings {
+                        ui.label(format!("• {}: {}", warning.path.display(), warning.message));
+                    }
+                }
+
+                ui.separator();
+
+                if self.entries.is_empty() {
+                    ui.weak("沒有寫入檔案的操作記錄");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("history_entries")
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("history_grid").striped(true).show(ui, |ui| {
+                            for entry in &self.entries {
+                                ui.label(&entry.operation);
+                                ui.label(format_time(entry.created_at));
+                                ui.label(format!("{} 個檔案", entry.files.len())).on_hover_text(
+                                    entry
+                                        .files
+                                        .iter()
+                                        .map(|f| f.path.display().to_string())
+                                        .collect::<Vec<_>>()
+                                        .join("\n"),
+                                );
+                                if ui.button("↩ 復原").clicked() {
+                                    restore_id = Some(entry.id.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+        self.open = open;
+
+        if let Some(id) = restore_id {
+            self.warnings.clear();
+            self.restore(&id);
+        }
+    }
+}
+
+fn format_time(seconds: u64) -> String {
+    DateTime::from_timestamp(seconds as i64, 0)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
@@ -1,81 +1,223 @@
 use eframe::egui;
-use quick_xml::events::Event;
-use quick_xml::Reader;
-use rayon::prelude::*;
-use std::collections::{BTreeMap, HashMap};
-use std::fs;
-use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use lru::LruCache;
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use walkdir::WalkDir;
+use crate::database::SharedDatabase;
+use crate::command_palette::Command;
+use crate::export::{defs_to_dot, node_to_patch_add};
+use crate::export_preview::Exporter;
+use crate::inheritance_logic::{diff_from_parent, FieldChange, FieldDiff};
+use crate::intern::Symbol;
+use crate::merge::{
+    compute_depths, concrete_descendant_counts, expand_def, merged_nodes, parse_def_data_from_str, DefData, MergeOrder,
+    XmlNode,
+};
+use crate::popout::Popouts;
+use crate::scanner::ScanRequest;
+use crate::search::{fuzzy_match, FuzzyMatch};
 use crate::settings::AppSettings;
+use crate::status::StatusSink;
+use crate::widgets::{empty_state, highlighted_text};
 
 pub struct InheritanceTab {
     base_directory: String,
-    all_defs: HashMap<String, DefData>,    // 所有 Defs（包括 Abstract 和具體的）
+    database: SharedDatabase,               // 所有 Defs（包括 Abstract 和具體的）
+    last_generation: u64,                   // 上次看到的資料庫版本
     selected_def_name: String,
     search_query: String,
-    is_loading: bool,
-    status_message: String,
+    fuzzy_search: bool, // 搜尋以模糊比對 defName，結果依相符程度排序
+    scan_request: ScanRequest,
+    status: StatusSink,
+    exporter: Exporter,
     expanded_xml: String,
     inheritance_chain: Vec<String>,
+    field_diffs: Option<Vec<FieldDiff>>, // 與父類比較的結果，沒有父類時為 None
+    view: DetailView,
+    node_tree: Option<NodeTree>,         // 展開後的節點樹，供節點樹檢視使用
+    adhoc_def: Option<DefData>,          // 展開中的 Def 不在資料庫中時（快速分析），保留以便重新展開
+    expansion_cache: LruCache<Symbol, (Vec<String>, String)>, // defName -> (繼承鏈, 展開的 XML)，重新掃描或變更合併順序時清空
     settings: Arc<Mutex<AppSettings>>,
     initialized: bool,
-    auto_scanned: bool,    // 記錄是否已自動掃描
+    pending_restore: Option<String>, // 上次結束時選取的 Def，等掃描完成後還原
+    popouts: Popouts,
+    xml_search_open: bool,   // Ctrl+F 開啟的展開 XML 搜尋列
+    xml_search: String,
+    match_lines: Vec<usize>, // 包含搜尋字串的行號（從 0 開始）
+    current_match: usize,
+    scroll_to_match: bool,   // 下一幀捲動到目前的相符行
+    def_type_filter: Option<Symbol>, // 只列出此類型的 Defs
+    sort: DefListSort,
+    descendant_counts: HashMap<Symbol, usize>, // Abstract Def -> 具體子孫數量
+    counts_key: Option<(u64, Option<Symbol>)>, // 計算子孫數量時的資料庫版本與類型篩選
+    paste_open: bool,            // 顯示「貼上 XML」視窗
+    paste_input: String,
+    paste_error: Option<String>,
 }
 
-#[derive(Debug, Clone)]
-struct DefData {
-    def_name: String,        // defName 或 Name (for Abstract)
-    parent_name: Option<String>,
-    #[allow(dead_code)]
-    file_path: PathBuf,
-    #[allow(dead_code)]
-    xml_content: String,
-    #[allow(dead_code)]
-    is_abstract: bool,
-    def_type: String,        // ThingDef, RecipeDef, etc.
-    raw_nodes: Vec<XmlNode>, // 原始 XML 節點結構
+/// 展開結果快取的條目數量
+const EXPANSION_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(50).unwrap();
+
+/// Def 列表的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DefListSort {
+    #[default]
+    Name,
+    Descendants,
+}
+
+impl DefListSort {
+    pub const ALL: [DefListSort; 2] = [DefListSort::Name, DefListSort::Descendants];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DefListSort::Name => "名稱",
+            DefListSort::Descendants => "子孫數量",
+        }
+    }
+}
+
+/// 右側顯示的內容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DetailView {
+    #[default]
+    Xml,  // 展開的 XML
+    Diff, // 與父類比較
+    Tree, // 節點樹，可將節點複製為 Patch
+}
+
+/// 合併整條繼承鏈後的節點
+struct NodeTree {
+    def_type: Symbol,
+    nodes: Vec<XmlNode>,
+}
+
+/// 左側 Def 列表的一列
+struct ListedDef {
+    def_name: Symbol,
+    depth: u8,
+    descendants: Option<usize>, // 只有 Abstract Def 顯示子孫數量
+    matched: Option<FuzzyMatch>,
+    is_temp: bool, // 貼上的暫時 Def
 }
 
-#[derive(Debug, Clone)]
-struct XmlNode {
-    tag: String,
-    attributes: Vec<(String, String)>,
-    children: Vec<XmlNode>,
-    text: Option<String>,
+/// 跨重新啟動保存的展開繼承介面狀態
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InheritanceUiState {
+    selected_def_name: Option<String>,
+    search_query: String,
+    fuzzy_search: bool,
+    view: DetailView,
+    sort: DefListSort,
 }
 
+
 impl InheritanceTab {
-    pub fn new(settings: Arc<Mutex<AppSettings>>) -> Self {
+    pub fn new(
+        settings: Arc<Mutex<AppSettings>>,
+        database: SharedDatabase,
+        scan_request: ScanRequest,
+        status: StatusSink,
+        popouts: Popouts,
+        exporter: Exporter,
+    ) -> Self {
         Self {
             base_directory: String::new(),
-            all_defs: HashMap::new(),
+            database,
+            last_generation: 0,
             selected_def_name: String::new(),
             search_query: String::new(),
-            is_loading: false,
-            status_message: String::new(),
+            fuzzy_search: false,
             expanded_xml: String::new(),
             inheritance_chain: Vec::new(),
+            field_diffs: None,
+            view: DetailView::default(),
+            node_tree: None,
+            adhoc_def: None,
+            expansion_cache: LruCache::new(EXPANSION_CACHE_SIZE),
             settings,
+            scan_request,
+            status,
+            exporter,
             initialized: false,
-            auto_scanned: false,
+            pending_restore: None,
+            popouts,
+            xml_search_open: false,
+            xml_search: String::new(),
+            match_lines: Vec::new(),
+            current_match: 0,
+            scroll_to_match: false,
+            def_type_filter: None,
+            sort: DefListSort::default(),
+            descendant_counts: HashMap::new(),
+            counts_key: None,
+            paste_open: false,
+            paste_input: String::new(),
+            paste_error: None,
+        }
+    }
+
+    /// 保存介面狀態
+    pub fn save_state(&self) -> InheritanceUiState {
+        let selected_def_name = if self.selected_def_name.is_empty() {
+            self.pending_restore.clone()
+        } else {
+            Some(self.selected_def_name.clone())
+        };
+        InheritanceUiState {
+            selected_def_name,
+            search_query: self.search_query.clone(),
+            fuzzy_search: self.fuzzy_search,
+            view: self.view,
+            sort: self.sort,
         }
     }
 
+    /// 還原介面狀態；選取的 Def 在下一次掃描完成後才還原，不存在時忽略
+    pub fn load_state(&mut self, state: InheritanceUiState) {
+        self.search_query = state.search_query;
+        self.fuzzy_search = state.fuzzy_search;
+        self.view = state.view;
+        self.sort = state.sort;
+        self.pending_restore = state.selected_def_name;
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
         // 每次更新時檢查設置是否變更
         if let Ok(settings) = self.settings.lock() {
-            if settings.base_path != self.base_directory {
-                self.base_directory = settings.base_path.clone();
+            let base_path = settings.active_base_path();
+            if base_path != self.base_directory {
+                self.base_directory = base_path;
                 self.initialized = true;
-                self.auto_scanned = false;  // 重置自動掃描標記
             }
         }
 
-        // 首次進入且有目錄時自動掃描
-        if !self.auto_scanned && !self.base_directory.is_empty() && self.all_defs.is_empty() {
-            self.auto_scanned = true;
-            self.scan_all_defs();
+        // 重新掃描完成後，舊的展開結果已失效
+        let (generation, refreshing) = self
+            .database
+            .read()
+            .map_or((0, false), |db| (db.generation, db.refreshing));
+        if generation != self.last_generation {
+            self.last_generation = generation;
+            self.expansion_cache.clear();
+            self.selected_def_name.clear();
+            self.expanded_xml.clear();
+            self.inheritance_chain.clear();
+            self.field_diffs = None;
+
+            if let Some(def_name) = self.pending_restore.take() {
+                let exists = self
+                    .database
+                    .read()
+                    .is_ok_and(|db| db.data.contains_key(def_name.as_str()));
+                if exists {
+                    self.selected_def_name = def_name;
+                    self.expand_inheritance();
+                }
+            }
         }
 
         // 頂部控制面板
@@ -87,20 +229,37 @@ impl InheritanceTab {
                 self.scan_all_defs();
             }
 
-            if !self.status_message.is_empty() {
-                ui.colored_label(
-                    if self.is_loading {
-                        egui::Color32::from_rgb(255, 165, 0)
-                    } else {
-                        egui::Color32::from_rgb(0, 200, 0)
-                    },
-                    &self.status_message,
-                );
+            if ui
+                .button("📤 匯出 DOT")
+                .on_hover_text("將繼承樹匯出為 Graphviz .dot 檔案")
+                .clicked()
+            {
+                self.export_dot();
+            }
+
+            if ui
+                .add_enabled(generation != 0, egui::Button::new("🧪 貼上 XML"))
+                .on_hover_text("貼上 Def 片段並展開繼承，不需要檔案；下次掃描時清除")
+                .clicked()
+            {
+                self.paste_open = true;
+            }
+
+            // 背景掃描時仍顯示舊資料
+            if refreshing {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "🔄 更新中…");
             }
         });
 
         ui.separator();
 
+        if generation == 0 {
+            empty_state(ui, &self.settings, refreshing);
+            return;
+        }
+
+        self.paste_window(ui.ctx());
+
         // 主要內容區域
         ui.horizontal_top(|ui| {
             // 左側: Def 列表
@@ -111,32 +270,117 @@ impl InheritanceTab {
                     ui.horizontal(|ui| {
                         ui.label("🔍");
                         let response = ui.text_edit_singleline(&mut self.search_query);
-                        if response.changed() {
+                        let fuzzy = ui
+                            .toggle_value(&mut self.fuzzy_search, "模糊")
+                            .on_hover_text("依序包含所有字元即相符（例如 gnts 相符 Gun_Test），結果依相符程度排序");
+                        if response.changed() || fuzzy.changed() {
                             self.selected_def_name = String::new();
                             self.expanded_xml = String::new();
                             self.inheritance_chain.clear();
+                            self.field_diffs = None;
                         }
                     });
 
+                    self.list_options_ui(ui);
+                    self.update_descendant_counts(generation);
+
                     ui.separator();
 
                     egui::ScrollArea::vertical()
                         .id_salt("def_list")
                         .auto_shrink([false; 2])
                         .show(ui, |ui| {
-                            let filtered_defs: Vec<_> = self.all_defs
-                                .keys()
-                                .filter(|name| {
-                                    self.search_query.is_empty() 
-                                        || name.to_lowercase().contains(&self.search_query.to_lowercase())
+                            let query = self.search_query.to_lowercase();
+                            let fuzzy = self.fuzzy_search && !query.trim().is_empty();
+                            let mut filtered_defs: Vec<ListedDef> = self
+                                .database
+                                .read()
+                                .unwrap()
+                                .data
+                                .values()
+                                .filter(|def| {
+                                    self.def_type_filter.as_ref().is_none_or(|t| def.def_type == *t)
+                                })
+                                .filter_map(|def| {
+                                    let matched = if fuzzy {
+                                        Some(fuzzy_match(&def.def_name, &query)?)
+                                    } else if query.is_empty() || def.def_name.to_lowercase().contains(&query) {
+                                        None
+                                    } else {
+                                        return None;
+                                    };
+                                    // Abstract Def 才顯示子孫數量（沒有子孫時為 0）
+                                    let count = def.is_abstract.then(|| {
+                                        self.descendant_counts.get(&def.def_name).copied().unwrap_or(0)
+                                    });
+                                    Some(ListedDef {
+                                        def_name: def.def_name.clone(),
+                                        depth: def.depth,
+                                        descendants: count,
+                                        matched,
+                                        is_temp: def.is_temp,
+                                    })
                                 })
-                                .cloned()
                                 .collect();
+                            match self.sort {
+                                DefListSort::Name => filtered_defs.sort_by(|a, b| a.def_name.cmp(&b.def_name)),
+                                DefListSort::Descendants => filtered_defs.sort_by(|a, b| {
+                                    b.descendants
+                                        .unwrap_or(0)
+                                        .cmp(&a.descendants.unwrap_or(0))
+                                        .then_with(|| a.def_name.cmp(&b.def_name))
+                                }),
+                            }
+                            if fuzzy {
+                                // 相符程度優先，同分時保留上面的排序
+                                filtered_defs.sort_by_key(|def| std::cmp::Reverse(def.matched.as_ref().map_or(0, |m| m.score)));
+                            }
 
-                            for def_name in filtered_defs {
-                                let is_selected = &self.selected_def_name == &def_name;
-                                if ui.selectable_label(is_selected, &def_name).clicked() {
-                                    self.selected_def_name = def_name.clone();
+                            for ListedDef {
+                                def_name,
+                                depth,
+                                descendants,
+                                matched,
+                                is_temp,
+                            } in filtered_defs
+                            {
+                                let is_selected = def_name == self.selected_def_name;
+                                let clicked = ui
+                                    .horizontal(|ui| {
+                                        // 繼承深度色塊
+                                        let (rect, _) = ui.allocate_exact_size(
+                                            egui::vec2(6.0, 6.0),
+                                            egui::Sense::hover(),
+                                        );
+                                        ui.painter().rect_filled(rect, 1.0, depth_to_color(depth));
+                                        let mut suffix = descendants.map(|count| format!(" ({})", count)).unwrap_or_default();
+                                        if is_temp {
+                                            suffix.push_str(" 🧪");
+                                        }
+                                        let label: egui::WidgetText = match &matched {
+                                            Some(m) => {
+                                                let mut job = highlighted_text(ui, &def_name, &m.positions);
+                                                egui::RichText::new(suffix).append_to(
+                                                    &mut job,
+                                                    ui.style(),
+                                                    egui::FontSelection::Default,
+                                                    egui::Align::Center,
+                                                );
+                                                job.into()
+                                            }
+                                            None => format!("{}{}", def_name, suffix).into(),
+                                        };
+                                        ui.selectable_label(is_selected, label)
+                                            .on_hover_text(if is_temp {
+                                                format!("繼承深度: {}（貼上的暫時 Def）", depth)
+                                            } else {
+                                                format!("繼承深度: {}", depth)
+                                            })
+                                            .clicked()
+                                    })
+                                    .inner;
+                                if clicked {
+                                    self.selected_def_name = def_name.to_string();
                                     self.expand_inheritance();
                                 }
                             }
@@ -153,40 +397,88 @@ impl InheritanceTab {
                 |ui| {
                     if !self.selected_def_name.is_empty() {
 
-                        // 顯示繼承鏈
+                        // 顯示繼承鏈，點擊祖先即可切換到該 Def
                         if !self.inheritance_chain.is_empty() {
                             ui.label("📜 繼承鏈:");
+                            let mut navigate_to = None;
+                            let known: Vec<bool> = self
+                                .database
+                                .read()
+                                .map(|db| {
+                                    self.inheritance_chain
+                                        .iter()
+                                        .map(|name| db.data.contains_key(name.as_str()))
+                                        .collect()
+                                })
+                                .unwrap_or_default();
                             ui.horizontal_wrapped(|ui| {
                                 for (i, name) in self.inheritance_chain.iter().enumerate() {
                                     if i > 0 {
                                         ui.label("→");
                                     }
-                                    ui.label(name);
+                                    if *name == self.selected_def_name {
+                                        ui.label(egui::RichText::new(name).strong());
+                                    } else if known.get(i).copied().unwrap_or(false) {
+                                        if ui.button(name).on_hover_text("切換到此 Def").clicked() {
+                                            navigate_to = Some(name.clone());
+                                        }
+                                    } else {
+                                        ui.weak(name).on_hover_text("找不到此父類");
+                                    }
                                 }
                             });
+                            if let Some(name) = navigate_to {
+                                self.selected_def_name = name;
+                                self.expand_inheritance();
+                            }
                             ui.separator();
                         }
 
-                        // 顯示展開後的 XML
+                        // 顯示展開後的 XML 或與父類的比較
                         ui.horizontal(|ui| {
-                            ui.label("📄 展開的 XML:");
+                            ui.selectable_value(&mut self.view, DetailView::Xml, "📄 展開的 XML");
+                            ui.selectable_value(&mut self.view, DetailView::Diff, "🔀 與父類比較")
+                                .on_hover_text("只看子類新增或變更了哪些欄位");
+                            ui.selectable_value(&mut self.view, DetailView::Tree, "🌳 節點樹")
+                                .on_hover_text("右鍵節點可複製為 PatchOperationAdd");
+                            ui.separator();
                         
+                            self.merge_order_ui(ui);
+                            ui.separator();
+
                             // 複製按鈕
                             if ui.button("📋 複製 XML").clicked() {
                                 ui.output_mut(|o| o.copied_text = self.expanded_xml.clone());
                             }
+
+                            if ui
+                                .button("🗗 彈出視窗")
+                                .on_hover_text("在獨立視窗中開啟展開後的 XML")
+                                .clicked()
+                            {
+                                self.popouts.open(&self.selected_def_name, &self.expanded_xml);
+                            }
                         });
                     
-                        egui::ScrollArea::vertical()
-                            .id_salt("expanded_xml")
-                            .show(ui, |ui| {
-                                ui.add(
-                                    egui::TextEdit::multiline(&mut self.expanded_xml.as_str())
-                                        .code_editor()
-                                        .desired_width(f32::INFINITY)
-                                        .desired_rows(30),
-                                );
+                        if self.view == DetailView::Diff {
+                            egui::ScrollArea::vertical()
+                                .id_salt("field_diffs")
+                                .auto_shrink([false; 2])
+                                .show(ui, |ui| field_diffs_ui(ui, self.field_diffs.as_deref()));
+                        } else if self.view == DetailView::Tree {
+                            self.node_tree_view_ui(ui);
+                        } else {
+                            let focus_search = ui.input_mut(|i| {
+                                i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)
                             });
+                            if focus_search {
+                                self.xml_search_open = true;
+                            }
+                            if self.xml_search_open {
+                                self.xml_search_ui(ui, focus_search);
+                            }
+                            self.expanded_xml_ui(ui);
+                        }
                     } else {
                         ui.label("請從左側選擇一個 Def");
                     }
@@ -195,403 +487,507 @@ impl InheritanceTab {
         });
     }
 
-    fn scan_all_defs(&mut self) {
-        self.is_loading = true;
-        self.status_message = "正在掃描 Defs...".to_string();
-        self.all_defs.clear();
-        self.selected_def_name.clear();
-        self.expanded_xml.clear();
-        self.inheritance_chain.clear();
+    /// Def 列表上方的類型篩選與排序方式
+    fn list_options_ui(&mut self, ui: &mut egui::Ui) {
+        let def_types: BTreeSet<Symbol> = self
+            .database
+            .read()
+            .map(|db| db.data.values().map(|d| d.def_type.clone()).collect())
+            .unwrap_or_default();
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("inheritance_type_filter")
+                .selected_text(self.def_type_filter.as_deref().unwrap_or("所有類型"))
+                .width(120.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.def_type_filter, None, "所有類型");
+                    for def_type in def_types {
+                        let label = def_type.to_string();
+                        ui.selectable_value(&mut self.def_type_filter, Some(def_type), label);
+                    }
+                });
+            egui::ComboBox::from_id_salt("inheritance_sort")
+                .selected_text(self.sort.label())
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for option in DefListSort::ALL {
+                        ui.selectable_value(&mut self.sort, option, option.label());
+                    }
+                })
+                .response
+                .on_hover_text("排序方式；依子孫數量排序時最常被繼承的 Abstract Def 排在最前面");
+        });
+    }
 
-        let base_path = PathBuf::from(&self.base_directory);
-
-        // 尋找所有 XML 檔案
-        let xml_files: Vec<PathBuf> = WalkDir::new(&base_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.path().is_file()
-                    && e.path().extension().and_then(|s| s.to_str()) == Some("xml")
-            })
-            .map(|e| e.path().to_path_buf())
-            .collect();
-
-        self.status_message = format!("找到 {} 個 XML 檔案，正在解析...", xml_files.len());
-
-        // 並行解析
-        let parsed_defs: Vec<DefData> = xml_files
-            .par_iter()
-            .filter_map(|path| parse_def_data(path).ok())
-            .flatten()
-            .collect();
-
-        // 存儲所有 Defs
-        for def_data in parsed_defs {
-            self.all_defs.insert(def_data.def_name.clone(), def_data);
+    /// 掃描完成或類型篩選變更後重新計算 Abstract Def 的具體子孫數量
+    fn update_descendant_counts(&mut self, generation: u64) {
+        let key = (generation, self.def_type_filter.clone());
+        if self.counts_key.as_ref() == Some(&key) {
+            return;
         }
-
-        self.status_message = format!(
-            "掃描完成！找到 {} 個 Defs（包括抽象定義）",
-            self.all_defs.len()
-        );
-        self.is_loading = false;
+        let Ok(db) = self.database.read() else {
+            return;
+        };
+        self.descendant_counts = concrete_descendant_counts(&db.data, key.1.as_ref());
+        self.counts_key = Some(key);
     }
 
-    fn expand_inheritance(&mut self) {
-        self.inheritance_chain.clear();
-        self.expanded_xml.clear();
+    /// 展開 XML 上方的搜尋列：輸入時更新相符行，↑ / ↓ 切換並捲動到相符行
+    fn xml_search_ui(&mut self, ui: &mut egui::Ui, focus: bool) {
+        ui.horizontal(|ui| {
+            ui.label("🔎");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.xml_search)
+                    .hint_text("搜尋展開的 XML")
+                    .desired_width(200.0),
+            );
+            if response.changed() {
+                self.update_matches();
+            }
+            if focus {
+                response.request_focus();
+            }
 
-        if let Some(def_data) = self.all_defs.get(&self.selected_def_name) {
-            // 建立繼承鏈
-            let mut chain = vec![def_data.def_name.clone()];
-            let mut current_parent = def_data.parent_name.clone();
-
-            while let Some(parent_name) = current_parent {
-                chain.push(parent_name.clone());
-                if let Some(parent_def) = self.all_defs.get(&parent_name) {
-                    current_parent = parent_def.parent_name.clone();
-                } else {
-                    break;
+            let count = self.match_lines.len();
+            let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let escape = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape));
+            if ui.add_enabled(count > 0, egui::Button::new("↑")).clicked() {
+                self.current_match = (self.current_match + count - 1) % count;
+                self.scroll_to_match = true;
+            }
+            if ui.add_enabled(count > 0, egui::Button::new("↓")).clicked() || (enter && count > 0) {
+                self.current_match = (self.current_match + 1) % count;
+                self.scroll_to_match = true;
+                if enter {
+                    response.request_focus();
                 }
             }
+            if count > 0 {
+                ui.label(format!("Match {} of {}", self.current_match + 1, count));
+            } else if !self.xml_search.is_empty() {
+                ui.weak("沒有相符的行");
+            }
 
-            chain.reverse();
-            self.inheritance_chain = chain.clone();
-
-            // 合併節點（從最頂層父類開始）
-            let mut merged_nodes: BTreeMap<String, XmlNode> = BTreeMap::new();
+            if ui.small_button("✖").clicked() || escape {
+                self.xml_search_open = false;
+                self.xml_search.clear();
+                self.match_lines.clear();
+            }
+        });
+    }
 
-            for ancestor_name in &chain {
-                if let Some(ancestor) = self.all_defs.get(ancestor_name) {
-                    for node in &ancestor.raw_nodes {
-                        merge_node(&mut merged_nodes, node);
-                    }
+    /// 展開的 XML；相符的行以黃色背景標示，目前的相符行顏色較深
+    fn expanded_xml_ui(&mut self, ui: &mut egui::Ui) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let current_line = self.match_lines.get(self.current_match).copied();
+        let match_lines = &self.match_lines;
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            let mut job = egui::text::LayoutJob::default();
+            job.wrap.max_width = wrap_width;
+            for (index, line) in text.split_inclusive('\n').enumerate() {
+                let mut format = egui::TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+                if match_lines.binary_search(&index).is_ok() {
+                    format.color = egui::Color32::BLACK;
+                    format.background = if Some(index) == current_line {
+                        egui::Color32::from_rgb(255, 165, 0)
+                    } else {
+                        egui::Color32::from_rgb(255, 230, 100)
+                    };
                 }
+                job.append(line, 0.0, format);
+            }
+            ui.fonts(|f| f.layout_job(job))
+        };
+
+        let mut scroll_area = egui::ScrollArea::vertical().id_salt("expanded_xml");
+        if self.scroll_to_match {
+            self.scroll_to_match = false;
+            if let Some(line) = current_line {
+                // 讓相符行出現在可視範圍上方幾行處，而不是緊貼頂端
+                let offset = (line as f32 - 3.0).max(0.0) * row_height;
+                scroll_area = scroll_area.vertical_scroll_offset(offset);
             }
-
-            // 生成展開的 XML
-            self.expanded_xml = generate_expanded_xml(
-                &self.selected_def_name,
-                &def_data.def_type,
-                &merged_nodes,
-            );
         }
+        scroll_area.show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.expanded_xml.as_str())
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(30)
+                    .layouter(&mut layouter),
+            );
+        });
     }
-}
 
-// 合併節點：對於 <li> 標籤進行合併，其他標籤覆蓋
-fn merge_node(merged: &mut BTreeMap<String, XmlNode>, node: &XmlNode) {
-    let key = node.tag.clone();
-    
-    if merged.contains_key(&key) {
-        // 已存在此標籤
-        let existing = merged.get_mut(&key).unwrap();
-        
-        // 檢查是否包含 <li> 子節點
-        let has_li_children = node.children.iter().any(|c| c.tag == "li");
-        
-        if has_li_children {
-            // 合併 <li> 子節點
-            for child in &node.children {
-                if child.tag == "li" {
-                    // 檢查是否已存在相同的 <li>（比較文本和屬性）
-                    let child_text = child.text.as_ref().map(|s| s.as_str()).unwrap_or("");
-                    let exists = existing.children.iter().any(|c| {
-                        if c.tag != "li" {
-                            return false;
-                        }
-                        let c_text = c.text.as_ref().map(|s| s.as_str()).unwrap_or("");
-                        // 文本相同且屬性相同才算重複
-                        c_text == child_text && c.attributes == child.attributes
-                    });
-                    if !exists {
-                        existing.children.push(child.clone());
-                    }
-                } else {
-                    // 非 <li> 子節點遞歸合併
-                    let mut child_map: BTreeMap<String, XmlNode> = existing
-                        .children
-                        .iter()
-                        .filter(|c| c.tag != "li")
-                        .map(|c| (c.tag.clone(), c.clone()))
-                        .collect();
-                    
-                    merge_node(&mut child_map, child);
-                    
-                    existing.children.retain(|c| c.tag == "li");
-                    existing.children.extend(child_map.into_values());
-                }
-            }
+    /// 依搜尋字串（不分大小寫）重新計算相符行，並捲動到第一個相符行
+    fn update_matches(&mut self) {
+        let query = self.xml_search.to_lowercase();
+        self.match_lines = if query.is_empty() {
+            Vec::new()
         } else {
-            // 完全覆蓋（包括 text 和子節點）
-            *existing = node.clone();
+            self.expanded_xml
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.to_lowercase().contains(&query))
+                .map(|(index, _)| index)
+                .collect()
+        };
+        self.current_match = 0;
+        self.scroll_to_match = !self.match_lines.is_empty();
+    }
+
+    /// 請求掃描協調器重新掃描
+    pub fn scan_all_defs(&mut self) {
+        self.scan_request.request();
+    }
+
+    /// 命令面板中的展開繼承命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![
+            Command::new("重新掃描 Defs", |tab: &mut Self, _| tab.scan_all_defs()),
+            Command::new("匯出繼承樹（DOT）", |tab: &mut Self, _| tab.export_dot()),
+        ]
+    }
+
+    fn export_dot(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Graphviz", &["dot"])
+            .set_file_name("inheritance.dot")
+            .save_file()
+        else {
+            return;
+        };
+
+        let dot = match self.database.read() {
+            Ok(db) => defs_to_dot(&db.data),
+            Err(_) => return,
+        };
+
+        let message = self.exporter.export("展開繼承", &path, dot.into_bytes());
+        if let Ok(mut status) = self.status.lock() {
+            status.notify("展開繼承", message);
         }
-    } else {
-        // 新標籤，直接插入
-        merged.insert(key, node.clone());
     }
-}
 
-fn parse_def_data(path: &Path) -> Result<Vec<DefData>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
-    reader.config_mut().trim_text(true);
-
-    let mut results = Vec::new();
-    let mut buf = Vec::new();
-    let mut inside_defs = false;
-    let mut def_depth = 0;
-    let mut current_def_type: Option<String> = None;
-    let mut current_def_name: Option<String> = None;
-    let mut current_parent_name: Option<String> = None;
-    let mut is_abstract = false;
-    let mut node_stack: Vec<XmlNode> = Vec::new();
-    let mut root_nodes: Vec<XmlNode> = Vec::new();
-
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                
-                if name == "Defs" {
-                    inside_defs = true;
-                } else if inside_defs && def_depth == 0 && name.ends_with("Def") {
-                    // 新的 Def 開始
-                    current_def_type = Some(name.clone());
-                    def_depth = 1;
-                    current_def_name = None;
-                    current_parent_name = None;
-                    is_abstract = false;
-                    root_nodes.clear();
-                    node_stack.clear();
-                    
-                    // 解析屬性
-                    for attr in e.attributes().filter_map(|a| a.ok()) {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let value = String::from_utf8_lossy(&attr.value).to_string();
-                        
-                        if key == "Abstract" && value == "True" {
-                            is_abstract = true;
-                        } else if key == "ParentName" {
-                            current_parent_name = Some(value.clone());
-                        } else if key == "Name" {
-                            current_def_name = Some(value.clone());
-                        }
-                    }
-                } else if def_depth > 0 {
-                    // Def 內的子節點
-                    def_depth += 1;
-                    
-                    let mut attributes = Vec::new();
-                    for attr in e.attributes().filter_map(|a| a.ok()) {
-                        attributes.push((
-                            String::from_utf8_lossy(attr.key.as_ref()).to_string(),
-                            String::from_utf8_lossy(&attr.value).to_string(),
-                        ));
+    /// 「貼上 XML」視窗
+    fn paste_window(&mut self, ctx: &egui::Context) {
+        if !self.paste_open {
+            return;
+        }
+        let mut open = self.paste_open;
+        let mut parse = false;
+        egui::Window::new("🧪 貼上 XML")
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label("貼上一個或多個 Def（例如 <ThingDef Name=\"X\" ParentName=\"Y\">…</ThingDef>），父類可來自掃描結果或同時貼上的 Def");
+                ui.horizontal(|ui| {
+                    parse = ui.button("🔍 解析並展開").clicked();
+                    if ui.button("🗑 清除").clicked() {
+                        self.paste_input.clear();
+                        self.paste_error = None;
                     }
-                    
-                    let node = XmlNode {
-                        tag: name.clone(),
-                        attributes,
-                        children: Vec::new(),
-                        text: None,
-                    };
-                    
-                    node_stack.push(node);
+                });
+                if let Some(error) = &self.paste_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 80, 80), error);
                 }
-            }
-            Ok(Event::Empty(ref e)) => {
-                // 空標籤 <tag />
-                if def_depth > 0 {
-                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    let mut attributes = Vec::new();
-                    for attr in e.attributes().filter_map(|a| a.ok()) {
-                        attributes.push((
-                            String::from_utf8_lossy(attr.key.as_ref()).to_string(),
-                            String::from_utf8_lossy(&attr.value).to_string(),
-                        ));
-                    }
-                    
-                    let node = XmlNode {
-                        tag: name.clone(),
-                        attributes,
-                        children: Vec::new(),
-                        text: None,
-                    };
-                    
-                    if let Some(parent) = node_stack.last_mut() {
-                        parent.children.push(node);
-                    } else {
-                        root_nodes.push(node);
-                    }
+                egui::ScrollArea::vertical().id_salt("paste_xml_input").show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.paste_input)
+                            .code_editor()
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(16),
+                    );
+                });
+            });
+        self.paste_open = open;
+        if parse {
+            self.parse_pasted();
+        }
+    }
+
+    /// 將貼上的 Def 暫時加入資料庫並展開最後一個；與掃描結果同名的 Def 不會覆蓋
+    fn parse_pasted(&mut self) {
+        // 貼上的片段常常少了 <Defs> 根節點，補上後再解析
+        let content = if self.paste_input.contains("<Defs") {
+            self.paste_input.clone()
+        } else {
+            format!("<Defs>\n{}\n</Defs>", self.paste_input)
+        };
+        let rules = self.settings.lock().map(|s| s.def_root_rules()).unwrap_or_default();
+        let defs = parse_def_data_from_str(&content, Path::new("（貼上的 XML）"), &rules);
+        if defs.is_empty() {
+            self.paste_error = Some("找不到任何 Def（需要 defName 或 Name）".to_string());
+            return;
+        }
+
+        let mut added = Vec::new();
+        let mut skipped = Vec::new();
+        {
+            let Ok(mut db) = self.database.write() else {
+                return;
+            };
+            for mut def in defs {
+                if db.data.get(&def.def_name).is_some_and(|d| !d.is_temp) {
+                    skipped.push(def.def_name);
+                    continue;
                 }
+                def.is_temp = true;
+                added.push(def.def_name.clone());
+                db.data.insert(def.def_name.clone(), def);
             }
-            Ok(Event::Text(e)) => {
-                if def_depth > 0 && !node_stack.is_empty() {
-                    if let Ok(text) = e.unescape() {
-                        let trimmed = text.trim();
-                        if !trimmed.is_empty() {
-                            let last = node_stack.last_mut().unwrap();
-                            
-                            // 特殊處理 defName
-                            if last.tag == "defName" && current_def_name.is_none() {
-                                current_def_name = Some(trimmed.to_string());
-                            }
-                            
-                            last.text = Some(trimmed.to_string());
-                        }
-                    }
-                }
+            compute_depths(&mut db.data);
+        }
+
+        let Some(last) = added.last() else {
+            self.paste_error = Some(format!("與掃描結果中的 Def 同名，請改名後再試: {}", skipped.join(", ")));
+            return;
+        };
+        self.paste_error = None;
+        self.paste_open = false;
+        self.expansion_cache.clear();
+        self.counts_key = None;
+        self.selected_def_name = last.to_string();
+        self.expand_inheritance();
+
+        let mut message = format!("✅ 已加入 {} 個暫時 Def，下次掃描時清除", added.len());
+        if !skipped.is_empty() {
+            message.push_str(&format!("；⚠ 與掃描結果同名而略過: {}", skipped.join(", ")));
+        }
+        if let Ok(mut status) = self.status.lock() {
+            status.set_message("展開繼承", message);
+        }
+    }
+
+    /// 節點樹檢視；複製的 Patch 以展開中的 defName 為目標
+    fn node_tree_view_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(tree) = &self.node_tree else {
+            ui.label("找不到此 Def 的資料");
+            return;
+        };
+        let copied = egui::ScrollArea::vertical()
+            .id_salt("node_tree")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| node_tree_ui(ui, &tree.nodes, &mut Vec::new(), egui::Id::new("node_tree")))
+            .inner;
+        if let Some((path, node)) = copied {
+            let patch = node_to_patch_add(&self.selected_def_name, tree.def_type.as_str(), &path, &node);
+            ui.output_mut(|o| o.copied_text = patch);
+            if let Ok(mut status) = self.status.lock() {
+                status.set_message("展開繼承", format!("✅ 已複製 <{}> 的 PatchOperationAdd", node.tag));
             }
-            Ok(Event::End(ref e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                
-                if def_depth > 0 && name.ends_with("Def") {
-                    def_depth -= 1;
-                    
-                    if def_depth == 0 {
-                        // Def 結束
-                        if let Some(def_name) = &current_def_name {
-                            results.push(DefData {
-                                def_name: def_name.clone(),
-                                parent_name: current_parent_name.clone(),
-                                file_path: path.to_path_buf(),
-                                xml_content: String::new(),
-                                is_abstract,
-                                def_type: current_def_type.clone().unwrap_or_default(),
-                                raw_nodes: root_nodes.clone(),
-                            });
-                        }
-                    }
-                } else if def_depth > 0 {
-                    def_depth -= 1;
-                    
-                    // 彈出完成的節點
-                    if let Some(completed_node) = node_stack.pop() {
-                        if let Some(parent) = node_stack.last_mut() {
-                            parent.children.push(completed_node);
-                        } else {
-                            root_nodes.push(completed_node);
-                        }
-                    }
-                }
-                
-                if name == "Defs" {
-                    inside_defs = false;
+        }
+    }
+
+    /// 合併順序的選單；變更時重新展開目前的 Def
+    fn merge_order_ui(&mut self, ui: &mut egui::Ui) {
+        let mut order = self.merge_order();
+        let response = egui::ComboBox::from_id_salt("inheritance_merge_order")
+            .selected_text(order.label())
+            .show_ui(ui, |ui| {
+                let mut changed = false;
+                for option in MergeOrder::ALL {
+                    changed |= ui
+                        .selectable_value(&mut order, option, option.label())
+                        .on_hover_text(option.description())
+                        .changed();
                 }
-            }
-            Ok(Event::Eof) => break,
-            Err(_) => break,
-            _ => {}
+                changed
+            });
+        response.response.on_hover_text(order.description());
+        if response.inner != Some(true) {
+            return;
+        }
+
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.inheritance_merge_order = order;
+            settings.save();
+        }
+        self.expansion_cache.clear();
+        match self.adhoc_def.take() {
+            Some(def_data) => self.expand_adhoc(def_data),
+            None => self.expand_inheritance(),
         }
-        buf.clear();
     }
 
-    Ok(results)
-}
+    fn merge_order(&self) -> MergeOrder {
+        self.settings
+            .lock()
+            .map(|s| s.inheritance_merge_order)
+            .unwrap_or_default()
+    }
+
+    fn expand_inheritance(&mut self) {
+        self.inheritance_chain.clear();
+        self.expanded_xml.clear();
+        self.field_diffs = None;
+        self.node_tree = None;
+        self.adhoc_def = None;
+
+        let database = self.database.clone();
+        let Ok(db) = database.read() else {
+            return;
+        };
 
-fn generate_expanded_xml(
-    def_name: &str,
-    def_type: &str,
-    nodes: &BTreeMap<String, XmlNode>,
-) -> String {
-    let mut xml = String::new();
-    
-    xml.push_str(&format!("<{}>\n", def_type));
-    xml.push_str(&format!("  <defName>{}</defName>\n", def_name));
-    
-    // 生成所有其他節點
-    for (_, node) in nodes {
-        if node.tag != "defName" {
-            generate_node_xml(&mut xml, node, 1);
+        if let Some(def_data) = db.data.get(self.selected_def_name.as_str()) {
+            self.expand_def(def_data, &db.data, true);
         }
     }
-    
-    xml.push_str(&format!("</{}>\n", def_type));
-    xml
-}
 
-fn generate_node_xml(xml: &mut String, node: &XmlNode, indent_level: usize) {
-    let indent = "  ".repeat(indent_level);
-    
-    // 檢查是否是簡單節點（只有文本，無子節點）
-    let is_simple = node.children.is_empty() && node.text.is_some();
-    let is_empty = node.children.is_empty() && node.text.is_none();
-    
-    if is_simple {
-        // 簡單節點：單行輸出
-        let text = node.text.as_ref().unwrap();
-        if node.attributes.is_empty() {
-            xml.push_str(&format!("{}<{}>{}</{}>\n", indent, node.tag, text, node.tag));
-        } else {
-            xml.push_str(&format!("{}<{}", indent, node.tag));
-            for (key, value) in &node.attributes {
-                xml.push_str(&format!(" {}=\"{}\"", key, value));
-            }
-            xml.push_str(&format!(">{}</{}>\n", text, node.tag));
+    /// 展開不在資料庫中的 Def（例如快速分析貼上的內容），父類從目前掃描的資料中尋找
+    pub fn expand_adhoc(&mut self, def_data: DefData) {
+        self.inheritance_chain.clear();
+        self.expanded_xml.clear();
+        self.field_diffs = None;
+        self.selected_def_name = def_data.def_name.to_string();
+
+        let database = self.database.clone();
+        let Ok(db) = database.read() else {
+            return;
+        };
+        // 分頁尚未看到最新掃描時，避免下一幀因版本變更而清除展開結果
+        if self.last_generation != db.generation {
+            self.last_generation = db.generation;
+            self.expansion_cache.clear();
         }
-    } else if is_empty {
-        // 空節點：自閉合標籤
-        if node.attributes.is_empty() {
-            xml.push_str(&format!("{}<{} />\n", indent, node.tag));
-        } else {
-            xml.push_str(&format!("{}<{}", indent, node.tag));
-            for (key, value) in &node.attributes {
-                xml.push_str(&format!(" {}=\"{}\"", key, value));
+        self.expand_def(&def_data, &db.data, false);
+        self.adhoc_def = Some(def_data);
+    }
+
+    /// `cached` 為 false 時（不在資料庫中的 Def）不讀取也不寫入快取，避免與同名的 Def 混淆
+    fn expand_def(&mut self, def_data: &DefData, all_defs: &HashMap<Symbol, DefData>, cached: bool) {
+        let order = self.merge_order();
+        let hit = cached
+            .then(|| self.expansion_cache.get(&def_data.def_name).cloned())
+            .flatten();
+        let (chain, xml) = hit.unwrap_or_else(|| {
+            let expanded = expand_def(def_data, all_defs, order);
+            if cached {
+                self.expansion_cache
+                    .put(def_data.def_name.clone(), (expanded.chain.clone(), expanded.xml.clone()));
             }
-            xml.push_str(" />\n");
-        }
-    } else {
-        // 複雜節點：多行輸出
-        // 開標籤
-        if node.attributes.is_empty() {
-            xml.push_str(&format!("{}<{}>\n", indent, node.tag));
+            (expanded.chain, expanded.xml)
+        });
+        self.inheritance_chain = chain;
+        self.expanded_xml = xml;
+        self.update_matches();
+        self.node_tree = Some(NodeTree {
+            def_type: def_data.def_type.clone(),
+            nodes: merged_nodes(def_data, all_defs, order).into_values().collect(),
+        });
+
+        // 與合併了整條繼承鏈的父類比較
+        self.field_diffs = def_data
+            .parent_name
+            .as_ref()
+            .and_then(|name| all_defs.get(name))
+            .map(|parent| {
+                let effective_parent = DefData {
+                    raw_nodes: merged_nodes(parent, all_defs, order).into_values().collect(),
+                    ..parent.clone()
+                };
+                diff_from_parent(def_data, &effective_parent)
+            });
+    }
+}
+
+/// 節點樹：有子節點的節點可收合；右鍵節點回傳要複製為 Patch 的節點與其父節點路徑
+fn node_tree_ui(
+    ui: &mut egui::Ui,
+    nodes: &[XmlNode],
+    path: &mut Vec<String>,
+    id: egui::Id,
+) -> Option<(Vec<String>, XmlNode)> {
+    let mut copied = None;
+    for (index, node) in nodes.iter().enumerate() {
+        let id = id.with(index);
+        let response = if node.children.is_empty() {
+            let text = match &node.text {
+                Some(text) => format!("{}: {}", node.tag, text),
+                None => node.tag.to_string(),
+            };
+            ui.add(egui::Label::new(egui::RichText::new(text).monospace()).sense(egui::Sense::click()))
         } else {
-            xml.push_str(&format!("{}<{}", indent, node.tag));
-            for (key, value) in &node.attributes {
-                xml.push_str(&format!(" {}=\"{}\"", key, value));
+            path.push(node.tag.to_string());
+            let output = egui::CollapsingHeader::new(egui::RichText::new(node.tag.as_str()).monospace())
+                .id_salt(id)
+                .show(ui, |ui| node_tree_ui(ui, &node.children, path, id));
+            path.pop();
+            if let Some(inner) = output.body_returned.flatten() {
+                copied = Some(inner);
             }
-            xml.push_str(">\n");
-        }
-        
-        // 文本內容（如果有的話，在有子節點的情況下較少見）
-        if let Some(text) = &node.text {
-            xml.push_str(&format!("{}  {}\n", indent, text));
-        }
-        
-        // 子節點
-        for child in &node.children {
-            if child.tag == "li" && child.children.is_empty() {
-                // <li> 標籤特殊處理：總是單行
-                if let Some(text) = &child.text {
-                    // 有文本內容
-                    if child.attributes.is_empty() {
-                        xml.push_str(&format!("{}  <li>{}</li>\n", indent, text));
-                    } else {
-                        xml.push_str(&format!("{}  <li", indent));
-                        for (key, value) in &child.attributes {
-                            xml.push_str(&format!(" {}=\"{}\"", key, value));
-                        }
-                        xml.push_str(&format!(">{}</li>\n", text));
+            output.header_response
+        };
+        response.context_menu(|ui| {
+            if ui.button("📋 複製為 PatchOperationAdd").clicked() {
+                copied = Some((path.clone(), node.clone()));
+                ui.close_menu();
+            }
+        });
+    }
+    copied
+}
+
+/// 「與父類比較」檢視：覆蓋的值以刪除線顯示父類原值，未定義的欄位以斜體顯示繼承的值
+fn field_diffs_ui(ui: &mut egui::Ui, diffs: Option<&[FieldDiff]>) {
+    let Some(diffs) = diffs else {
+        ui.label("此 Def 沒有 ParentName（或找不到父類），沒有可比較的內容");
+        return;
+    };
+
+    let added = egui::Color32::from_rgb(0, 200, 0);
+    let changed = egui::Color32::from_rgb(255, 200, 0);
+    egui::Grid::new("field_diff_grid")
+        .striped(true)
+        .num_columns(2)
+        .show(ui, |ui| {
+            for diff in diffs {
+                ui.monospace(format!("<{}>", diff.tag));
+                let parent_value = diff.parent_value.as_deref().unwrap_or_default();
+                let child_value = diff.child_value.as_deref().unwrap_or_default();
+                ui.vertical(|ui| match diff.change {
+                    FieldChange::Added => {
+                        ui.colored_label(added, egui::RichText::new(child_value).monospace());
                     }
-                } else {
-                    // 空 <li> 標籤
-                    if child.attributes.is_empty() {
-                        xml.push_str(&format!("{}  <li />\n", indent));
-                    } else {
-                        xml.push_str(&format!("{}  <li", indent));
-                        for (key, value) in &child.attributes {
-                            xml.push_str(&format!(" {}=\"{}\"", key, value));
-                        }
-                        xml.push_str(" />\n");
+                    FieldChange::Overridden => {
+                        ui.label(egui::RichText::new(parent_value).monospace().strikethrough().weak());
+                        ui.colored_label(changed, egui::RichText::new(child_value).monospace());
                     }
-                }
-            } else {
-                generate_node_xml(xml, child, indent_level + 1);
+                    FieldChange::Extended => {
+                        ui.label(egui::RichText::new(parent_value).monospace().weak());
+                        ui.colored_label(
+                            added,
+                            egui::RichText::new(format!("＋ 追加:\n{}", child_value)).monospace(),
+                        );
+                    }
+                    FieldChange::Unchanged => {
+                        ui.label(egui::RichText::new(child_value).monospace())
+                            .on_hover_text("與父類的值相同");
+                    }
+                    FieldChange::Inherited => {
+                        ui.label(
+                            egui::RichText::new(format!("[繼承: {}]", parent_value))
+                                .monospace()
+                                .italics()
+                                .weak(),
+                        );
+                    }
+                });
+                ui.end_row();
             }
-        }
-        
-        // 閉標籤
-        xml.push_str(&format!("{}</{}>\n", indent, node.tag));
+        });
+}
+
+/// 繼承深度的顏色：0 為綠色，3 為黃色，6 以上為紅色，中間線性插值
+fn depth_to_color(depth: u8) -> egui::Color32 {
+    let lerp = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    let depth = depth.min(6) as f32;
+    if depth <= 3.0 {
+        let t = depth / 3.0;
+        egui::Color32::from_rgb(lerp(0, 255, t), 200, 0)
+    } else {
+        let t = (depth - 3.0) / 3.0;
+        egui::Color32::from_rgb(255, lerp(200, 80, t), lerp(0, 80, t))
     }
 }
+
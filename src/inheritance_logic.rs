@@ -0,0 +1,68 @@
+use crate::intern::Symbol;
+use crate::merge::{node_to_xml, DefData, XmlNode};
+
+/// 子類欄位相對於父類的變化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldChange {
+    Added,      // 只有子類定義
+    Overridden, // 子類覆蓋父類的值
+    Extended,   // 子類在父類的列表後追加 <li>
+    Unchanged,  // 子類重複定義了與父類相同的值
+    Inherited,  // 子類未定義，沿用父類的值
+}
+
+/// 「與父類比較」檢視中的一個欄位
+#[derive(Debug, Clone)]
+pub struct FieldDiff {
+    pub tag: Symbol,
+    pub change: FieldChange,
+    pub parent_value: Option<String>,
+    pub child_value: Option<String>,
+}
+
+/// 比較子類與父類的頂層欄位：先依序列出子類定義的欄位，再列出只從父類繼承的欄位
+///
+/// `parent` 應為已合併繼承鏈的父類（見 `merge::merged_nodes`），否則祖父類的欄位不會出現
+pub fn diff_from_parent(child: &DefData, parent: &DefData) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+
+    for node in child.raw_nodes.iter().filter(|n| n.tag != "defName") {
+        let parent_node = parent.raw_nodes.iter().find(|p| p.tag == node.tag);
+        let child_value = field_value(node);
+        let change = match parent_node {
+            None => FieldChange::Added,
+            Some(p) if field_value(p) == child_value => FieldChange::Unchanged,
+            // 與合併規則相同：含 <li> 的列表會追加而非覆蓋
+            Some(_) if node.children.iter().any(|c| c.tag == "li") => FieldChange::Extended,
+            Some(_) => FieldChange::Overridden,
+        };
+        diffs.push(FieldDiff {
+            tag: node.tag.clone(),
+            change,
+            parent_value: parent_node.map(field_value),
+            child_value: Some(child_value),
+        });
+    }
+
+    for node in &parent.raw_nodes {
+        if node.tag == "defName" || child.raw_nodes.iter().any(|c| c.tag == node.tag) {
+            continue;
+        }
+        diffs.push(FieldDiff {
+            tag: node.tag.clone(),
+            change: FieldChange::Inherited,
+            parent_value: Some(field_value(node)),
+            child_value: None,
+        });
+    }
+
+    diffs
+}
+
+/// 欄位的顯示值：純文字節點只取文字，其他輸出整個節點的 XML
+fn field_value(node: &XmlNode) -> String {
+    match &node.text {
+        Some(text) if node.children.is_empty() => text.to_string(),
+        _ => node_to_xml(node),
+    }
+}
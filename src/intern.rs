@@ -0,0 +1,134 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// 全域字串池：標籤名稱、屬性名稱、Def 類型、defName 與文本值在數萬個節點與索引間重複出現，只保留一份
+static INTERNER: OnceLock<RwLock<HashSet<Symbol>>> = OnceLock::new();
+
+/// 駐留字串，複製只增加引用計數
+///
+/// 內部為 `Arc<Box<str>>` 而非 `Arc<str>`：只佔一個指標（`Option<Symbol>` 亦同），
+/// 數十萬個 XML 節點各省下 8 到 16 位元組
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Arc<Box<str>>);
+
+impl Symbol {
+    pub fn new(value: &str) -> Self {
+        let interner = INTERNER.get_or_init(Default::default);
+
+        if let Ok(set) = interner.read() {
+            if let Some(existing) = set.get(value) {
+                return existing.clone();
+            }
+        }
+
+        let Ok(mut set) = interner.write() else {
+            return Symbol(Arc::new(value.into()));
+        };
+        // 取得寫入鎖前可能已被其他執行緒加入
+        if let Some(existing) = set.get(value) {
+            return existing.clone();
+        }
+        let symbol = Symbol(Arc::new(value.into()));
+        set.insert(symbol.clone());
+        symbol
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// 移除只剩字串池本身引用的字串，回傳移除數量；重新掃描換掉舊資料庫後呼叫，
+/// 否則改過或刪除的標籤與文本會一直留在池中
+pub fn prune_unused() -> usize {
+    let Some(interner) = INTERNER.get() else {
+        return 0;
+    };
+    let Ok(mut set) = interner.write() else {
+        return 0;
+    };
+    // 持有寫入鎖時無法從池中取得新的引用，計數為 1 即表示已無人使用
+    let before = set.len();
+    set.retain(|symbol| Arc::strong_count(&symbol.0) > 1);
+    before - set.len()
+}
+
+/// 字串池目前保留的字串數量
+pub fn pool_size() -> usize {
+    INTERNER
+        .get()
+        .and_then(|interner| interner.read().ok().map(|set| set.len()))
+        .unwrap_or(0)
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Symbol::new("")
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// 以字串序列化，讀回時重新駐留
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|value| Symbol::new(&value))
+    }
+}
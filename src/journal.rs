@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::settings::AppSettings;
+
+/// 操作記錄在快取目錄下的子目錄
+pub const JOURNAL_DIR: &str = "journal";
+
+/// 操作記錄的預設大小上限
+pub const DEFAULT_MAX_BYTES: u64 = 200 * 1024 * 1024;
+
+const MANIFEST: &str = "manifest.json";
+
+/// 檔案在操作完成後的狀態，用於偵測之後是否被外部修改
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    len: u64,
+    modified: u128, // 修改時間（自 UNIX epoch 起的奈秒）
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(Self {
+            len: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// 操作影響的一個檔案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalFile {
+    pub path: PathBuf,
+    backup: Option<String>,   // 備份檔名；操作前檔案不存在時為 None，復原時刪除
+    after: Option<FileStamp>, // 操作完成後的狀態
+}
+
+/// 一次寫入檔案的操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,          // 記錄目錄名稱，依時間排序
+    pub operation: String,   // 操作類型（重新命名、取代等）
+    pub created_at: u64,     // 建立時間（UNIX 秒）
+    pub files: Vec<JournalFile>,
+}
+
+/// 復原時略過的檔案與原因
+#[derive(Debug, Clone)]
+pub struct RestoreWarning {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// 操作記錄：寫入檔案前先備份原內容到快取目錄，之後可整批復原；
+/// 超過大小上限時從最舊的記錄開始刪除
+#[derive(Debug, Clone)]
+pub struct Journal {
+    root: PathBuf,
+    max_bytes: u64,
+}
+
+/// 已備份、尚未完成的操作；寫入完成後呼叫 `commit`
+pub struct PendingOperation {
+    journal: Journal,
+    dir: PathBuf,
+    entry: JournalEntry,
+}
+
+impl Journal {
+    pub fn new(root: PathBuf, max_bytes: u64) -> Self {
+        Self { root, max_bytes }
+    }
+
+    /// 設置目錄下的操作記錄
+    pub fn open_default() -> Option<Self> {
+        let root = AppSettings::config_dir().ok()?.join(JOURNAL_DIR);
+        Some(Self::new(root, DEFAULT_MAX_BYTES))
+    }
+
+    /// 開始一次操作：在寫入前備份所有將被修改的檔案
+    pub fn begin(&self, operation: &str, paths: &[PathBuf]) -> io::Result<PendingOperation> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut id = format!("{:020}", now.as_nanos());
+        while self.root.join(&id).exists() {
+            id.push('_');
+        }
+        let dir = self.root.join(&id);
+        fs::create_dir_all(&dir)?;
+
+        let mut files = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            let backup = if path.is_file() {
+                let name = format!("{}.bak", index);
+                fs::copy(path, dir.join(&name))?;
+                Some(name)
+            } else {
+                None
+            };
+            files.push(JournalFile {
+                path: path.clone(),
+                backup,
+                after: None,
+            });
+        }
+
+        Ok(PendingOperation {
+            journal: self.clone(),
+            dir,
+            entry: JournalEntry {
+                id,
+                operation: operation.to_string(),
+                created_at: now.as_secs(),
+                files,
+            },
+        })
+    }
+
+    /// 所有記錄，最新的在前
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        let Ok(read_dir) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<JournalEntry> = read_dir
+            .filter_map(|e| e.ok())
+            .filter_map(|e| fs::read_to_string(e.path().join(MANIFEST)).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        entries.sort_by(|a, b| b.id.cmp(&a.id));
+        entries
+    }
+
+    /// 復原指定的操作並刪除其記錄；操作後被外部修改的檔案不會覆寫，改為回傳警告
+    ///
+    /// 每個檔案先寫入暫存檔再改名取代，單一檔案的復原不會只寫一半
+    pub fn restore(&self, id: &str) -> io::Result<Vec<RestoreWarning>> {
+        let dir = self.root.join(id);
+        let entry: JournalEntry = serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST))?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut warnings = Vec::new();
+        for file in &entry.files {
+            if FileStamp::read(&file.path) != file.after {
+                warnings.push(RestoreWarning {
+                    path: file.path.clone(),
+                    message: "操作後已被其他程式修改，未復原".to_string(),
+                });
+                continue;
+            }
+
+            let result = match &file.backup {
+                Some(backup) => replace_file(&dir.join(backup), &file.path),
+                None => fs::remove_file(&file.path),
+            };
+            if let Err(e) = result {
+                warnings.push(RestoreWarning {
+                    path: file.path.clone(),
+                    message: format!("復原失敗: {}", e),
+                });
+            }
+        }
+
+        fs::remove_dir_all(&dir)?;
+        Ok(warnings)
+    }
+
+    /// 超過大小上限時刪除最舊的記錄（至少保留最新的一筆）
+    fn evict(&self) {
+        let mut entries: Vec<(String, u64)> = self
+            .entries()
+            .into_iter()
+            .map(|e| {
+                let size = dir_size(&self.root.join(&e.id));
+                (e.id, size)
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|(_, size)| size).sum();
+
+        while total > self.max_bytes && entries.len() > 1 {
+            let Some((id, size)) = entries.pop() else {
+                break;
+            };
+            if fs::remove_dir_all(self.root.join(&id)).is_ok() {
+                total -= size;
+            }
+        }
+    }
+}
+
+impl PendingOperation {
+    /// 寫入完成：記錄檔案目前的狀態並儲存記錄
+    pub fn commit(mut self) -> io::Result<JournalEntry> {
+        for file in &mut self.entry.files {
+            file.after = FileStamp::read(&file.path);
+        }
+        let json = serde_json::to_string_pretty(&self.entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(self.dir.join(MANIFEST), json)?;
+        self.journal.evict();
+        Ok(self.entry)
+    }
+
+    /// 寫入失敗時放棄記錄（不會還原已寫入的檔案）
+    pub fn discard(self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 以備份內容取代目標檔案：先寫入同目錄的暫存檔，再改名覆蓋
+fn replace_file(backup: &Path, target: &Path) -> io::Result<()> {
+    let mut temp = target.as_os_str().to_owned();
+    temp.push(".restore");
+    let temp = PathBuf::from(temp);
+    fs::copy(backup, &temp)?;
+    fs::rename(&temp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,26 @@
+//! RimWorld XML Tools 的核心邏輯：掃描、解析與繼承合併，不依賴任何 GUI
+
+/// 程式版本（來自 Cargo.toml）
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod crash;
+pub mod database;
+pub mod defs;
+pub mod diff;
+pub mod export;
+pub mod file_writer;
+pub mod fs_abstraction;
+pub mod game_view;
+pub mod inheritance_logic;
+pub mod intern;
+pub mod journal;
+pub mod merge;
+pub mod patches;
+pub mod query;
+pub mod scan_cache;
+pub mod search;
+pub mod search_index;
+pub mod settings;
+pub mod status;
+pub mod validation;
+pub mod xml_parser;
@@ -0,0 +1,137 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+use crate::intern::Symbol;
+
+/// 輸入字母跳到條目時，超過此時間沒有輸入就重新開始比對
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// 列表中可用鍵盤移動到的一列：類型標題（`entry` 為 None）或其下的條目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavRow {
+    pub group: Option<String>, // 依模組分組時所在的模組
+    pub def_type: Symbol,
+    pub entry: Option<usize>,
+}
+
+/// 列表處理的按鍵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavKey {
+    Up,
+    Down,
+    Collapse,
+    Expand,
+    Toggle,
+    TypeAhead(String), // 目前累積輸入的字元
+}
+
+/// 瀏覽器列表的鍵盤操作：點擊列表後取得焦點，點擊其他地方或文字框取得焦點時失去
+///
+/// 列表每幀記錄顯示的列，下一幀依此決定方向鍵移動到哪一列
+#[derive(Default)]
+pub struct ListNavigation {
+    focused: bool,
+    rect: Option<egui::Rect>,   // 上一幀列表所佔的範圍
+    rows: Vec<NavRow>,          // 上一幀顯示的列，依畫面順序
+    building: Vec<NavRow>,      // 本幀正在記錄的列
+    pub cursor: Option<NavRow>, // 沒有選取條目時，鍵盤所在的類型標題
+    pub scroll: bool,           // 下次顯示時將游標所在的列捲到畫面內
+    typed: String,
+    last_typed: Option<Instant>,
+}
+
+impl ListNavigation {
+    /// 每幀開始時呼叫：依點擊位置更新焦點，列表有焦點且沒有文字框在輸入時取出要處理的按鍵
+    pub fn begin(&mut self, ctx: &egui::Context) -> Vec<NavKey> {
+        self.rows = std::mem::take(&mut self.building);
+        let pressed = ctx.input(|i| i.pointer.any_pressed().then(|| i.pointer.interact_pos()).flatten());
+        if let Some(pos) = pressed {
+            self.focused = self.rect.is_some_and(|rect| rect.contains(pos));
+        }
+        if !self.has_focus(ctx) {
+            return Vec::new();
+        }
+
+        ctx.input_mut(|i| {
+            let mut keys = Vec::new();
+            for (key, nav) in [
+                (egui::Key::ArrowUp, NavKey::Up),
+                (egui::Key::ArrowDown, NavKey::Down),
+                (egui::Key::ArrowLeft, NavKey::Collapse),
+                (egui::Key::ArrowRight, NavKey::Expand),
+                (egui::Key::Enter, NavKey::Toggle),
+            ] {
+                if i.consume_key(egui::Modifiers::NONE, key) {
+                    keys.push(nav);
+                }
+            }
+
+            let text: String = i
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    egui::Event::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            i.events.retain(|event| !matches!(event, egui::Event::Text(_)));
+            let text = text.trim();
+            if !text.is_empty() {
+                if self.last_typed.is_none_or(|at| at.elapsed() > TYPE_AHEAD_TIMEOUT) {
+                    self.typed.clear();
+                }
+                self.typed.push_str(text);
+                self.last_typed = Some(Instant::now());
+                keys.push(NavKey::TypeAhead(self.typed.clone()));
+            }
+            keys
+        })
+    }
+
+    /// 列表有鍵盤焦點：上次點擊在列表中，且沒有其他元件（文字框等）取得焦點
+    pub fn has_focus(&self, ctx: &egui::Context) -> bool {
+        self.focused && ctx.memory(|m| m.focused().is_none())
+    }
+
+    /// 記錄本幀列表所佔的範圍
+    pub fn set_rect(&mut self, rect: egui::Rect) {
+        self.rect = Some(rect);
+    }
+
+    /// 記錄本幀顯示的一列
+    pub fn push(&mut self, row: NavRow) {
+        self.building.push(row);
+    }
+
+    /// 上一幀顯示的列
+    pub fn rows(&self) -> &[NavRow] {
+        &self.rows
+    }
+
+    /// 類型標題在上一幀是否展開（其下有顯示的條目）
+    pub fn is_expanded(&self, header: &NavRow) -> bool {
+        self.rows
+            .iter()
+            .any(|r| r.entry.is_some() && r.def_type == header.def_type && r.group == header.group)
+    }
+
+    /// 游標是否在此類型標題上
+    pub fn is_cursor_header(&self, group: Option<&str>, def_type: &Symbol) -> bool {
+        self.cursor
+            .as_ref()
+            .is_some_and(|c| c.entry.is_none() && &c.def_type == def_type && c.group.as_deref() == group)
+    }
+}
+
+/// 從 `current` 往上或往下移動一列後的位置；沒有目前位置時往下從第一列、往上從最後一列開始
+pub fn step(len: usize, current: Option<usize>, down: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(match (current, down) {
+        (Some(i), true) => (i + 1).min(len - 1),
+        (Some(i), false) => i.saturating_sub(1),
+        (None, true) => 0,
+        (None, false) => len - 1,
+    })
+}
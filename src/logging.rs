@@ -0,0 +1,292 @@
+use chrono::Local;
+use eframe::egui;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::settings::{AppSettings, LogLevel};
+
+/// 日誌面板保留的最近行數
+const MAX_LOG_LINES: usize = 1000;
+
+/// 日誌檔超過此大小時，啟動時改名為 .1 並重新開始
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+fn to_log_level(level: &Level) -> LogLevel {
+    match *level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warn,
+        Level::INFO => LogLevel::Info,
+        _ => LogLevel::Debug,
+    }
+}
+
+fn level_color(level: LogLevel) -> egui::Color32 {
+    match level {
+        LogLevel::Error => egui::Color32::from_rgb(255, 80, 80),
+        LogLevel::Warn => egui::Color32::from_rgb(255, 200, 0),
+        LogLevel::Info => egui::Color32::from_rgb(0, 200, 0),
+        LogLevel::Debug => egui::Color32::GRAY,
+    }
+}
+
+/// 日誌面板中的一行
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub time: String,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// 日誌控制代碼：面板緩衝區、目前等級與日誌檔位置
+#[derive(Clone)]
+pub struct Logger {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    level: Arc<AtomicU8>,
+    log_path: Option<PathBuf>,
+}
+
+impl Logger {
+    /// 安裝全域 subscriber；`debug` 為真時同時輸出到終端並強制除錯等級
+    pub fn init(level: LogLevel, debug: bool) -> Self {
+        let log_path = log_file_path();
+        let file = log_path.as_ref().and_then(open_log_file);
+        let logger = Self {
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(MAX_LOG_LINES))),
+            level: Arc::new(AtomicU8::new(level as u8)),
+            log_path,
+        };
+        if debug {
+            logger.set_level(LogLevel::Debug);
+        }
+
+        let layer = LogLayer {
+            records: logger.records.clone(),
+            level: logger.level.clone(),
+            file: file.map(Mutex::new),
+        };
+        let console = debug.then(|| {
+            tracing_subscriber::fmt::layer()
+                .with_filter(tracing_subscriber::filter::LevelFilter::DEBUG)
+        });
+        let _ = tracing_subscriber::registry()
+            .with(layer)
+            .with(console)
+            .try_init();
+
+        logger
+    }
+
+    pub fn level(&self) -> LogLevel {
+        LogLevel::ALL
+            .get(self.level.load(Ordering::Relaxed) as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn log_path(&self) -> Option<&PathBuf> {
+        self.log_path.as_ref()
+    }
+
+    /// 最近的日誌行（已依等級篩選）
+    pub fn recent(&self, max_level: LogLevel) -> Vec<LogRecord> {
+        self.records
+            .lock()
+            .map(|records| {
+                records
+                    .iter()
+                    .filter(|r| r.level as u8 <= max_level as u8)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut records) = self.records.lock() {
+            records.clear();
+        }
+    }
+}
+
+/// 日誌檔路徑
+fn log_file_path() -> Option<PathBuf> {
+    AppSettings::config_dir().ok().map(|dir| dir.join("rimworld-xml-tools.log"))
+}
+
+/// 開啟日誌檔（附加模式），過大時先輪替
+fn open_log_file(path: &PathBuf) -> Option<File> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if std::fs::metadata(path).is_ok_and(|m| m.len() > MAX_LOG_FILE_BYTES) {
+        let _ = std::fs::rename(path, path.with_extension("log.1"));
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+/// 將事件寫入面板緩衝區與日誌檔的 layer
+struct LogLayer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    level: Arc<AtomicU8>,
+    file: Option<Mutex<File>>,
+}
+
+/// span 的欄位（例如正在解析的檔案），附加在其中事件的訊息後
+struct SpanFields(String);
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = to_log_level(event.metadata().level());
+        if level as u8 > self.level.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        message.push_str(&visitor.fields);
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    message.push_str(&fields.0);
+                }
+            }
+        }
+
+        let now = Local::now();
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(
+                    file,
+                    "{} {:5} {}: {}",
+                    now.format("%Y-%m-%d %H:%M:%S%.3f"),
+                    event.metadata().level(),
+                    event.metadata().target(),
+                    message
+                );
+            }
+        }
+
+        if let Ok(mut records) = self.records.lock() {
+            if records.len() >= MAX_LOG_LINES {
+                records.pop_front();
+            }
+            records.push_back(LogRecord {
+                time: now.format("%H:%M:%S").to_string(),
+                level,
+                message,
+            });
+        }
+    }
+}
+
+/// 將事件欄位格式化為 `訊息 key=value ...`
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// 底部日誌面板
+pub struct LogPanel {
+    logger: Logger,
+    filter: LogLevel, // 面板只顯示此等級（含）以上的訊息
+}
+
+impl LogPanel {
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            filter: LogLevel::Debug,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("📜 日誌");
+            egui::ComboBox::from_id_salt("log_level_filter")
+                .selected_text(self.filter.label())
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut self.filter, level, level.label());
+                    }
+                });
+            if ui.button("🗑 清除").clicked() {
+                self.logger.clear();
+            }
+            if let Some(path) = self.logger.log_path() {
+                if ui.button("📄 開啟日誌檔").clicked() {
+                    crate::browser::open_file_with_default_app(path);
+                }
+            }
+            ui.weak(format!("檔案記錄等級: {}", self.logger.level().label()));
+        });
+
+        ui.separator();
+
+        let records = self.logger.recent(self.filter);
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .id_salt("log_lines")
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, records.len(), |ui, range| {
+                for record in &records[range] {
+                    ui.horizontal(|ui| {
+                        ui.monospace(&record.time);
+                        ui.colored_label(level_color(record.level), record.level.label());
+                        ui.monospace(&record.message);
+                    });
+                }
+            });
+    }
+}
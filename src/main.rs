@@ -1,19 +1,125 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod xml_parser;
 mod browser;
+mod changelog;
+mod command_palette;
+mod export_preview;
 mod finder;
+mod game_view_tab;
+mod history;
 mod inheritance;
-mod settings;
+mod list_navigation;
+mod logging;
+mod onboarding;
+mod parse_errors;
+mod popout;
+mod quick_analysis;
+mod renderers;
+mod scanner;
+mod settings_tab;
+mod statistics;
+mod textures;
+mod toast;
+mod validator;
+mod widgets;
 
 use eframe::egui;
-use finder::TagFinderTab;
-use browser::DefBrowserTab;
-use inheritance::InheritanceTab;
-use settings::{AppSettings, SettingsTab};
-use std::sync::{Arc, Mutex};
+use rimworld_xml_tools::{
+    crash, database, defs, diff, export, file_writer, fs_abstraction, game_view, inheritance_logic, intern, journal, merge,
+    patches, scan_cache, search, search_index, settings, status, validation, xml_parser,
+};
+use finder::{FinderUiState, TagFinderTab};
+use browser::{BrowserUiState, DefBrowserTab};
+use command_palette::{Command, CommandPalette, CommandRegistry, PaletteChoice};
+use database::{find_def, SharedDatabase, SharedDefDatabase};
+use export_preview::Exporter;
+use file_writer::{shared_writer, SharedWriter};
+use game_view_tab::GameViewTab;
+use history::HistoryWindow;
+use inheritance::{InheritanceTab, InheritanceUiState};
+use logging::{LogPanel, Logger};
+use onboarding::{Onboarding, OnboardingResult};
+use parse_errors::ParseErrorWindow;
+use popout::Popouts;
+use quick_analysis::QuickAnalysis;
+use scanner::{ScanCoordinator, ScanRequest};
+use settings::{AppSettings, PortableMode};
+use settings_tab::SettingsTab;
+use statistics::StatisticsTab;
+use status::{StatusSink, StatusState};
+use textures::TextureLoader;
+use validator::ValidatorTab;
+use widgets::{show_status, take_open_settings_request};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 跨重新啟動保存的介面狀態（透過 eframe 的儲存空間），各分頁各自提供一段
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UiState {
+    active_tab: usize,
+    show_log_panel: bool,
+    browser: BrowserUiState,
+    inheritance: InheritanceUiState,
+    finder: FinderUiState,
+}
+
+/// 命令列參數
+struct LaunchArgs {
+    debug: bool,
+    dry_run: bool, // --dry-run：匯出等寫入操作只列出檔案，不實際寫入
+    path: Option<PathBuf>, // 啟動時開啟的目錄或 XML 檔案
+    startup_action: Option<StartupAction>,
+}
+
+/// 第一次掃描完成後執行一次的動作
+enum StartupAction {
+    OpenDef(String), // --open-def：在瀏覽器中開啟指定的 defName
+}
+
+impl LaunchArgs {
+    fn parse() -> Self {
+        let mut args = Self {
+            debug: false,
+            dry_run: false,
+            path: None,
+            startup_action: None,
+        };
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            if arg == "--debug" {
+                args.debug = true;
+            } else if arg == "--dry-run" {
+                args.dry_run = true;
+            } else if arg == "--open-def" {
+                args.startup_action = iter.next().map(StartupAction::OpenDef);
+            } else if let Some(def_name) = arg.strip_prefix("--open-def=") {
+                args.startup_action = Some(StartupAction::OpenDef(def_name.to_string()));
+            } else if !arg.starts_with("--") && args.path.is_none() {
+                args.path = Some(PathBuf::from(arg));
+            }
+        }
+        args
+    }
+}
 
 fn main() -> eframe::Result {
+    crash::install_panic_hook();
+
+    let launch_args = LaunchArgs::parse();
+
+    let settings = AppSettings::load();
+
+    // 日誌寫入設置目錄；以 --debug 或 RWXT_LOG=debug 另外輸出到終端
+    let debug_logging = launch_args.debug
+        || std::env::var("RWXT_LOG").is_ok_and(|v| v.eq_ignore_ascii_case("debug"));
+    let logger = Logger::init(settings.log_level, debug_logging);
+    tracing::info!(version = changelog::VERSION, "程式啟動");
+    if let Some(warning) = PortableMode::current().warning() {
+        tracing::warn!("{}", warning);
+    }
+
     // 載入圖標
     let icon_data = load_icon();
 
@@ -31,7 +137,19 @@ fn main() -> eframe::Result {
         Box::new(|cc| {
             // 設置中文字體
             setup_custom_fonts(&cc.egui_ctx);
-            Ok(Box::new(XmlToolsApp::default()))
+            let ui_state = cc
+                .storage
+                .and_then(|storage| eframe::get_value::<UiState>(storage, eframe::APP_KEY))
+                .unwrap_or_default();
+            Ok(Box::new(XmlToolsApp::new(
+                settings,
+                ui_state,
+                logger,
+                debug_logging,
+                shared_writer(launch_args.dry_run),
+                launch_args.path,
+                launch_args.startup_action,
+            )))
         }),
     )
 }
@@ -103,40 +221,453 @@ struct XmlToolsApp {
     finder: TagFinderTab,
     browser: DefBrowserTab,
     inheritance: InheritanceTab,
+    statistics: StatisticsTab,
+    validator: ValidatorTab,
+    game_view: GameViewTab,
     settings_tab: SettingsTab,
+    scanner: ScanCoordinator,
+    database: SharedDatabase,
+    settings: Arc<Mutex<AppSettings>>,
+    status: StatusSink,
     active_tab: usize,
+    startup_warning: Option<String>, // 啟動參數無效時顯示的警告
+    crash_log: Option<PathBuf>,      // 上次執行當機時留下的日誌
+    logger: Logger,
+    log_panel: LogPanel,
+    show_log_panel: bool,
+    debug_logging: bool, // 命令列強制除錯等級，不套用設置中的等級
+    show_whats_new: bool, // 更新後第一次啟動時顯示更新內容
+    quick_analysis: QuickAnalysis,
+    history: HistoryWindow,
+    parse_errors: ParseErrorWindow,
+    command_palette: CommandPalette,
+    commands: CommandRegistry<XmlToolsApp>, // 命令面板中的命令
+    popouts: Popouts,
+    textures: TextureLoader,
+    exporter: Exporter, // 匯出前的確認視窗（僅預覽、試執行）
+    onboarding: Option<Onboarding>, // 第一次啟動時的歡迎面板
+    startup_action: Option<StartupAction>, // 等第一次掃描完成後執行
+    first_frame: bool, // 第一幀時套用設置中的主題
 }
 
-impl Default for XmlToolsApp {
-    fn default() -> Self {
-        let settings = Arc::new(Mutex::new(AppSettings::load()));
-        Self {
-            finder: TagFinderTab::new(settings.clone()),
-            browser: DefBrowserTab::new(settings.clone()),
-            inheritance: InheritanceTab::new(settings.clone()),
-            settings_tab: SettingsTab::new(settings.clone()),
+impl XmlToolsApp {
+    fn new(
+        settings: AppSettings,
+        ui_state: UiState,
+        logger: Logger,
+        debug_logging: bool,
+        writer: SharedWriter,
+        launch_path: Option<PathBuf>,
+        startup_action: Option<StartupAction>,
+    ) -> Self {
+        let first_run = AppSettings::is_first_run();
+        let mut settings = settings;
+        if first_run {
+            // 新使用者不需要看更新內容
+            settings.last_seen_version = changelog::VERSION.to_string();
+        }
+        let show_whats_new = settings.last_seen_version != changelog::VERSION;
+        let onboarding = (first_run && launch_path.is_none()).then(Onboarding::default);
+        let settings = Arc::new(Mutex::new(settings));
+        let database: SharedDatabase = Arc::new(RwLock::new(SharedDefDatabase::default()));
+        let status: StatusSink = Arc::new(Mutex::new(StatusState::default()));
+        if writer.is_dry_run() {
+            tracing::info!("試執行模式：寫入操作只會列出檔案");
+            if let Ok(mut status) = status.lock() {
+                status.set_message("試執行", "試執行模式：匯出前會列出將寫入的檔案，確認後也只在終端列出，不會實際寫入");
+            }
+        }
+        let scan_request = ScanRequest::default();
+        let popouts = Popouts::default();
+        let textures = TextureLoader::default();
+        let exporter = Exporter::new(writer, settings.clone(), status.clone());
+        let mut app = Self {
+            finder: TagFinderTab::new(
+                settings.clone(),
+                database.clone(),
+                status.clone(),
+                scan_request.clone(),
+            ),
+            browser: DefBrowserTab::new(
+                settings.clone(),
+                database.clone(),
+                scan_request.clone(),
+                popouts.clone(),
+                textures.clone(),
+                exporter.clone(),
+            ),
+            inheritance: InheritanceTab::new(
+                settings.clone(),
+                database.clone(),
+                scan_request.clone(),
+                status.clone(),
+                popouts.clone(),
+                exporter.clone(),
+            ),
+            statistics: StatisticsTab::new(
+                settings.clone(),
+                database.clone(),
+                status.clone(),
+                exporter.clone(),
+            ),
+            validator: ValidatorTab::new(settings.clone(), database.clone(), scan_request.clone()),
+            game_view: GameViewTab::new(settings.clone(), status.clone()),
+            settings_tab: SettingsTab::new(settings.clone(), database.clone(), status.clone()),
+            quick_analysis: QuickAnalysis::new(database.clone(), settings.clone()),
+            history: HistoryWindow::new(status.clone()),
+            parse_errors: ParseErrorWindow::new(database.clone(), settings.clone()),
+            command_palette: CommandPalette::new(database.clone()),
+            commands: register_commands(),
+            popouts,
+            textures,
+            onboarding,
+            startup_action,
+            exporter,
+            first_frame: true,
+            scanner: ScanCoordinator::new(
+                database.clone(),
+                status.clone(),
+                settings.clone(),
+                scan_request,
+            ),
+            database,
+            settings,
+            status,
             active_tab: 0,
+            startup_warning: PortableMode::current().warning(),
+            crash_log: crash::take_crash_marker(),
+            log_panel: LogPanel::new(logger.clone()),
+            logger,
+            show_log_panel: false,
+            debug_logging,
+            show_whats_new,
+        };
+
+        app.load_ui_state(ui_state);
+
+        // 命令列參數優先於上次的介面狀態
+        if let Some(path) = launch_path {
+            app.open_launch_path(path);
+        }
+        app
+    }
+
+    fn load_ui_state(&mut self, state: UiState) {
+        self.active_tab = state.active_tab;
+        self.show_log_panel = state.show_log_panel;
+        self.browser.load_state(state.browser);
+        self.inheritance.load_state(state.inheritance);
+        self.finder.load_state(state.finder);
+    }
+
+    fn save_ui_state(&self) -> UiState {
+        UiState {
+            active_tab: self.active_tab,
+            show_log_panel: self.show_log_panel,
+            browser: self.browser.save_state(),
+            inheritance: self.inheritance.save_state(),
+            finder: self.finder.save_state(),
+        }
+    }
+
+    /// 套用歡迎面板的選擇並儲存設置（設置檔存在後不會再顯示歡迎面板）
+    fn finish_onboarding(&mut self, result: OnboardingResult) {
+        let Ok(mut settings) = self.settings.lock() else {
+            return;
+        };
+        if let Some(path) = result.base_path {
+            if !result.scan_now {
+                self.scanner.skip_path(&path);
+            }
+            settings.set_base_path(path);
+        }
+        settings.save();
+    }
+
+    /// 掃描完成後執行命令列指定的動作，只執行一次
+    fn run_startup_action(&mut self) {
+        if self.startup_action.is_none() || !self.scanner.is_idle() {
+            return;
+        }
+        let generation = self.database.read().map_or(0, |db| db.generation);
+        let has_base_path = self.settings.lock().is_ok_and(|s| !s.active_base_path().is_empty());
+        if generation == 0 && has_base_path {
+            return; // 尚未開始第一次掃描
+        }
+        let Some(action) = self.startup_action.take() else {
+            return;
+        };
+
+        match action {
+            StartupAction::OpenDef(def_name) => {
+                let def_type = self.database.read().ok().and_then(|db| {
+                    find_def(&db, &def_name).map(|entry| entry.def_type.to_string())
+                });
+                match def_type {
+                    Some(def_type) => {
+                        self.browser.select_def(&def_type, &def_name);
+                        self.active_tab = 0;
+                    }
+                    None if generation == 0 => {
+                        self.startup_warning =
+                            Some(format!("尚未設定工作目錄，無法開啟 Def: {}", def_name));
+                    }
+                    None => {
+                        self.startup_warning =
+                            Some(format!("掃描完成，但找不到命令列指定的 Def: {}", def_name));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 開啟命令列傳入的路徑；只用於本次執行，不會寫入設置檔
+    fn open_launch_path(&mut self, path: PathBuf) {
+        let path = if path.is_absolute() {
+            path
+        } else {
+            std::env::current_dir().map(|dir| dir.join(&path)).unwrap_or(path)
+        };
+
+        let base_path = if path.is_dir() {
+            path.clone()
+        } else if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("xml") {
+            // 以檔案所在目錄為基礎，並在瀏覽器中只顯示此檔案的 Defs
+            self.browser.focus_file(&path);
+            self.active_tab = 0;
+            match path.parent() {
+                Some(parent) => parent.to_path_buf(),
+                None => return,
+            }
+        } else {
+            self.startup_warning = Some(format!(
+                "無法開啟命令列指定的路徑（不存在或不是目錄 / XML 檔案）:\n{}",
+                path.display()
+            ));
+            return;
+        };
+
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.session_base_path = Some(base_path);
+        }
+        if let Ok(mut status) = self.status.lock() {
+            status.set_message("命令列", format!("已開啟 {}", path.display()));
         }
     }
 }
 
 impl eframe::App for XmlToolsApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.save_ui_state());
+        self.browser.flush_recent();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // 路徑變更或有分頁要求時，在背景統一掃描一次
+        self.scanner.update(ctx);
+        self.run_startup_action();
+        self.browser.save_recent_when_idle(ctx);
+        self.exporter.show(ctx);
+
+        if self.first_frame {
+            self.first_frame = false;
+            let dark_mode = self.settings.lock().map_or(true, |s| s.dark_mode);
+            apply_theme(ctx, dark_mode);
+        }
+
+        let texture_budget = self.settings.lock().map_or(64, |s| s.texture_cache_mb);
+        self.textures.begin_frame(ctx, texture_budget);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.selectable_value(&mut self.active_tab, 0, "📚 Def 瀏覽器");
                 ui.selectable_value(&mut self.active_tab, 1, "🔗 展開繼承");
                 ui.selectable_value(&mut self.active_tab, 2, "🔍 標籤查找器");
-                ui.selectable_value(&mut self.active_tab, 3, "🔧 設置");
+                ui.selectable_value(&mut self.active_tab, 3, "📊 統計");
+                ui.selectable_value(&mut self.active_tab, 4, "✅ 驗證");
+                ui.selectable_value(&mut self.active_tab, 5, "🎮 最終載入結果");
+                ui.selectable_value(&mut self.active_tab, 6, "🔧 設置");
+                ui.separator();
+                if ui
+                    .button("🧪 快速分析")
+                    .on_hover_text("貼上或開啟單一 XML 檔案進行分析，不需掃描目錄")
+                    .clicked()
+                {
+                    self.quick_analysis.open();
+                }
+                if ui
+                    .button("🕘 操作記錄")
+                    .on_hover_text("查看並復原寫入檔案的操作")
+                    .clicked()
+                {
+                    self.history.open();
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let dark_mode = ctx.style().visuals.dark_mode;
+                    let (icon, hint) = if dark_mode {
+                        ("☀", "切換為淺色主題")
+                    } else {
+                        ("🌙", "切換為深色主題")
+                    };
+                    if ui.button(icon).on_hover_text(hint).clicked() {
+                        apply_theme(ctx, !dark_mode);
+                        if let Ok(mut settings) = self.settings.lock() {
+                            settings.dark_mode = !dark_mode;
+                            settings.save();
+                        }
+                    }
+                });
             });
         });
 
+        // 拖放 XML 檔案到視窗上時以快速分析開啟
+        let dropped_xml = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .find(|p| p.extension().and_then(|s| s.to_str()) == Some("xml"))
+        });
+        if let Some(path) = dropped_xml {
+            self.quick_analysis.open_file(&path);
+        }
+        if let Some(def_data) = self.quick_analysis.show(ctx) {
+            self.inheritance.expand_adhoc(def_data);
+            self.active_tab = 1;
+        }
+
+        // F5: 在目前分頁重新掃描
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+            match self.active_tab {
+                0 => self.browser.scan_defs(),
+                1 => self.inheritance.scan_all_defs(),
+                2 => self.finder.rescan(),
+                3 => self.statistics.analyze_may_require(ctx.clone()),
+                4 => self.validator.rescan(),
+                5 => self.game_view.build(ctx),
+                _ => {}
+            }
+        }
+
+        // 套用設置中的日誌等級
+        if !self.debug_logging {
+            if let Ok(settings) = self.settings.lock() {
+                self.logger.set_level(settings.log_level);
+            }
+        }
+
+        // Ctrl+Shift+P: 命令面板；Ctrl+P: 命令與任一 Def（Shift 組合需先檢查）
+        let command_shift = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+        if ctx.input_mut(|i| i.consume_key(command_shift, egui::Key::P)) {
+            self.command_palette.open(true, &self.commands);
+        } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.command_palette.open(false, &self.commands);
+        }
+        match self.command_palette.show(ctx, &self.commands) {
+            Some(PaletteChoice::Command(index)) => {
+                let commands = std::mem::take(&mut self.commands);
+                commands.run(index, self, ctx);
+                self.commands = commands;
+            }
+            Some(PaletteChoice::Def(def_type, def_name)) => {
+                self.browser.select_def(&def_type, &def_name);
+                self.active_tab = 0;
+            }
+            None => {}
+        }
+
+        self.history.show(ctx);
+        self.parse_errors.show(ctx);
+        self.popouts.show(ctx);
+
+        if take_open_settings_request(ctx) {
+            self.active_tab = 6;
+        }
+
+        self.status_bar(ctx);
+
+        if let Ok(mut status) = self.status.lock() {
+            toast::show_toasts(ctx, &mut status.toasts);
+        }
+
+        if self.show_log_panel {
+            egui::TopBottomPanel::bottom("log_panel")
+                .resizable(true)
+                .default_height(200.0)
+                .show(ctx, |ui| self.log_panel.ui(ui));
+            // 背景執行緒寫入新日誌時持續更新
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+
+        // 第一次啟動時在瀏覽分頁上顯示歡迎面板
+        if self.active_tab == 0 {
+            if let Some(result) = self.onboarding.as_mut().and_then(|o| o.show(ctx)) {
+                self.onboarding = None;
+                self.finish_onboarding(result);
+            }
+        }
+
+        // 啟動參數無效時的非致命警告
+        if let Some(warning) = &self.startup_warning {
+            let mut close = false;
+            egui::Window::new("⚠ 警告")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(warning);
+                    if ui.button("確定").clicked() {
+                        close = true;
+                    }
+                });
+            if close {
+                self.startup_warning = None;
+            }
+        }
+
+        // 上次執行當機時提示開啟日誌
+        if let Some(log_path) = &self.crash_log {
+            let mut close = false;
+            egui::Window::new("⚠ 上次執行時發生錯誤")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("程式上次意外結束，錯誤訊息已記錄在日誌中。回報問題時請附上此檔案。");
+                    ui.label(log_path.display().to_string());
+                    ui.horizontal(|ui| {
+                        if ui.button("📄 開啟日誌").clicked() {
+                            browser::open_file_with_default_app(log_path);
+                            close = true;
+                        }
+                        if ui.button("關閉").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                self.crash_log = None;
+            }
+        }
+
+        // 更新後顯示更新內容，確認後記錄已看過的版本
+        if self.show_whats_new && changelog::show_whats_new(ctx) {
+            self.show_whats_new = false;
+            if let Ok(mut settings) = self.settings.lock() {
+                settings.last_seen_version = changelog::VERSION.to_string();
+                settings.save();
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.active_tab {
                 0 => self.browser.ui(ui, ctx),
                 1 => self.inheritance.ui(ui, ctx),
                 2 => self.finder.ui(ui, ctx),
-                3 => self.settings_tab.ui(ui, ctx),
+                3 => self.statistics.ui(ui, ctx),
+                4 => self.validator.ui(ui, ctx),
+                5 => self.game_view.ui(ui, ctx),
+                6 => self.settings_tab.ui(ui, ctx),
                 _ => {
                     ui.heading("未實現的功能");
                 }
@@ -144,3 +675,132 @@ impl eframe::App for XmlToolsApp {
         });
     }
 }
+
+/// 切換 egui 內建的深色或淺色主題
+fn apply_theme(ctx: &egui::Context, dark_mode: bool) {
+    ctx.set_visuals(if dark_mode {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    });
+}
+
+/// 加入分頁提供的命令，執行後切換到該分頁
+fn add_tab_commands<T: 'static>(
+    registry: &mut CommandRegistry<XmlToolsApp>,
+    tab: usize,
+    commands: Vec<Command<T>>,
+    project: fn(&mut XmlToolsApp) -> &mut T,
+) {
+    registry.extend(commands.into_iter().map(|command| {
+        command.map(TAB_NAMES[tab], project, move |app: &mut XmlToolsApp| app.active_tab = tab)
+    }));
+}
+
+/// 分頁名稱，依 `active_tab` 的索引排列
+const TAB_NAMES: [&str; 7] = [
+    "Def 瀏覽器",
+    "展開繼承",
+    "標籤查找器",
+    "統計",
+    "驗證",
+    "最終載入結果",
+    "設置",
+];
+
+/// 命令面板的命令：應用層級的命令與各分頁提供的命令（執行後切換到該分頁）
+fn register_commands() -> CommandRegistry<XmlToolsApp> {
+    let mut registry = CommandRegistry::default();
+
+    for (index, name) in TAB_NAMES.iter().enumerate() {
+        registry.add(format!("切換到{}分頁", name), move |app: &mut XmlToolsApp, _| {
+            app.active_tab = index;
+        });
+    }
+    registry.add("開啟設定", |app: &mut XmlToolsApp, _| app.active_tab = 6);
+    registry.add("快速分析 XML", |app: &mut XmlToolsApp, _| app.quick_analysis.open());
+    registry.add("復原上一次操作", |app: &mut XmlToolsApp, _| app.history.undo_last());
+    registry.add("操作記錄", |app: &mut XmlToolsApp, _| app.history.open());
+    registry.add("查看解析錯誤", |app: &mut XmlToolsApp, _| app.parse_errors.open());
+    registry.add("顯示/隱藏日誌面板", |app: &mut XmlToolsApp, _| {
+        app.show_log_panel = !app.show_log_panel;
+    });
+
+    add_tab_commands(&mut registry, 0, DefBrowserTab::commands(), |app| &mut app.browser);
+    add_tab_commands(&mut registry, 1, InheritanceTab::commands(), |app| &mut app.inheritance);
+    add_tab_commands(&mut registry, 2, TagFinderTab::commands(), |app| &mut app.finder);
+    add_tab_commands(&mut registry, 3, StatisticsTab::commands(), |app| &mut app.statistics);
+    add_tab_commands(&mut registry, 4, ValidatorTab::commands(), |app| &mut app.validator);
+    add_tab_commands(&mut registry, 5, GameViewTab::commands(), |app| &mut app.game_view);
+
+    registry
+}
+
+impl XmlToolsApp {
+    /// 底部全域狀態列
+    fn status_bar(&mut self, ctx: &egui::Context) {
+        let base_path = self
+            .settings
+            .lock()
+            .map(|s| s.active_base_path())
+            .unwrap_or_default();
+        let error_count = self.database.read().map(|db| db.errors.len()).unwrap_or_default();
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            let Ok(mut status) = self.status.lock() else {
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut self.show_log_panel, "📜 日誌");
+                ui.separator();
+
+                ui.label("📁");
+                if base_path.is_empty() {
+                    ui.weak("（尚未設置工作目錄）");
+                } else {
+                    ui.label(&base_path);
+                }
+
+                ui.separator();
+
+                // 最近一次操作的訊息
+                show_status(ui, &mut status.message);
+                if error_count > 0
+                    && ui
+                        .small_button(format!("⚠ 查看錯誤 ({})", error_count))
+                        .on_hover_text("列出上次掃描時無法完整解析的檔案")
+                        .clicked()
+                {
+                    self.parse_errors.open();
+                }
+
+                // 背景工作進度
+                if status.is_busy() {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add(egui::Spinner::new());
+                        for task in &status.tasks {
+                            match task.fraction() {
+                                Some(fraction) => {
+                                    ui.add(
+                                        egui::ProgressBar::new(fraction)
+                                            .desired_width(150.0)
+                                            .text(format!("{} {}/{}", task.name, task.done, task.total)),
+                                    );
+                                }
+                                None => {
+                                    ui.label(&task.name);
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+
+            // 工作進行中持續重繪
+            if status.is_busy() {
+                ctx.request_repaint();
+            }
+        });
+    }
+}
@@ -0,0 +1,673 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::defs::DefRootRules;
+use crate::intern::Symbol;
+use crate::xml_parser::{find_version_comment, read_xml_file};
+
+/// 繼承展開使用的 Def 資料（包括只有 Name 的抽象定義）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefData {
+    pub def_name: Symbol,        // defName 或 Name (for Abstract)
+    pub parent_name: Option<Symbol>,
+    #[allow(dead_code)]
+    pub file_path: PathBuf,
+    #[allow(dead_code)]
+    pub xml_content: String,
+    pub is_abstract: bool,
+    pub def_type: Symbol,        // ThingDef, RecipeDef, etc.
+    pub raw_nodes: Vec<XmlNode>, // 原始 XML 節點結構
+    pub depth: u8,               // 繼承深度（ParentName 的層數），掃描後計算
+    pub file_version: Option<String>, // 檔案註解中標示的 RimWorld 版本
+    pub attributes: Vec<(Symbol, Symbol)>, // Def 元素本身的屬性（MayRequire 等）
+    pub is_temp: bool,           // 在展開繼承中貼上的暫時 Def，不在任何檔案中，下次掃描時清除
+}
+
+/// Def 內的 XML 節點
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XmlNode {
+    pub tag: Symbol,
+    pub attributes: Vec<(Symbol, Symbol)>,
+    pub children: Vec<XmlNode>,
+    pub text: Option<Symbol>,
+}
+
+/// 計算所有 Def 的繼承深度；遇到循環或找不到的父類時停止
+pub fn compute_depths(all_defs: &mut HashMap<Symbol, DefData>) {
+    let depths: Vec<(Symbol, u8)> = all_defs
+        .keys()
+        .map(|name| (name.clone(), inheritance_depth(all_defs, name)))
+        .collect();
+    for (name, depth) in depths {
+        if let Some(def) = all_defs.get_mut(&name) {
+            def.depth = depth;
+        }
+    }
+}
+
+fn inheritance_depth(all_defs: &HashMap<Symbol, DefData>, def_name: &str) -> u8 {
+    let mut depth: u8 = 0;
+    let mut visited = HashSet::new();
+    let mut current = all_defs.get(def_name).and_then(|d| d.parent_name.as_ref());
+    while let Some(parent_name) = current {
+        if !visited.insert(parent_name) {
+            break;
+        }
+        depth = depth.saturating_add(1);
+        current = all_defs.get(parent_name).and_then(|d| d.parent_name.as_ref());
+    }
+    depth
+}
+
+/// 每個 Abstract Def 底下的具體（非 Abstract）子孫數量；指定 `def_type` 時只計算該類型的子孫
+pub fn concrete_descendant_counts(
+    all_defs: &HashMap<Symbol, DefData>,
+    def_type: Option<&Symbol>,
+) -> HashMap<Symbol, usize> {
+    let mut counts: HashMap<Symbol, usize> = HashMap::new();
+    let concrete = all_defs
+        .values()
+        .filter(|d| !d.is_abstract && def_type.is_none_or(|t| d.def_type == *t));
+    for def in concrete {
+        // 沿著父類往上走，每個 Abstract 祖先都多一個子孫
+        let mut visited = HashSet::new();
+        let mut current = def.parent_name.as_ref();
+        while let Some(parent_name) = current {
+            if !visited.insert(parent_name) {
+                break;
+            }
+            let Some(parent) = all_defs.get(parent_name) else {
+                break;
+            };
+            if parent.is_abstract {
+                *counts.entry(parent_name.clone()).or_default() += 1;
+            }
+            current = parent.parent_name.as_ref();
+        }
+    }
+    counts
+}
+
+/// 合併繼承鏈的順序
+///
+/// - `RootFirst`：從最頂層父類開始，子類的欄位整個覆蓋父類的同名欄位（與遊戲相同）
+/// - `LeafFirst`：從此 Def 開始往上，父類只補上子類沒有定義的欄位；
+///   巢狀欄位（例如 `statBases`）會逐一補齊而不是整個覆蓋，`<li>` 列表中子類的項目排在前面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MergeOrder {
+    #[default]
+    RootFirst,
+    LeafFirst,
+}
+
+impl MergeOrder {
+    pub const ALL: [MergeOrder; 2] = [MergeOrder::RootFirst, MergeOrder::LeafFirst];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MergeOrder::RootFirst => "由父到子（覆蓋）",
+            MergeOrder::LeafFirst => "由子到父（補齊）",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            MergeOrder::RootFirst => {
+                "從最頂層父類開始套用，子類定義的欄位整個取代父類的同名欄位（與遊戲載入時相同）"
+            }
+            MergeOrder::LeafFirst => {
+                "從此 Def 開始往上，父類只補上子類沒有定義的欄位；巢狀欄位逐一補齊，列表中子類的項目排在前面"
+            }
+        }
+    }
+}
+
+/// 展開繼承的結果
+#[derive(Debug, Clone, Default)]
+pub struct ExpandedDef {
+    pub chain: Vec<String>, // 繼承鏈，由最頂層父類到此 Def
+    pub xml: String,
+}
+
+/// 沿 ParentName 合併所有父類的節點並產生展開後的 XML；找不到的父類會出現在繼承鏈中但沒有內容
+pub fn expand_def(
+    def_data: &DefData,
+    all_defs: &HashMap<Symbol, DefData>,
+    order: MergeOrder,
+) -> ExpandedDef {
+    let (chain, ancestors) = ancestors(def_data, all_defs);
+    let merged_nodes = merge_ancestors(&ancestors, order);
+
+    // 生成展開的 XML
+    let xml = generate_expanded_xml(&def_data.def_name, &def_data.def_type, &merged_nodes);
+    ExpandedDef { chain, xml }
+}
+
+/// 合併繼承鏈上所有節點後的欄位（標籤 -> 節點）
+pub fn merged_nodes(
+    def_data: &DefData,
+    all_defs: &HashMap<Symbol, DefData>,
+    order: MergeOrder,
+) -> BTreeMap<Symbol, XmlNode> {
+    merge_ancestors(&ancestors(def_data, all_defs).1, order)
+}
+
+/// 繼承鏈名稱與找得到的祖先（皆由最頂層父類到此 Def）
+fn ancestors<'a>(
+    def_data: &'a DefData,
+    all_defs: &'a HashMap<Symbol, DefData>,
+) -> (Vec<String>, Vec<&'a DefData>) {
+    // 建立繼承鏈（由子類到父類）
+    let mut ancestors = vec![def_data];
+    let mut chain = vec![def_data.def_name.to_string()];
+    let mut current_parent = def_data.parent_name.as_ref();
+
+    while let Some(parent_name) = current_parent {
+        chain.push(parent_name.to_string());
+        if let Some(parent_def) = all_defs.get(parent_name) {
+            ancestors.push(parent_def);
+            current_parent = parent_def.parent_name.as_ref();
+        } else {
+            break;
+        }
+    }
+
+    chain.reverse();
+    ancestors.reverse();
+    (chain, ancestors)
+}
+
+fn merge_ancestors(ancestors: &[&DefData], order: MergeOrder) -> BTreeMap<Symbol, XmlNode> {
+    let mut merged_nodes: BTreeMap<Symbol, XmlNode> = BTreeMap::new();
+
+    match order {
+        // 從最頂層父類開始，後面的覆蓋前面的
+        MergeOrder::RootFirst => {
+            for ancestor in ancestors {
+                for node in &ancestor.raw_nodes {
+                    merge_node(&mut merged_nodes, node);
+                }
+            }
+        }
+        // 從此 Def 開始，父類只補上尚未定義的部分
+        MergeOrder::LeafFirst => {
+            for ancestor in ancestors.iter().rev() {
+                for node in &ancestor.raw_nodes {
+                    match merged_nodes.get_mut(&node.tag) {
+                        Some(existing) => fill_node(existing, node),
+                        None => {
+                            merged_nodes.insert(node.tag.clone(), node.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    merged_nodes
+}
+
+// 以父類節點補齊已定義的節點：有文本值的欄位保留，<li> 附加不重複的項目，其他子節點遞迴補齊
+fn fill_node(existing: &mut XmlNode, parent: &XmlNode) {
+    if existing.children.is_empty() && existing.text.is_some() {
+        return;
+    }
+    for child in &parent.children {
+        if child.tag == "li" {
+            if !existing.children.iter().any(|c| same_li(c, child)) {
+                existing.children.push(child.clone());
+            }
+        } else if let Some(own) = existing.children.iter_mut().find(|c| c.tag == child.tag) {
+            fill_node(own, child);
+        } else {
+            existing.children.push(child.clone());
+        }
+    }
+}
+
+// 文本相同且屬性相同的 <li> 視為重複
+fn same_li(a: &XmlNode, b: &XmlNode) -> bool {
+    a.tag == "li"
+        && a.text.as_deref().unwrap_or("") == b.text.as_deref().unwrap_or("")
+        && a.attributes == b.attributes
+}
+
+// 合併節點：對於 <li> 標籤進行合併，其他標籤覆蓋
+fn merge_node(merged: &mut BTreeMap<Symbol, XmlNode>, node: &XmlNode) {
+    let key = node.tag.clone();
+    
+    if let Some(existing) = merged.get_mut(&key) {
+        // 已存在此標籤
+        // 檢查是否包含 <li> 子節點
+        let has_li_children = node.children.iter().any(|c| c.tag == "li");
+        
+        if has_li_children {
+            // 合併 <li> 子節點
+            for child in &node.children {
+                if child.tag == "li" {
+                    // 檢查是否已存在相同的 <li>（比較文本和屬性）
+                    if !existing.children.iter().any(|c| same_li(c, child)) {
+                        existing.children.push(child.clone());
+                    }
+                } else {
+                    // 非 <li> 子節點遞歸合併
+                    let mut child_map: BTreeMap<Symbol, XmlNode> = existing
+                        .children
+                        .iter()
+                        .filter(|c| c.tag != "li")
+                        .map(|c| (c.tag.clone(), c.clone()))
+                        .collect();
+                    
+                    merge_node(&mut child_map, child);
+                    
+                    existing.children.retain(|c| c.tag == "li");
+                    existing.children.extend(child_map.into_values());
+                }
+            }
+        } else {
+            // 完全覆蓋（包括 text 和子節點）
+            *existing = node.clone();
+        }
+    } else {
+        // 新標籤，直接插入
+        merged.insert(key, node.clone());
+    }
+}
+
+pub fn parse_def_data(
+    path: &Path,
+    rules: &DefRootRules,
+) -> Result<Vec<DefData>, Box<dyn std::error::Error>> {
+    let _span = tracing::debug_span!("parse_def_data", file = %path.display()).entered();
+    tracing::debug!("開始解析");
+
+    let content = read_xml_file(path)?;
+    Ok(parse_def_data_from_str(&content, path, rules))
+}
+
+/// 從 XML 字串解析繼承資料；`path` 只用於記錄來源
+pub fn parse_def_data_from_str(content: &str, path: &Path, rules: &DefRootRules) -> Vec<DefData> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut results = Vec::new();
+    let mut buf = Vec::new();
+    let mut inside_defs = false;
+    let mut def_depth = 0;
+    let mut current_def_type: Option<Symbol> = None;
+    let mut current_def_name: Option<Symbol> = None;
+    let mut current_parent_name: Option<Symbol> = None;
+    let mut is_abstract = false;
+    let mut current_attributes: Vec<(Symbol, Symbol)> = Vec::new();
+    let mut node_stack: Vec<XmlNode> = Vec::new();
+    let mut root_nodes: Vec<XmlNode> = Vec::new();
+    let mut file_version: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                
+                if name == "Defs" {
+                    inside_defs = true;
+                } else if inside_defs && def_depth == 0 && !rules.is_def_root(&name) {
+                    // 不是 Def 的元素或使用者忽略的類型，略過整個子樹
+                    let end = e.to_end().into_owned();
+                    let _ = reader.read_to_end_into(end.name(), &mut Vec::new());
+                } else if inside_defs && def_depth == 0 {
+                    // 新的 Def 開始
+                    current_def_type = Some(Symbol::new(&name));
+                    def_depth = 1;
+                    current_def_name = None;
+                    current_parent_name = None;
+                    is_abstract = false;
+                    root_nodes.clear();
+                    node_stack.clear();
+                    current_attributes.clear();
+                    
+                    // 解析屬性
+                    for attr in e.attributes().filter_map(|a| a.ok()) {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let value = String::from_utf8_lossy(&attr.value).to_string();
+                        current_attributes.push((Symbol::new(&key), Symbol::new(&value)));
+                        
+                        if key == "Abstract" && value == "True" {
+                            is_abstract = true;
+                        } else if key == "ParentName" {
+                            current_parent_name = Some(Symbol::new(&value));
+                        } else if key == "Name" {
+                            current_def_name = Some(Symbol::new(&value));
+                        }
+                    }
+                } else if def_depth > 0 {
+                    // Def 內的子節點
+                    def_depth += 1;
+                    
+                    let mut attributes = Vec::new();
+                    for attr in e.attributes().filter_map(|a| a.ok()) {
+                        attributes.push((
+                            Symbol::new(&String::from_utf8_lossy(attr.key.as_ref())),
+                            Symbol::new(&String::from_utf8_lossy(&attr.value)),
+                        ));
+                    }
+                    
+                    let node = XmlNode {
+                        tag: Symbol::new(&name),
+                        attributes,
+                        children: Vec::new(),
+                        text: None,
+                    };
+                    
+                    node_stack.push(node);
+                }
+            }
+            Ok(Event::Empty(ref e)) if def_depth > 0 => {
+                // 空標籤 <tag />
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut attributes = Vec::new();
+                for attr in e.attributes().filter_map(|a| a.ok()) {
+                    attributes.push((
+                        Symbol::new(&String::from_utf8_lossy(attr.key.as_ref())),
+                        Symbol::new(&String::from_utf8_lossy(&attr.value)),
+                    ));
+                }
+                
+                let node = XmlNode {
+                    tag: Symbol::new(&name),
+                    attributes,
+                    children: Vec::new(),
+                    text: None,
+                };
+                
+                if let Some(parent) = node_stack.last_mut() {
+                    parent.children.push(node);
+                } else {
+                    root_nodes.push(node);
+                }
+            }
+            Ok(Event::Text(e)) if def_depth > 0 && !node_stack.is_empty() => {
+                if let Ok(text) = e.unescape() {
+                    let trimmed = text.trim();
+                    if !trimmed.is_empty() {
+                        let last = node_stack.last_mut().unwrap();
+                        
+                        // 特殊處理 defName
+                        if last.tag == "defName" && current_def_name.is_none() {
+                            current_def_name = Some(Symbol::new(trimmed));
+                        }
+                        
+                        last.text = Some(Symbol::new(trimmed));
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                
+                if def_depth == 1 {
+                    // Def 結束（子節點名稱也可能以 Def 結尾，例如 <thingDef>，因此以深度判斷）
+                    def_depth = 0;
+                    if let Some(def_name) = &current_def_name {
+                        results.push(DefData {
+                            def_name: def_name.clone(),
+                            parent_name: current_parent_name.clone(),
+                            file_path: path.to_path_buf(),
+                            xml_content: String::new(),
+                            is_abstract,
+                            def_type: current_def_type.clone().unwrap_or_default(),
+                            raw_nodes: root_nodes.clone(),
+                            depth: 0,
+                            file_version: None,
+                            attributes: current_attributes.clone(),
+                            is_temp: false,
+                        });
+                    }
+                } else if def_depth > 0 {
+                    def_depth -= 1;
+                    
+                    // 彈出完成的節點
+                    if let Some(completed_node) = node_stack.pop() {
+                        if let Some(parent) = node_stack.last_mut() {
+                            parent.children.push(completed_node);
+                        } else {
+                            root_nodes.push(completed_node);
+                        }
+                    }
+                }
+                
+                if name == "Defs" {
+                    inside_defs = false;
+                }
+            }
+            Ok(Event::Comment(e)) if file_version.is_none() => {
+                // 版本註解屬於整個檔案，取第一個找到的
+                file_version = find_version_comment(&String::from_utf8_lossy(&e));
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!(position = reader.buffer_position(), error = %e, "XML 解析錯誤");
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    for def in &mut results {
+        def.file_version = file_version.clone();
+    }
+    results
+}
+
+fn generate_expanded_xml(
+    def_name: &str,
+    def_type: &str,
+    nodes: &BTreeMap<Symbol, XmlNode>,
+) -> String {
+    let mut xml = String::new();
+    
+    xml.push_str(&format!("<{}>\n", def_type));
+    xml.push_str(&format!("  <defName>{}</defName>\n", def_name));
+    
+    // 生成所有其他節點
+    for node in nodes.values() {
+        if node.tag != "defName" {
+            generate_node_xml(&mut xml, node, 1);
+        }
+    }
+    
+    xml.push_str(&format!("</{}>\n", def_type));
+    xml
+}
+
+/// 將任意 XML 片段解析為節點樹（包含最外層元素），供樹狀檢視使用；語法錯誤之後的內容略過
+pub fn parse_xml_nodes(xml: &str) -> Vec<XmlNode> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<XmlNode> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => stack.push(XmlNode {
+                tag: Symbol::new(&String::from_utf8_lossy(e.name().as_ref())),
+                attributes: node_attributes(&e),
+                children: Vec::new(),
+                text: None,
+            }),
+            Ok(Event::Empty(e)) => {
+                let node = XmlNode {
+                    tag: Symbol::new(&String::from_utf8_lossy(e.name().as_ref())),
+                    attributes: node_attributes(&e),
+                    children: Vec::new(),
+                    text: None,
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let (Some(node), Ok(text)) = (stack.last_mut(), e.unescape()) {
+                    let trimmed = text.trim();
+                    if !trimmed.is_empty() {
+                        node.text = Some(Symbol::new(trimmed));
+                    }
+                }
+            }
+            Ok(Event::End(_)) => {
+                let Some(node) = stack.pop() else {
+                    continue;
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    roots
+}
+
+/// 將節點樹攤平為 (標籤路徑, 值)，依文件順序，供表格檢視使用
+///
+/// 路徑從最外層元素（Def 本身）之下開始，例如 `statBases/MarketValue`；`<li>` 依順序加上索引
+/// （`comps/li[0]/compClass`），屬性為 `路徑/@名稱`；沒有文本也沒有屬性的元素不列出
+pub fn flatten_nodes(nodes: &[XmlNode]) -> Vec<(String, String)> {
+    let mut rows = Vec::new();
+    for root in nodes {
+        flatten_node(root, "", &mut rows);
+    }
+    rows
+}
+
+fn flatten_node(node: &XmlNode, path: &str, rows: &mut Vec<(String, String)>) {
+    let join = |name: &str| match path {
+        "" => name.to_string(),
+        _ => format!("{}/{}", path, name),
+    };
+    for (key, value) in &node.attributes {
+        rows.push((join(&format!("@{}", key)), value.to_string()));
+    }
+    if let (Some(text), false) = (&node.text, path.is_empty()) {
+        rows.push((path.to_string(), text.to_string()));
+    }
+    let mut li_index = 0;
+    for child in &node.children {
+        let name = if child.tag == "li" {
+            li_index += 1;
+            format!("li[{}]", li_index - 1)
+        } else {
+            child.tag.to_string()
+        };
+        flatten_node(child, &join(&name), rows);
+    }
+}
+
+fn node_attributes(element: &quick_xml::events::BytesStart) -> Vec<(Symbol, Symbol)> {
+    element
+        .attributes()
+        .filter_map(|a| a.ok())
+        .map(|attr| {
+            (
+                Symbol::new(&String::from_utf8_lossy(attr.key.as_ref())),
+                Symbol::new(&String::from_utf8_lossy(&attr.value)),
+            )
+        })
+        .collect()
+}
+
+/// 單一節點的 XML（不縮排）
+pub fn node_to_xml(node: &XmlNode) -> String {
+    let mut xml = String::new();
+    generate_node_xml(&mut xml, node, 0);
+    xml.trim_end().to_string()
+}
+
+fn generate_node_xml(xml: &mut String, node: &XmlNode, indent_level: usize) {
+    let indent = "  ".repeat(indent_level);
+    
+    // 檢查是否是簡單節點（只有文本，無子節點）
+    let is_simple = node.children.is_empty() && node.text.is_some();
+    let is_empty = node.children.is_empty() && node.text.is_none();
+    
+    if is_simple {
+        // 簡單節點：單行輸出
+        let text = node.text.as_ref().unwrap();
+        if node.attributes.is_empty() {
+            xml.push_str(&format!("{}<{}>{}</{}>\n", indent, node.tag, text, node.tag));
+        } else {
+            xml.push_str(&format!("{}<{}", indent, node.tag));
+            for (key, value) in &node.attributes {
+                xml.push_str(&format!(" {}=\"{}\"", key, value));
+            }
+            xml.push_str(&format!(">{}</{}>\n", text, node.tag));
+        }
+    } else if is_empty {
+        // 空節點：自閉合標籤
+        if node.attributes.is_empty() {
+            xml.push_str(&format!("{}<{} />\n", indent, node.tag));
+        } else {
+            xml.push_str(&format!("{}<{}", indent, node.tag));
+            for (key, value) in &node.attributes {
+                xml.push_str(&format!(" {}=\"{}\"", key, value));
+            }
+            xml.push_str(" />\n");
+        }
+    } else {
+        // 複雜節點：多行輸出
+        // 開標籤
+        if node.attributes.is_empty() {
+            xml.push_str(&format!("{}<{}>\n", indent, node.tag));
+        } else {
+            xml.push_str(&format!("{}<{}", indent, node.tag));
+            for (key, value) in &node.attributes {
+                xml.push_str(&format!(" {}=\"{}\"", key, value));
+            }
+            xml.push_str(">\n");
+        }
+        
+        // 文本內容（如果有的話，在有子節點的情況下較少見）
+        if let Some(text) = &node.text {
+            xml.push_str(&format!("{}  {}\n", indent, text));
+        }
+        
+        // 子節點
+        for child in &node.children {
+            if child.tag == "li" && child.children.is_empty() {
+                // <li> 標籤特殊處理：總是單行
+                if let Some(text) = &child.text {
+                    // 有文本內容
+                    if child.attributes.is_empty() {
+                        xml.push_str(&format!("{}  <li>{}</li>\n", indent, text));
+                    } else {
+                        xml.push_str(&format!("{}  <li", indent));
+                        for (key, value) in &child.attributes {
+                            xml.push_str(&format!(" {}=\"{}\"", key, value));
+                        }
+                        xml.push_str(&format!(">{}</li>\n", text));
+                    }
+                } else {
+                    // 空 <li> 標籤
+                    if child.attributes.is_empty() {
+                        xml.push_str(&format!("{}  <li />\n", indent));
+                    } else {
+                        xml.push_str(&format!("{}  <li", indent));
+                        for (key, value) in &child.attributes {
+                            xml.push_str(&format!(" {}=\"{}\"", key, value));
+                        }
+                        xml.push_str(" />\n");
+                    }
+                }
+            } else {
+                generate_node_xml(xml, child, indent_level + 1);
+            }
+        }
+        
+        // 閉標籤
+        xml.push_str(&format!("{}</{}>\n", indent, node.tag));
+    }
+}
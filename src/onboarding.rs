@@ -0,0 +1,101 @@
+use eframe::egui;
+
+use crate::settings::AppSettings;
+
+/// 歡迎面板完成時的選擇
+pub struct OnboardingResult {
+    pub base_path: Option<String>, // 選擇的工作目錄（稍後設定時為 None）
+    pub scan_now: bool,
+}
+
+/// 第一次啟動時的歡迎面板：選擇 Data 資料夾後儲存設置，之後不再顯示
+pub struct Onboarding {
+    base_path: String,
+    scan_now: bool,
+    detect_message: Option<String>, // 自動偵測失敗時的提示
+}
+
+impl Default for Onboarding {
+    fn default() -> Self {
+        Self {
+            base_path: String::new(),
+            scan_now: true,
+            detect_message: None,
+        }
+    }
+}
+
+impl Onboarding {
+    /// 顯示歡迎面板；使用者完成或略過時回傳結果
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<OnboardingResult> {
+        let mut result = None;
+
+        egui::Window::new("👋 歡迎使用 RimWorld XML Tools")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .fixed_size([460.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("這個工具可以瀏覽 RimWorld 的所有 Defs、展開 ParentName 繼承、查詢標籤值並檢查常見問題。");
+                ui.label("開始之前，請選擇要掃描的資料夾：遊戲的 Data 資料夾，或正在製作的模組目錄。");
+                ui.add_space(10.0);
+
+                ui.vertical_centered(|ui| {
+                    let label = egui::RichText::new("📂 選擇 RimWorld Data 資料夾").heading();
+                    let button = egui::Button::new(label).min_size(egui::vec2(320.0, 40.0));
+                    if ui.add(button).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.base_path = path.display().to_string();
+                            self.detect_message = None;
+                        }
+                    }
+
+                    if ui.button("🔎 自動偵測 Steam 安裝位置").clicked() {
+                        match AppSettings::detect_rimworld_data() {
+                            Some(path) => {
+                                self.base_path = path.display().to_string();
+                                self.detect_message = None;
+                            }
+                            None => {
+                                self.detect_message =
+                                    Some("找不到 Steam 版 RimWorld，請手動選擇資料夾".to_string());
+                            }
+                        }
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                if let Some(message) = &self.detect_message {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), message);
+                }
+                if !self.base_path.is_empty() {
+                    ui.label(format!("✅ {}", self.base_path));
+                }
+
+                ui.checkbox(&mut self.scan_now, "完成後立即掃描");
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!self.base_path.is_empty(), egui::Button::new("✅ 開始使用"))
+                        .clicked()
+                    {
+                        result = Some(OnboardingResult {
+                            base_path: Some(self.base_path.clone()),
+                            scan_now: self.scan_now,
+                        });
+                    }
+                    if ui.button("稍後在設置中設定").clicked() {
+                        result = Some(OnboardingResult {
+                            base_path: None,
+                            scan_now: false,
+                        });
+                    }
+                });
+            });
+
+        result
+    }
+}
@@ -0,0 +1,82 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::browser::open_file_at_line;
+use crate::database::SharedDatabase;
+use crate::settings::AppSettings;
+use crate::xml_parser::ParseErrorKind;
+
+/// 解析錯誤報告：列出上次掃描時無法（完整）解析的檔案，可直接開啟錯誤所在的行
+pub struct ParseErrorWindow {
+    open: bool,
+    database: SharedDatabase,
+    settings: Arc<Mutex<AppSettings>>,
+}
+
+impl ParseErrorWindow {
+    pub fn new(database: SharedDatabase, settings: Arc<Mutex<AppSettings>>) -> Self {
+        Self {
+            open: false,
+            database,
+            settings,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("⚠ 解析錯誤")
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                let Ok(db) = self.database.read() else {
+                    return;
+                };
+                if db.errors.is_empty() {
+                    ui.weak("上次掃描沒有解析錯誤");
+                    return;
+                }
+                ui.label(format!("{} 個檔案無法完整解析；語法錯誤之前的 Defs 仍會被讀取", db.errors.len()));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("parse_errors")
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("parse_errors_grid").striped(true).show(ui, |ui| {
+                            for error in &db.errors.errors {
+                                let file = error.file.strip_prefix(&db.base_path).unwrap_or(&error.file);
+                                ui.label(file.display().to_string())
+                                    .on_hover_text(error.file.display().to_string());
+                                match error.line {
+                                    Some(line) => ui.label(format!("第 {} 行", line)),
+                                    None => ui.weak("—"),
+                                };
+                                ui.label(kind_label(&error.kind)).on_hover_text(&error.message);
+                                if ui.button("📂 開啟").clicked() {
+                                    open_file_at_line(&self.settings, &error.file, error.line.unwrap_or(1) as u64);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+        self.open = open;
+    }
+}
+
+fn kind_label(kind: &ParseErrorKind) -> &'static str {
+    match kind {
+        ParseErrorKind::UnsupportedEncoding => "不支援的編碼",
+        ParseErrorKind::InvalidUtf8 => "無效的 UTF-8",
+        ParseErrorKind::Syntax => "XML 語法錯誤",
+        ParseErrorKind::Panic => "解析時發生錯誤",
+    }
+}
@@ -0,0 +1,155 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::defs::format_xml;
+use crate::intern::Symbol;
+use crate::xml_parser::read_xml_file;
+
+/// Patches 資料夾中的一個 PatchOperation；`PatchOperationSequence`、`PatchOperationFindMod`
+/// 等容器內的操作也各自是一個條目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchEntry {
+    pub operation: Symbol,      // Class 屬性，例如 PatchOperationAdd
+    pub xpath: Option<String>,  // 操作本身的 <xpath>（容器沒有）
+    pub mods: Vec<String>,      // PatchOperationFindMod 的 <mods>
+    pub parent: Option<String>, // 所在的容器，例如「PatchOperationFindMod › match」
+    pub file_path: PathBuf,
+    pub xml_content: String,
+    pub start_line: u64, // 操作開始標籤所在行（從 1 起算）
+}
+
+impl PatchEntry {
+    /// 瀏覽器列表中顯示的名稱：xpath，沒有時為 FindMod 的模組或「（無 xpath）」
+    pub fn display_name(&self) -> String {
+        match &self.xpath {
+            Some(xpath) => xpath.clone(),
+            None if !self.mods.is_empty() => format!("mods: {}", self.mods.join(", ")),
+            None => "（無 xpath）".to_string(),
+        }
+    }
+}
+
+/// 檔案是否位於 Patches 資料夾中
+pub fn is_patch_file(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str().eq_ignore_ascii_case("Patches"))
+}
+
+pub fn parse_patches_from_file(path: &Path) -> Result<Vec<PatchEntry>, Box<dyn std::error::Error>> {
+    let content = read_xml_file(path)?;
+    Ok(parse_patches_from_str(&content, path))
+}
+
+/// 解析 Patch XML 中的所有操作，依出現順序（容器在其內部的操作之前）
+///
+/// `<Operation>` 與帶有 `Class="…PatchOperation…"` 的元素（`<li>`、`<match>`、`<nomatch>` 等）都視為操作
+pub fn parse_patches_from_str(content: &str, path: &Path) -> Vec<PatchEntry> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut patches: Vec<PatchEntry> = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    loop {
+        let before = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let start = element_start(content, before);
+                let operation = operation_class(&tag, &e).map(|class| {
+                    patches.push(new_entry(class, parent_note(&stack, &patches, &tag), path, content, start));
+                    patches.len() - 1
+                });
+                stack.push(Frame { tag, operation, start });
+            }
+            Ok(Event::Empty(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if let Some(class) = operation_class(&tag, &e) {
+                    let start = element_start(content, before);
+                    let mut entry = new_entry(class, parent_note(&stack, &patches, &tag), path, content, start);
+                    entry.xml_content = content[start..reader.buffer_position() as usize].to_string();
+                    patches.push(entry);
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let Ok(text) = e.unescape() else {
+                    continue;
+                };
+                let text = text.trim().to_string();
+                // <xpath> 與 <mods><li> 屬於最近的操作
+                match stack.as_slice() {
+                    [.., owner, last] if last.tag == "xpath" => {
+                        if let Some(index) = owner.operation {
+                            patches[index].xpath = Some(text);
+                        }
+                    }
+                    [.., owner, mods, li] if mods.tag == "mods" && li.tag == "li" => {
+                        if let Some(index) = owner.operation {
+                            patches[index].mods.push(text);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => {
+                let Some(frame) = stack.pop() else {
+                    continue;
+                };
+                if let Some(index) = frame.operation {
+                    let end = reader.buffer_position() as usize;
+                    patches[index].xml_content = format_xml(&content[frame.start..end]);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!(file = %path.display(), position = reader.buffer_position(), error = %e, "Patch XML 解析錯誤");
+                break;
+            }
+            _ => {}
+        }
+    }
+    // 讀到語法錯誤時，未結束的操作沒有內容
+    patches.retain(|p| !p.xml_content.is_empty());
+    patches
+}
+
+/// 開啟中的元素
+struct Frame {
+    tag: String,
+    operation: Option<usize>, // 此元素為操作時，在結果中的索引
+    start: usize,             // 開始標籤的位元組位置
+}
+
+fn operation_class(tag: &str, element: &BytesStart) -> Option<String> {
+    let class = element
+        .attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.as_ref() == b"Class")
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())?;
+    (tag == "Operation" || class.contains("PatchOperation")).then_some(class)
+}
+
+/// 最近的容器操作與此元素所在的位置，例如「PatchOperationSequence › operations」
+fn parent_note(stack: &[Frame], patches: &[PatchEntry], tag: &str) -> Option<String> {
+    let (position, owner) = stack.iter().enumerate().rev().find(|(_, f)| f.operation.is_some())?;
+    let container = &patches[owner.operation?].operation;
+    let role = stack.get(position + 1).map_or(tag, |child| child.tag.as_str());
+    Some(format!("{} › {}", container, role))
+}
+
+fn new_entry(class: String, parent: Option<String>, path: &Path, content: &str, start: usize) -> PatchEntry {
+    PatchEntry {
+        operation: Symbol::new(&class),
+        xpath: None,
+        mods: Vec::new(),
+        parent,
+        file_path: path.to_path_buf(),
+        xml_content: String::new(),
+        start_line: content[..start].bytes().filter(|&b| b == b'\n').count() as u64 + 1,
+    }
+}
+
+/// 讀取事件前的位置可能包含開始標籤前的空白，略過到 `<`
+fn element_start(content: &str, before: usize) -> usize {
+    content[before..].find('<').map_or(before, |offset| before + offset)
+}
@@ -0,0 +1,104 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::widgets::LineNumberedCode;
+
+/// 彈出的 XML 視窗；保存開啟時的內容，不受之後的選擇或重新掃描影響
+struct PopoutWindow {
+    id: egui::ViewportId,
+    title: String,
+    content: String,
+    word_wrap: bool,
+    open: bool,
+}
+
+#[derive(Default)]
+struct PopoutList {
+    windows: Vec<Arc<Mutex<PopoutWindow>>>,
+    next_id: u64,
+}
+
+/// 所有彈出視窗，分頁透過它開啟新視窗，主程式每幀負責顯示
+#[derive(Clone, Default)]
+pub struct Popouts(Arc<Mutex<PopoutList>>);
+
+impl Popouts {
+    /// 以獨立的作業系統視窗開啟唯讀內容
+    pub fn open(&self, title: &str, content: &str) {
+        let Ok(mut list) = self.0.lock() else {
+            return;
+        };
+        let id = egui::ViewportId::from_hash_of(("popout", list.next_id));
+        list.next_id += 1;
+        list.windows.push(Arc::new(Mutex::new(PopoutWindow {
+            id,
+            title: title.to_string(),
+            content: content.to_string(),
+            word_wrap: false,
+            open: true,
+        })));
+    }
+
+    /// 每幀呼叫：顯示仍開啟的視窗，移除已關閉的
+    pub fn show(&self, ctx: &egui::Context) {
+        let Ok(mut list) = self.0.lock() else {
+            return;
+        };
+        list.windows.retain(|w| w.lock().is_ok_and(|w| w.open));
+
+        for window in &list.windows {
+            let Ok((id, title)) = window.lock().map(|w| (w.id, w.title.clone())) else {
+                continue;
+            };
+            let window = window.clone();
+            ctx.show_viewport_deferred(
+                id,
+                egui::ViewportBuilder::default()
+                    .with_title(&title)
+                    .with_inner_size([600.0, 700.0]),
+                move |ctx, class| {
+                    let Ok(mut window) = window.lock() else {
+                        return;
+                    };
+                    if class == egui::ViewportClass::Embedded {
+                        // 不支援多視窗時退回為主視窗內的浮動視窗
+                        let mut open = window.open;
+                        egui::Window::new(title.as_str())
+                            .id(egui::Id::new(id))
+                            .open(&mut open)
+                            .default_size([500.0, 500.0])
+                            .show(ctx, |ui| popout_ui(ui, &mut window));
+                        window.open = open;
+                    } else {
+                        egui::CentralPanel::default().show(ctx, |ui| popout_ui(ui, &mut window));
+                        if ctx.input(|i| i.viewport().close_requested()) {
+                            window.open = false;
+                            ctx.request_repaint_of(egui::ViewportId::ROOT);
+                        }
+                    }
+                },
+            );
+        }
+    }
+}
+
+fn popout_ui(ui: &mut egui::Ui, window: &mut PopoutWindow) {
+    ui.horizontal(|ui| {
+        ui.strong(&window.title);
+        if ui.button("📋 複製").clicked() {
+            ui.output_mut(|o| o.copied_text = window.content.clone());
+        }
+        ui.checkbox(&mut window.word_wrap, "自動換行");
+    });
+
+    ui.separator();
+
+    egui::ScrollArea::both()
+        .id_salt("popout_content")
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            LineNumberedCode::new(&window.content)
+                .word_wrap(window.word_wrap)
+                .show(ui);
+        });
+}
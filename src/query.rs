@@ -0,0 +1,115 @@
+use crate::database::SharedDefDatabase;
+use crate::defs::DefEntry;
+use crate::merge::{DefData, XmlNode};
+
+/// 單一篩選條件
+enum Filter {
+    Tag { tag: String, value: String },
+    Abstract(bool),
+    Parent(String),
+}
+
+impl Filter {
+    fn matches(&self, entry: &DefEntry, data: Option<&DefData>) -> bool {
+        match self {
+            Filter::Abstract(is_abstract) => entry.is_abstract == *is_abstract,
+            Filter::Parent(parent) => data.is_some_and(|d| d.parent_name.as_deref() == Some(parent.as_str())),
+            Filter::Tag { tag, value } => data.is_some_and(|d| has_tag_value(&d.raw_nodes, tag, value)),
+        }
+    }
+}
+
+/// 以鏈式呼叫組合 Def 篩選條件，供腳本、命令列等共用
+///
+/// 結果為瀏覽器條目；只有 `Name` 沒有 `defName` 的抽象基底以其 `Name` 出現
+///
+/// ```ignore
+/// let items = DefQuery::new(&db)
+///     .of_type("ThingDef")
+///     .with_tag("category", "Item")
+///     .abstract_only(false)
+///     .has_parent("BaseItem")
+///     .execute();
+/// ```
+pub struct DefQuery<'a> {
+    db: &'a SharedDefDatabase,
+    def_type: Option<String>,
+    filters: Vec<Filter>,
+}
+
+impl<'a> DefQuery<'a> {
+    pub fn new(db: &'a SharedDefDatabase) -> Self {
+        Self {
+            db,
+            def_type: None,
+            filters: Vec::new(),
+        }
+    }
+
+    /// 只包含此類型的 Def（例如 `ThingDef`）
+    pub fn of_type(mut self, def_type: &str) -> Self {
+        self.def_type = Some(def_type.to_string());
+        self
+    }
+
+    /// Def 本身（不含繼承）有此標籤且值相符；列表標籤中任一 `<li>` 相符也算
+    pub fn with_tag(mut self, tag: &str, value: &str) -> Self {
+        self.filters.push(Filter::Tag {
+            tag: tag.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// `true` 只包含抽象定義，`false` 只包含具體的 Def
+    pub fn abstract_only(mut self, is_abstract: bool) -> Self {
+        self.filters.push(Filter::Abstract(is_abstract));
+        self
+    }
+
+    /// 直接的 ParentName 為此名稱
+    pub fn has_parent(mut self, parent: &str) -> Self {
+        self.filters.push(Filter::Parent(parent.to_string()));
+        self
+    }
+
+    /// 依加入的順序逐一套用條件，結果為空時不再繼續；依類型、defName 排序
+    pub fn execute(&self) -> Vec<&'a DefEntry> {
+        let db = self.db;
+        let mut results: Vec<&DefEntry> = match &self.def_type {
+            Some(def_type) => db
+                .entries
+                .get(def_type.as_str())
+                .map(|entries| entries.iter().collect())
+                .unwrap_or_default(),
+            None => db.entries.values().flatten().collect(),
+        };
+
+        for filter in &self.filters {
+            if results.is_empty() {
+                break;
+            }
+            results.retain(|entry| {
+                // 同名的 Def 以來源檔案區分
+                let data = db
+                    .data
+                    .get(&entry.def_name)
+                    .filter(|d| d.file_path == entry.file_path);
+                filter.matches(entry, data)
+            });
+        }
+        results
+    }
+}
+
+fn has_tag_value(nodes: &[XmlNode], tag: &str, value: &str) -> bool {
+    nodes.iter().any(|node| {
+        let matches = node.tag == tag
+            && (node.text.as_deref().map(str::trim) == Some(value)
+                || node
+                    .children
+                    .iter()
+                    .any(|li| li.tag == "li" && li.text.as_deref().map(str::trim) == Some(value)));
+        matches || has_tag_value(&node.children, tag, value)
+    })
+}
@@ -0,0 +1,259 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::defs::{parse_defs_from_str, DefEntry};
+use crate::database::SharedDatabase;
+use crate::intern::Symbol;
+use crate::merge::{parse_def_data_from_str, DefData};
+use crate::settings::AppSettings;
+use crate::xml_parser::{find_syntax_error, read_xml_file, XmlSyntaxError};
+
+/// 快速分析：貼上或開啟單一 XML，不需掃描目錄，也不會寫入資料庫或設置
+pub struct QuickAnalysis {
+    open: bool,
+    input: String,
+    source: Option<PathBuf>, // 開啟的檔案（貼上的內容為 None）
+    load_error: Option<String>,
+    syntax_error: Option<XmlSyntaxError>,
+    entries: Vec<DefEntry>,
+    defs: Vec<DefData>,
+    selected: Option<Symbol>, // 選取的 defName（或抽象定義的 Name）
+    database: SharedDatabase,
+    settings: Arc<Mutex<AppSettings>>,
+}
+
+impl QuickAnalysis {
+    pub fn new(database: SharedDatabase, settings: Arc<Mutex<AppSettings>>) -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            source: None,
+            load_error: None,
+            syntax_error: None,
+            entries: Vec::new(),
+            defs: Vec::new(),
+            selected: None,
+            database,
+            settings,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// 開啟單一檔案（選擇檔案或拖放到視窗上）
+    pub fn open_file(&mut self, path: &Path) {
+        self.open = true;
+        match read_xml_file(path) {
+            Ok(content) => {
+                self.input = content;
+                self.source = Some(path.to_path_buf());
+                self.load_error = None;
+                self.analyze();
+            }
+            Err(e) => {
+                self.clear_results();
+                self.load_error = Some(format!("無法讀取 {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    fn clear_results(&mut self) {
+        self.syntax_error = None;
+        self.entries.clear();
+        self.defs.clear();
+        self.selected = None;
+    }
+
+    fn analyze(&mut self) {
+        self.clear_results();
+
+        // 貼上的片段常常少了 <Defs> 根節點，補上後再解析
+        let content = if self.input.contains("<Defs") {
+            self.input.clone()
+        } else {
+            format!("<Defs>\n{}\n</Defs>", self.input)
+        };
+
+        self.syntax_error = find_syntax_error(&self.input);
+        let path = self
+            .source
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("（貼上的內容）"));
+        let rules = self.settings.lock().map(|s| s.def_root_rules()).unwrap_or_default();
+        self.entries = parse_defs_from_str(&content, &path, None, &rules).entries;
+        self.defs = parse_def_data_from_str(&content, &path, &rules);
+        self.selected = self.defs.first().map(|d| d.def_name.clone());
+    }
+
+    /// 顯示視窗；使用者要求在展開繼承分頁中展開時回傳該 Def
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<DefData> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = self.open;
+        let mut expand = None;
+        egui::Window::new("🧪 快速分析")
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                expand = self.window_ui(ui);
+            });
+        self.open = open;
+        expand
+    }
+
+    fn window_ui(&mut self, ui: &mut egui::Ui) -> Option<DefData> {
+        ui.horizontal(|ui| {
+            if ui.button("📂 開啟檔案").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("XML", &["xml"])
+                    .pick_file()
+                {
+                    self.open_file(&path);
+                }
+            }
+            if ui.button("🔍 分析").clicked() {
+                self.load_error = None;
+                self.analyze();
+            }
+            if ui.button("🗑 清除").clicked() {
+                self.input.clear();
+                self.source = None;
+                self.load_error = None;
+                self.clear_results();
+            }
+            match &self.source {
+                Some(path) => ui.weak(path.display().to_string()),
+                None => ui.weak("貼上 XML 片段後按「分析」，或將 .xml 檔案拖放到視窗上"),
+            };
+        });
+
+        egui::ScrollArea::vertical()
+            .id_salt("quick_analysis_input")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::multiline(&mut self.input)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(6),
+                );
+                // 手動編輯後，內容不再對應開啟的檔案
+                if response.changed() {
+                    self.source = None;
+                }
+            });
+
+        if let Some(error) = &self.load_error {
+            ui.colored_label(egui::Color32::from_rgb(255, 80, 80), error);
+        }
+        if let Some(error) = &self.syntax_error {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 80, 80),
+                format!("❌ 第 {} 行，第 {} 欄: {}", error.line, error.column, error.message),
+            );
+        }
+
+        ui.separator();
+
+        if self.defs.is_empty() {
+            ui.label("沒有找到任何 Def");
+            return None;
+        }
+
+        let db = self.database.read().ok()?;
+        let scanned = db.generation > 0;
+        let mut expand = None;
+
+        ui.horizontal_top(|ui| {
+            // 左側: Def 列表
+            ui.allocate_ui_with_layout(
+                egui::vec2(200.0, ui.available_height()),
+                egui::Layout::top_down(egui::Align::Min),
+                |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("quick_analysis_defs")
+                        .show(ui, |ui| {
+                            for def in &self.defs {
+                                let is_selected = self.selected.as_ref() == Some(&def.def_name);
+                                let label = if def.is_abstract {
+                                    format!("{} (抽象)", def.def_name)
+                                } else {
+                                    def.def_name.to_string()
+                                };
+                                if ui
+                                    .selectable_label(is_selected, label)
+                                    .on_hover_text(def.def_type.as_str())
+                                    .clicked()
+                                {
+                                    self.selected = Some(def.def_name.clone());
+                                }
+                            }
+                        });
+                },
+            );
+
+            ui.separator();
+
+            // 右側: 詳細資訊
+            ui.vertical(|ui| {
+                let Some(def) = self
+                    .selected
+                    .as_ref()
+                    .and_then(|name| self.defs.iter().find(|d| &d.def_name == name))
+                else {
+                    return;
+                };
+
+                ui.label(format!("類型: {}", def.def_type));
+                match &def.parent_name {
+                    None => {
+                        ui.label("沒有 ParentName");
+                    }
+                    Some(parent) if db.data.contains_key(parent) => {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("✅ 父類 {} 已在目前的掃描資料中找到", parent));
+                            if ui.button("🔗 在展開繼承中展開").clicked() {
+                                expand = Some(def.clone());
+                            }
+                        });
+                    }
+                    Some(parent) if !scanned => {
+                        ui.weak(format!("父類 {}（尚未掃描目錄，無法解析）", parent));
+                    }
+                    Some(parent) => {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 200, 0),
+                            format!("⚠ 在目前的掃描資料中找不到父類 {}", parent),
+                        );
+                    }
+                }
+
+                ui.separator();
+
+                match self.entries.iter().find(|e| e.def_name == def.def_name) {
+                    Some(entry) => {
+                        egui::ScrollArea::vertical()
+                            .id_salt("quick_analysis_xml")
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut entry.xml_content.as_str())
+                                        .code_editor()
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+                    }
+                    None => {
+                        ui.weak("此定義沒有 defName，無法顯示格式化的 XML");
+                    }
+                }
+            });
+        });
+
+        expand
+    }
+}
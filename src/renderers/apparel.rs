@@ -0,0 +1,133 @@
+use eframe::egui;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// RimWorld 標準的服裝層，由內而外
+const LAYER_ORDER: [&str; 6] = ["OnSkin", "Middle", "Shell", "Belt", "Overhead", "EyeCover"];
+
+/// 依身體部位群組決定的穿戴位置，依序比對，第一個相符的為準
+const SLOTS: [(&str, &[&str]); 6] = [
+    ("Head", &["FullHead", "UpperHead", "Eyes", "Teeth", "Mouth"]),
+    ("Torso", &["Torso", "Shoulders", "Arms", "Neck"]),
+    ("Waist", &["Waist"]),
+    ("Legs", &["Legs"]),
+    ("Hands", &["Hands", "LeftHand", "RightHand"]),
+    ("Feet", &["Feet"]),
+];
+
+/// ThingDef 中 `<apparel>` 的摘要（只包含 Def 本身寫出的值，不含繼承）
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ApparelInfo {
+    pub layers: Vec<String>,
+    pub body_part_groups: Vec<String>,
+    pub tags: Vec<String>,
+    pub worn_graphic_path: Option<String>,
+}
+
+impl ApparelInfo {
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+            && self.body_part_groups.is_empty()
+            && self.tags.is_empty()
+            && self.worn_graphic_path.is_none()
+    }
+
+    /// 依覆蓋的身體部位群組推測的穿戴位置
+    pub fn slot(&self) -> Option<&'static str> {
+        SLOTS
+            .iter()
+            .find(|(_, groups)| self.body_part_groups.iter().any(|g| groups.contains(&g.as_str())))
+            .map(|(slot, _)| *slot)
+    }
+
+    /// 例如「部位: Torso | 層: Middle | 覆蓋: Torso, Shoulders | 標籤: Militaristic」，空的欄位省略
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(slot) = self.slot() {
+            parts.push(format!("部位: {}", slot));
+        }
+        for (label, values) in [
+            ("層", &self.layers),
+            ("覆蓋", &self.body_part_groups),
+            ("標籤", &self.tags),
+        ] {
+            if !values.is_empty() {
+                parts.push(format!("{}: {}", label, values.join(", ")));
+            }
+        }
+        parts.join(" | ")
+    }
+
+    /// 依標準服裝層順序：穿在哪些層之外、哪些層之內；有模組自訂的層或沒有標準層時為 None
+    pub fn worn_order(&self) -> Option<(Vec<&'static str>, Vec<&'static str>)> {
+        let indices: Vec<usize> = self
+            .layers
+            .iter()
+            .map(|layer| LAYER_ORDER.iter().position(|l| l == layer))
+            .collect::<Option<_>>()?;
+        let lowest = *indices.iter().min()?;
+        let highest = *indices.iter().max()?;
+        Some((LAYER_ORDER[..lowest].to_vec(), LAYER_ORDER[highest + 1..].to_vec()))
+    }
+}
+
+/// 解析單一 Def XML 中直接位於根元素下的 `<apparel>`
+pub fn parse_apparel_info(xml: &str) -> ApparelInfo {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut info = ApparelInfo::default();
+    let mut stack: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                stack.push(String::from_utf8_lossy(e.name().as_ref()).to_string());
+            }
+            Ok(Event::End(_)) => {
+                stack.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let Ok(text) = e.unescape() else {
+                    continue;
+                };
+                let text = text.trim().to_string();
+                let path: Vec<&str> = stack.iter().map(String::as_str).collect();
+                match path.as_slice() {
+                    [_, "apparel", "layers", "li"] => info.layers.push(text),
+                    [_, "apparel", "bodyPartGroups", "li"] => info.body_part_groups.push(text),
+                    [_, "apparel", "tags", "li"] => info.tags.push(text),
+                    [_, "apparel", "wornGraphicPath"] => info.worn_graphic_path = Some(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    info
+}
+
+/// 顯示服裝摘要與依服裝層推算的穿著順序
+pub fn apparel_ui(ui: &mut egui::Ui, info: &ApparelInfo) {
+    ui.label(info.summary());
+    match info.worn_order() {
+        Some((over, under)) => {
+            let list = |layers: &[&str]| {
+                if layers.is_empty() {
+                    "（無）".to_string()
+                } else {
+                    layers.join(", ")
+                }
+            };
+            ui.label(format!("穿在這些層之外: {}　之內: {}", list(&over), list(&under)))
+                .on_hover_text("依 RimWorld 標準服裝層順序 OnSkin → Middle → Shell → Belt → Overhead → EyeCover");
+        }
+        None if !info.layers.is_empty() => {
+            ui.weak("包含模組自訂的服裝層，無法判斷穿著順序");
+        }
+        None => {}
+    }
+    if let Some(path) = &info.worn_graphic_path {
+        ui.label(format!("穿戴貼圖: {}", path));
+    }
+}
@@ -0,0 +1,139 @@
+use eframe::egui;
+
+use crate::merge::XmlNode;
+
+/// 圖表的預設大小
+pub const CHART_SIZE: egui::Vec2 = egui::vec2(260.0, 140.0);
+
+/// 解析 SimpleCurve 的點，依 x 排序
+///
+/// 支援 `<li><x>0</x><y>1</y></li>` 與遊戲檔案常用的 `<li>(0, 1)</li>`，
+/// 點可以直接放在節點下，或放在 `<points>` 中
+pub fn parse_simple_curve(node: &XmlNode) -> Vec<[f64; 2]> {
+    let items = node
+        .children
+        .iter()
+        .find(|c| c.tag == "points")
+        .map_or(&node.children, |points| &points.children);
+
+    let mut points: Vec<[f64; 2]> = items
+        .iter()
+        .filter(|li| li.tag == "li")
+        .filter_map(parse_point)
+        .collect();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    points
+}
+
+fn parse_point(li: &XmlNode) -> Option<[f64; 2]> {
+    let child = |tag: &str| {
+        li.children
+            .iter()
+            .find(|c| c.tag == tag)
+            .and_then(|c| c.text.as_deref())
+            .and_then(|text| text.trim().parse::<f64>().ok())
+    };
+    if let (Some(x), Some(y)) = (child("x"), child("y")) {
+        return Some([x, y]);
+    }
+
+    let text = li.text.as_deref()?.trim();
+    let (x, y) = text.trim_start_matches('(').trim_end_matches(')').split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
+
+/// 找出節點樹中名稱含有 Curve 的節點與其路徑（例如 `comps/li/severityCurve`），
+/// 只保留解析得到點的節點，不再往其子節點尋找
+pub fn find_curves(nodes: &[XmlNode]) -> Vec<(String, Vec<[f64; 2]>)> {
+    let mut curves = Vec::new();
+    collect_curves(nodes, "", &mut curves);
+    curves
+}
+
+fn collect_curves(nodes: &[XmlNode], prefix: &str, curves: &mut Vec<(String, Vec<[f64; 2]>)>) {
+    for node in nodes {
+        let path = if prefix.is_empty() {
+            node.tag.to_string()
+        } else {
+            format!("{}/{}", prefix, node.tag)
+        };
+        if node.tag.to_lowercase().contains("curve") {
+            let points = parse_simple_curve(node);
+            if !points.is_empty() {
+                curves.push((path, points));
+                continue;
+            }
+        }
+        collect_curves(&node.children, &path, curves);
+    }
+}
+
+/// 繪製折線圖並標示資料點，座標軸依資料範圍自動縮放；滑鼠停留時顯示所有點
+pub fn curve_chart(ui: &mut egui::Ui, points: &[[f64; 2]], size: egui::Vec2) {
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+
+    let visuals = ui.visuals();
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let font = egui::FontId::monospace(10.0);
+    let text_color = visuals.weak_text_color();
+    let plot = rect.shrink2(egui::vec2(36.0, 14.0));
+
+    let (min_x, max_x) = range(points.iter().map(|p| p[0]));
+    let (min_y, max_y) = range(points.iter().map(|p| p[1]));
+    let to_screen = |p: &[f64; 2]| {
+        egui::pos2(
+            plot.left() + ((p[0] - min_x) / (max_x - min_x)) as f32 * plot.width(),
+            plot.bottom() - ((p[1] - min_y) / (max_y - min_y)) as f32 * plot.height(),
+        )
+    };
+
+    // 座標軸與範圍標示
+    let axis = egui::Stroke::new(1.0, text_color);
+    painter.line_segment([plot.left_bottom(), plot.right_bottom()], axis);
+    painter.line_segment([plot.left_bottom(), plot.left_top()], axis);
+    for (pos, align, value) in [
+        (plot.left_top(), egui::Align2::RIGHT_CENTER, max_y),
+        (plot.left_bottom(), egui::Align2::RIGHT_CENTER, min_y),
+        (plot.left_bottom(), egui::Align2::CENTER_TOP, min_x),
+        (plot.right_bottom(), egui::Align2::CENTER_TOP, max_x),
+    ] {
+        painter.text(pos, align, format_value(value), font.clone(), text_color);
+    }
+
+    let screen: Vec<egui::Pos2> = points.iter().map(to_screen).collect();
+    let color = egui::Color32::from_rgb(100, 180, 255);
+    painter.add(egui::Shape::line(screen.clone(), egui::Stroke::new(1.5, color)));
+    for pos in screen {
+        painter.circle_filled(pos, 2.5, color);
+    }
+
+    response.on_hover_text(
+        points
+            .iter()
+            .map(|p| format!("({}, {})", format_value(p[0]), format_value(p[1])))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+}
+
+/// 資料範圍；只有一個值時前後各擴展 1，避免除以零
+fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if max > min {
+        (min, max)
+    } else {
+        (min - 1.0, max + 1.0)
+    }
+}
+
+fn format_value(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,131 @@
+use eframe::egui;
+use std::ops::Range;
+
+use crate::database::{find_def, SharedDefDatabase};
+use crate::intern::Symbol;
+use crate::widgets::{FindBar, LineNumberedCode};
+use crate::xml_parser::{text_value_at, text_value_ranges};
+
+/// 預覽中顯示的參照 Def XML 行數
+const PREVIEW_LINES: usize = 5;
+
+/// 滑鼠停在 defName 參照上時顯示的預覽
+pub struct DefReference {
+    name: String,
+    pos: egui::Pos2,
+}
+
+/// 顯示唯讀、含行號的 XML；文字節點的值是其他已知 Def 的名稱時，停留即顯示該 Def 的前幾行與「前往 Def」按鈕
+///
+/// 整個文字節點（或 `<li>` 內容）完全等於瀏覽器中某個 defName 時以連結樣式顯示，點擊直接前往；
+/// 描述等長文字中出現的名稱不算
+///
+/// `def_name` 為目前顯示的 Def（不顯示自己的預覽）；`find` 為內容上方的尋找列，標示其相符處；
+/// 回傳使用者要前往的 Def（類型, 條目索引）
+pub fn xml_with_references(
+    ui: &mut egui::Ui,
+    xml: &str,
+    def_name: &str,
+    db: &SharedDefDatabase,
+    popup: &mut Option<DefReference>,
+    find: Option<&mut FindBar>,
+) -> Option<(Symbol, usize)> {
+    let links: Vec<Range<usize>> = text_value_ranges(xml)
+        .into_iter()
+        .filter(|range| {
+            let value = &xml[range.clone()];
+            value != def_name && db.name_index.contains_key(value)
+        })
+        .collect();
+    let code = LineNumberedCode::new(xml).links(&links);
+    let output = match find {
+        Some(find) => find.apply(code).show(ui),
+        None => code.show(ui),
+    };
+
+    // 滑鼠下的文字節點是否為其他 Def 的名稱
+    let hovered = output.response.hover_pos().and_then(|pos| {
+        let cursor = output.galley.cursor_from_pos(pos - output.galley_pos);
+        let offset = xml.char_indices().nth(cursor.ccursor.index)?.0;
+        let value = text_value_at(xml, offset)?;
+        (value != def_name && db.data.contains_key(value)).then(|| value.to_string())
+    });
+    if let Some(target) = hovered.as_ref().and_then(|name| db.name_index.get(name.as_str())) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        if output.response.clicked() {
+            *popup = None;
+            return Some(target.clone());
+        }
+    }
+    if let Some(name) = &hovered {
+        if popup.as_ref().is_none_or(|p| p.name != *name) {
+            // 預覽貼近游標，移到預覽上時不會經過其他文字而關閉
+            let pos = ui.ctx().pointer_hover_pos().unwrap_or_default() + egui::vec2(4.0, 4.0);
+            *popup = Some(DefReference { name: name.clone(), pos });
+        }
+    }
+
+    let reference = popup.as_ref()?;
+    let target = db.name_index.get(reference.name.as_str()).cloned();
+    let mut go_to = false;
+    let area = egui::Area::new(egui::Id::new("def_reference_popup"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(reference.pos)
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(420.0);
+                ui.strong(&reference.name);
+                match find_def(db, &reference.name) {
+                    Some(entry) => {
+                        let preview: Vec<&str> = entry.xml_content.lines().take(PREVIEW_LINES).collect();
+                        ui.label(egui::RichText::new(preview.join("\n")).monospace());
+                        go_to = ui.button("前往 Def ↗").clicked();
+                    }
+                    None => {
+                        ui.weak("此 Def 沒有可顯示的條目（可能不在 Defs 資料夾中）");
+                    }
+                }
+            });
+        });
+
+    // 滑鼠離開參照文字與預覽時關閉
+    let pointer = ui.ctx().pointer_hover_pos();
+    let over_popup = pointer.is_some_and(|p| area.response.rect.expand(8.0).contains(p));
+    let over_reference = hovered.as_ref() == Some(&reference.name);
+    if go_to || (!over_popup && !over_reference) {
+        *popup = None;
+    }
+    if go_to {
+        target
+    } else {
+        None
+    }
+}
+
+/// 列出參照此 Def 的其他條目（依類型分組）；回傳點擊要前往的條目（類型, 條目索引）
+pub fn referenced_by_ui(ui: &mut egui::Ui, def_name: &str, db: &SharedDefDatabase) -> Option<(Symbol, usize)> {
+    let referencing = db.referenced_by.get(def_name)?;
+    let mut go_to = None;
+    egui::CollapsingHeader::new(format!("⬅ 被參照 ({})", referencing.len()))
+        .id_salt("def_referenced_by")
+        .show(ui, |ui| {
+            let mut last_type = None;
+            for (def_type, index) in referencing {
+                let Some(entry) = db.entries.get(def_type).and_then(|e| e.get(*index)) else {
+                    continue;
+                };
+                if last_type != Some(def_type) {
+                    last_type = Some(def_type);
+                    ui.strong(def_type.as_str());
+                }
+                if ui
+                    .selectable_label(false, format!("  {}", entry.def_name))
+                    .on_hover_text(entry.file_path.display().to_string())
+                    .clicked()
+                {
+                    go_to = Some((def_type.clone(), *index));
+                }
+            }
+        });
+    go_to
+}
@@ -0,0 +1,8 @@
+//! 詳細資訊中特定標籤的圖形化顯示
+
+pub mod apparel;
+pub mod curves;
+pub mod def_refs;
+pub mod scenario;
+pub mod xml_table;
+pub mod xml_tree;
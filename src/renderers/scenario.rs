@@ -0,0 +1,183 @@
+use eframe::egui;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+
+/// 劇本的一個部分（`<scenario><parts>` 中的 `<li Class="ScenPart_…">`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioPart {
+    pub class: String,
+    pub properties: HashMap<String, String>, // 直接子元素的文字值（巢狀的元素略過）
+}
+
+/// 摘要中的分組，依顯示順序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PartCategory {
+    Pawns,
+    StartingItems,
+    MapConditions,
+    Rules,
+    Other,
+}
+
+impl PartCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            PartCategory::Pawns => "殖民者",
+            PartCategory::StartingItems => "起始物品",
+            PartCategory::MapConditions => "地圖狀況與事件",
+            PartCategory::Rules => "規則與研究",
+            PartCategory::Other => "其他",
+        }
+    }
+}
+
+impl ScenarioPart {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// 依 Class 名稱分組
+    pub fn category(&self) -> PartCategory {
+        let class = self.class.as_str();
+        if ["StartingThing", "ScatterThings", "StartingAnimal", "StartingMech"]
+            .iter()
+            .any(|k| class.contains(k))
+        {
+            PartCategory::StartingItems
+        } else if ["Pawn", "Forced", "SetNeedLevel", "ArriveMethod"].iter().any(|k| class.contains(k)) {
+            PartCategory::Pawns
+        } else if class.contains("DisableIncident") || class.contains("Rule") || class.contains("Research") {
+            PartCategory::Rules
+        } else if class.contains("GameCondition") || class.contains("Incident") {
+            PartCategory::MapConditions
+        } else {
+            PartCategory::Other
+        }
+    }
+
+    /// 人類可讀的說明，例如「開局擁有: 75 × Steel」；未知的類型列出所有屬性
+    pub fn description(&self) -> String {
+        let count = self.get("count").unwrap_or("1");
+        let short = self.class.trim_start_matches("ScenPart_");
+        let things = |key: &str| {
+            let thing = self.get(key).unwrap_or("?");
+            match self.get("stuff") {
+                Some(stuff) => format!("{} × {}（{}）", count, thing, stuff),
+                None => format!("{} × {}", count, thing),
+            }
+        };
+        match short {
+            "StartingThing_Defined" => format!("開局擁有: {}", things("thingDef")),
+            "ScatterThingsNearPlayerStart" => format!("散落在起始位置附近: {}", things("thingDef")),
+            "ScatterThingsAnywhere" => format!("散落在地圖各處: {}", things("thingDef")),
+            "StartingAnimal" => format!("起始動物: {}", things("animalKind")),
+            "ConfigPage_ConfigureStartingPawns" => match self.get("pawnChoiceCount") {
+                Some(choices) => format!("起始殖民者: {} 人（從 {} 人中選擇）", self.get("pawnCount").unwrap_or("?"), choices),
+                None => format!("起始殖民者: {} 人", self.get("pawnCount").unwrap_or("?")),
+            },
+            "PlayerPawnsArriveMethod" => format!("抵達方式: {}", self.get("method").unwrap_or("?")),
+            "ForcedHediff" => format!("強制健康狀態: {}", self.get("hediff").unwrap_or("?")),
+            "ForcedTrait" => format!("強制特質: {}", self.get("trait").unwrap_or("?")),
+            "PermaGameCondition" => format!("永久狀況: {}", self.get("gameCondition").unwrap_or("?")),
+            "CreateIncident" => format!("觸發事件: {}", self.get("incident").unwrap_or("?")),
+            "DisableIncident" => format!("停用事件: {}", self.get("incident").unwrap_or("?")),
+            "StartingResearch" => format!("起始研究: {}", self.get("project").unwrap_or("?")),
+            "GameStartDialog" => "開局對話".to_string(),
+            _ => {
+                let mut properties: Vec<String> = self
+                    .properties
+                    .iter()
+                    .filter(|(key, _)| key.as_str() != "def")
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect();
+                properties.sort();
+                if properties.is_empty() {
+                    short.to_string()
+                } else {
+                    format!("{}（{}）", short, properties.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// 解析 ScenarioDef XML 中 `<scenario><parts>` 的所有部分，依出現順序
+pub fn parse_scenario_parts(xml: &str) -> Vec<ScenarioPart> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut parts = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut current: Option<ScenarioPart> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if tag == "li" && in_parts(&stack) {
+                    current = Some(ScenarioPart {
+                        class: class_attribute(&e),
+                        properties: HashMap::new(),
+                    });
+                }
+                stack.push(tag);
+            }
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"li" && in_parts(&stack) => {
+                parts.push(ScenarioPart {
+                    class: class_attribute(&e),
+                    properties: HashMap::new(),
+                });
+            }
+            Ok(Event::End(_)) => {
+                let closed = stack.pop();
+                if closed.as_deref() == Some("li") && in_parts(&stack) {
+                    parts.extend(current.take());
+                }
+            }
+            Ok(Event::Text(e)) => {
+                // 只取部分的直接子元素：堆疊為 …/parts/li/<key>
+                let Some(part) = current.as_mut() else {
+                    continue;
+                };
+                let len = stack.len();
+                if len >= 3 && stack[len - 2] == "li" && stack[len - 3] == "parts" {
+                    if let Ok(text) = e.unescape() {
+                        part.properties.insert(stack[len - 1].clone(), text.trim().to_string());
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    parts
+}
+
+/// 堆疊目前位於 `<scenario><parts>` 中
+fn in_parts(stack: &[String]) -> bool {
+    matches!(stack, [.., scenario, parts] if scenario == "scenario" && parts == "parts")
+}
+
+fn class_attribute(element: &BytesStart) -> String {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == b"Class")
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+        .unwrap_or_default()
+}
+
+/// 依分組顯示劇本摘要
+pub fn scenario_ui(ui: &mut egui::Ui, parts: &[ScenarioPart]) {
+    let mut grouped: Vec<(PartCategory, &ScenarioPart)> = parts.iter().map(|p| (p.category(), p)).collect();
+    grouped.sort_by_key(|(category, _)| *category);
+
+    let mut last = None;
+    for (category, part) in grouped {
+        if last != Some(category) {
+            last = Some(category);
+            ui.strong(category.label());
+        }
+        ui.label(format!("  • {}", part.description())).on_hover_text(&part.class);
+    }
+}
@@ -0,0 +1,66 @@
+use eframe::egui;
+
+/// 表格檢視的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathSort {
+    #[default]
+    Document, // 依 XML 中出現的順序
+    Ascending,
+    Descending,
+}
+
+impl PathSort {
+    /// 點擊標題時依序切換：文件順序 → 遞增 → 遞減
+    fn next(self) -> Self {
+        match self {
+            PathSort::Document => PathSort::Ascending,
+            PathSort::Ascending => PathSort::Descending,
+            PathSort::Descending => PathSort::Document,
+        }
+    }
+
+    fn arrow(self) -> &'static str {
+        match self {
+            PathSort::Document => "",
+            PathSort::Ascending => " ⏶",
+            PathSort::Descending => " ⏷",
+        }
+    }
+}
+
+/// 以兩欄表格顯示攤平後的標籤路徑與值（見 `flatten_nodes`）；點擊「路徑」標題切換排序，
+/// 每列的 📋 複製為 `路徑 = 值`
+pub fn xml_table_ui(ui: &mut egui::Ui, rows: &[(String, String)], sort: &mut PathSort) {
+    if rows.is_empty() {
+        ui.weak("沒有含文本或屬性的元素");
+        return;
+    }
+    let mut order: Vec<&(String, String)> = rows.iter().collect();
+    match sort {
+        PathSort::Document => {}
+        PathSort::Ascending => order.sort_by(|a, b| a.0.cmp(&b.0)),
+        PathSort::Descending => order.sort_by(|a, b| b.0.cmp(&a.0)),
+    }
+
+    egui::Grid::new("def_xml_table").striped(true).num_columns(3).show(ui, |ui| {
+        if ui
+            .button(egui::RichText::new(format!("路徑{}", sort.arrow())).strong())
+            .on_hover_text("切換排序：文件順序 → 遞增 → 遞減")
+            .clicked()
+        {
+            *sort = sort.next();
+        }
+        ui.strong("值");
+        ui.label("");
+        ui.end_row();
+
+        for (path, value) in order {
+            ui.monospace(path);
+            ui.label(egui::RichText::new(value).monospace().strong());
+            if ui.small_button("📋").on_hover_text("複製為「路徑 = 值」").clicked() {
+                ui.output_mut(|o| o.copied_text = format!("{} = {}", path, value));
+            }
+            ui.end_row();
+        }
+    });
+}
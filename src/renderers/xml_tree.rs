@@ -0,0 +1,40 @@
+use eframe::egui;
+
+use crate::merge::XmlNode;
+
+/// 以可收合的樹狀顯示節點：屬性顯示在標籤後，只有文本的節點與值顯示在同一行
+///
+/// 收合狀態依 `id` 與節點位置保存在 egui 的記憶體中，以同一個 `id` 再次顯示時沿用；
+/// 預設只展開最外層（Def 本身）
+pub fn xml_tree_ui(ui: &mut egui::Ui, nodes: &[XmlNode], id: egui::Id) {
+    nodes_ui(ui, nodes, id, true);
+}
+
+fn nodes_ui(ui: &mut egui::Ui, nodes: &[XmlNode], id: egui::Id, open: bool) {
+    for (index, node) in nodes.iter().enumerate() {
+        let id = id.with(index);
+        if node.children.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.monospace(element_label(node));
+                if let Some(text) = &node.text {
+                    ui.label(egui::RichText::new(text.as_str()).monospace().strong());
+                }
+            });
+        } else {
+            egui::CollapsingHeader::new(egui::RichText::new(element_label(node)).monospace())
+                .id_salt(id)
+                .default_open(open)
+                .show(ui, |ui| nodes_ui(ui, &node.children, id, false));
+        }
+    }
+}
+
+/// 例如 `<li Class="CompProperties_Forbiddable">`
+fn element_label(node: &XmlNode) -> String {
+    let attributes: String = node
+        .attributes
+        .iter()
+        .map(|(key, value)| format!(" {}=\"{}\"", key, value))
+        .collect();
+    format!("<{}{}>", node.tag, attributes)
+}
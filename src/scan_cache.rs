@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::database::{ParsedFile, ScanMode};
+use crate::defs::DefRootRules;
+
+/// 快取檔案所在的目錄名稱（位於設置的資料目錄下），每個基礎路徑一個檔案
+pub const CACHE_DIR: &str = "scan_cache";
+
+/// 判斷檔案是否變更的依據：修改時間與大小都相同才沿用快取
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    modified_at: Option<SystemTime>,
+    size: u64,
+}
+
+impl FileStamp {
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified_at: metadata.modified().ok(),
+            size: metadata.len(),
+        })
+    }
+}
+
+/// 快取內容的格式版本，`ParsedFile` 的欄位改變時遞增
+const CACHE_FORMAT: u32 = 7;
+
+/// 快取的標頭：格式、程式版本或掃描設定不同時整個快取作廢
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CacheKey {
+    format: u32,
+    version: String,
+    base_path: PathBuf,
+    rules: DefRootRules,
+    mode: ScanMode,
+}
+
+#[derive(Deserialize)]
+struct CacheFile {
+    key: CacheKey,
+    files: Vec<CachedFile>,
+}
+
+#[derive(Deserialize)]
+struct CachedFile {
+    path: PathBuf,
+    stamp: FileStamp,
+    parsed: ParsedFile,
+}
+
+/// 寫入時借用本次的解析結果，不必複製
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    key: &'a CacheKey,
+    files: Vec<CachedFileRef<'a>>,
+}
+
+#[derive(Serialize)]
+struct CachedFileRef<'a> {
+    path: &'a Path,
+    stamp: FileStamp,
+    parsed: &'a ParsedFile,
+}
+
+/// 掃描結果的磁碟快取：未變更的檔案沿用上次的解析結果，不重新解析
+pub struct ScanCache {
+    path: PathBuf,
+    key: CacheKey,
+    files: HashMap<PathBuf, (FileStamp, ParsedFile)>,
+}
+
+impl ScanCache {
+    /// 讀取 `dir` 中此基礎路徑的快取；不存在、無法解析或掃描設定不同時為空的快取
+    pub fn load(dir: &Path, base_path: &Path, rules: &DefRootRules, mode: &ScanMode) -> Self {
+        let key = CacheKey {
+            format: CACHE_FORMAT,
+            version: crate::VERSION.to_string(),
+            base_path: base_path.to_path_buf(),
+            rules: rules.clone(),
+            mode: mode.clone(),
+        };
+        let path = dir.join(cache_file_name(base_path));
+        // 逐段讀取，不必先把整個快取檔案載入記憶體
+        let files = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CacheFile>(BufReader::new(file)).ok())
+            .filter(|cache| cache.key == key)
+            .map(|cache| {
+                cache
+                    .files
+                    .into_iter()
+                    .map(|file| (file.path, (file.stamp, file.parsed)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, key, files }
+    }
+
+    /// 取出未變更檔案的解析結果
+    pub(crate) fn take(&mut self, path: &Path, stamp: FileStamp) -> Option<ParsedFile> {
+        match self.files.remove(path) {
+            Some((cached, parsed)) if cached == stamp => Some(parsed),
+            _ => None,
+        }
+    }
+
+    /// 以本次掃描的結果覆寫快取，已刪除的檔案不會留下；有解析錯誤的檔案不快取，下次重新解析
+    pub(crate) fn save(&self, files: &[(PathBuf, Option<FileStamp>, ParsedFile)]) -> io::Result<()> {
+        let cache = CacheFileRef {
+            key: &self.key,
+            files: files
+                .iter()
+                .filter(|(_, _, parsed)| parsed.errors.is_empty())
+                .filter_map(|(path, stamp, parsed)| {
+                    Some(CachedFileRef {
+                        path,
+                        stamp: (*stamp)?,
+                        parsed,
+                    })
+                })
+                .collect(),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        serde_json::to_writer(&mut writer, &cache).map_err(io::Error::other)?;
+        writer.flush()
+    }
+}
+
+/// 刪除所有基礎路徑的掃描快取
+pub fn clear_cache(dir: &Path) -> io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn cache_file_name(base_path: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    base_path.hash(&mut hasher);
+    format!("{:016x}.json", hasher.finish())
+}
@@ -0,0 +1,254 @@
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::database::{ScanMode, ScanOptions, SharedDatabase, SharedDefDatabase, SCAN_TASK};
+use crate::defs::DefRootRules;
+use crate::diff::compute_diff;
+use crate::intern;
+use crate::scan_cache::{clear_cache, CACHE_DIR};
+use crate::settings::AppSettings;
+use crate::status::StatusSink;
+
+/// 掃描快取的目錄；無法取得設置目錄時不使用快取
+pub fn scan_cache_dir() -> Option<PathBuf> {
+    AppSettings::config_dir().ok().map(|dir| dir.join(CACHE_DIR))
+}
+
+/// 分頁向掃描協調器請求重新掃描的共享旗標
+#[derive(Clone, Default)]
+pub struct ScanRequest {
+    requested: Arc<AtomicBool>,
+    clear_cache: Arc<AtomicBool>, // 掃描前先刪除掃描快取
+}
+
+impl ScanRequest {
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+
+    /// 刪除掃描快取後重新解析所有檔案
+    pub fn request_without_cache(&self) {
+        self.clear_cache.store(true, Ordering::Relaxed);
+        self.request();
+    }
+
+    fn take(&self) -> bool {
+        self.requested.swap(false, Ordering::Relaxed)
+    }
+
+    fn take_clear_cache(&self) -> bool {
+        self.clear_cache.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// 掃描協調器：路徑變更或任何分頁要求重新整理時，只在背景執行一次掃描
+pub struct ScanCoordinator {
+    database: SharedDatabase,
+    status: StatusSink,
+    settings: Arc<Mutex<AppSettings>>,
+    request: ScanRequest,
+    scanned_path: String,                                // 上次觸發掃描的路徑
+    scanned_rules: DefRootRules,                         // 上次觸發掃描的 Def 根元素規則
+    scanned_mode: ScanMode,                              // 上次觸發掃描的掃描範圍
+    scanned_exclusions: Vec<String>,                     // 上次觸發掃描的排除樣式
+    result: Arc<Mutex<Option<SharedDefDatabase>>>,       // 背景掃描完成的資料庫
+    scanning: bool,
+    queued: bool,                                        // 掃描進行中又收到新的請求
+    cancel: Arc<AtomicBool>,                             // 進行中的掃描的取消旗標
+}
+
+impl ScanCoordinator {
+    pub fn new(
+        database: SharedDatabase,
+        status: StatusSink,
+        settings: Arc<Mutex<AppSettings>>,
+        request: ScanRequest,
+    ) -> Self {
+        Self {
+            database,
+            status,
+            settings,
+            request,
+            scanned_path: String::new(),
+            scanned_rules: DefRootRules::default(),
+            scanned_mode: ScanMode::default(),
+            scanned_exclusions: Vec::new(),
+            result: Arc::new(Mutex::new(None)),
+            scanning: false,
+            queued: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 每幀呼叫：偵測路徑變更、收取掃描結果、啟動排隊中的掃描
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let (base_path, rules, mode, exclusions) = self
+            .settings
+            .lock()
+            .map(|s| (s.active_base_path(), s.def_root_rules(), s.scan_mode.clone(), s.scan_exclusions.clone()))
+            .unwrap_or_default();
+        if base_path != self.scanned_path {
+            self.scanned_path = base_path;
+            self.queued = true;
+        }
+        if rules != self.scanned_rules {
+            self.scanned_rules = rules;
+            self.queued = true;
+        }
+        if mode != self.scanned_mode {
+            self.scanned_mode = mode;
+            self.queued = true;
+        }
+        if exclusions != self.scanned_exclusions {
+            self.scanned_exclusions = exclusions;
+            self.queued = true;
+        }
+
+        if self.request.take() {
+            self.queued = true;
+        }
+        if self.request.take_clear_cache() {
+            self.clear_cache();
+        }
+
+        self.collect_result();
+
+        // 新的請求取代進行中的掃描：通知舊的執行緒停止，並捨棄其結果
+        if self.queued && self.scanning {
+            self.cancel_scan();
+        }
+
+        if self.queued && !self.scanning {
+            self.queued = false;
+            if !self.scanned_path.is_empty() {
+                self.start_scan(ctx);
+            }
+        }
+    }
+
+    /// 將路徑視為已處理：變更到此路徑時不自動掃描，等使用者按 🔄 或 F5
+    pub fn skip_path(&mut self, path: &str) {
+        self.scanned_path = path.to_string();
+    }
+
+    /// 沒有進行中或排隊中的掃描
+    pub fn is_idle(&self) -> bool {
+        !self.scanning && !self.queued
+    }
+
+    /// 取消進行中的掃描；舊的執行緒寫入的是已捨棄的結果槽，不會覆蓋之後的掃描
+    fn cancel_scan(&mut self) {
+        tracing::info!("取消進行中的掃描");
+        self.cancel.store(true, Ordering::Relaxed);
+        self.result = Arc::new(Mutex::new(None));
+        self.scanning = false;
+
+        if let Ok(mut db) = self.database.write() {
+            db.refreshing = false;
+        }
+        if let Ok(mut status) = self.status.lock() {
+            status.end_task(SCAN_TASK);
+        }
+    }
+
+    fn clear_cache(&self) {
+        let Some(dir) = scan_cache_dir() else {
+            return;
+        };
+        let message = match clear_cache(&dir) {
+            Ok(()) => "已清除掃描快取，重新解析所有檔案".to_string(),
+            Err(e) => format!("錯誤: 無法清除掃描快取: {}", e),
+        };
+        if let Ok(mut status) = self.status.lock() {
+            status.set_message("掃描", message);
+        }
+    }
+
+    fn start_scan(&mut self, ctx: &egui::Context) {
+        self.scanning = true;
+        self.cancel = Arc::new(AtomicBool::new(false));
+
+        // 保留舊資料並標記為更新中，分頁不必清空畫面
+        if let Ok(mut db) = self.database.write() {
+            db.refreshing = true;
+        }
+        if let Ok(mut status) = self.status.lock() {
+            status.begin_task(SCAN_TASK);
+            status.set_loading("掃描", "正在掃描 Defs...");
+        }
+
+        let base_path = PathBuf::from(&self.scanned_path);
+        let rules = self.scanned_rules.clone();
+        let mode = self.scanned_mode.clone();
+        let exclusions = self.scanned_exclusions.clone();
+        let status = self.status.clone();
+        let result = self.result.clone();
+        let cancel = self.cancel.clone();
+        let cache_dir = scan_cache_dir();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            tracing::info!(path = %base_path.display(), "開始掃描");
+
+            // 在鎖外建立新的資料庫，完成後再一次替換
+            let mut db = SharedDefDatabase::default();
+            let options = ScanOptions {
+                cancel: Some(&cancel),
+                cache_dir: cache_dir.as_deref(),
+                exclude: &exclusions,
+            };
+            let Some(xml_count) = db.scan_cancellable(&base_path, &rules, &mode, &status, &options) else {
+                return;
+            };
+
+            tracing::info!(
+                xml_count,
+                def_types = db.entries.len(),
+                defs = db.total_entries(),
+                all_defs = db.data.len(),
+                tags = db.tag_index.len(),
+                skipped = db.errors.len(),
+                excluded = db.excluded_files,
+                "掃描完成"
+            );
+
+            if let Ok(mut result) = result.lock() {
+                *result = Some(db);
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn collect_result(&mut self) {
+        let Some(mut new_db) = self.result.lock().ok().and_then(|mut r| r.take()) else {
+            return;
+        };
+        self.scanning = false;
+
+        let message = format!(
+            "掃描完成！找到 {} 種類型，共 {} 個 Defs，{} 個繼承資料（包括抽象定義）{}",
+            new_db.entries.len(),
+            new_db.total_entries(),
+            new_db.data.len(),
+            new_db.skipped_suffix()
+        );
+
+        if let Ok(mut db) = self.database.write() {
+            // 重新掃描同一目錄時記錄變更
+            if db.generation > 0 && db.base_path == new_db.base_path {
+                new_db.changes = Some(compute_diff(&db.entries, &new_db.entries));
+            }
+            new_db.generation = db.generation + 1;
+            *db = new_db;
+        }
+        // 舊資料庫已釋放，清掉只剩字串池引用的字串
+        let pruned = intern::prune_unused();
+        tracing::debug!(pruned, remaining = intern::pool_size(), "字串池清理完成");
+        if let Ok(mut status) = self.status.lock() {
+            status.end_task(SCAN_TASK);
+            status.notify("掃描", message);
+        }
+    }
+}
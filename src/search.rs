@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+/// 連續相符字元的加分
+const CONSECUTIVE_BONUS: u32 = 5;
+/// 在字首或單字開頭（`_`、`-`、空白之後或小寫接大寫）相符的加分
+const WORD_START_BONUS: u32 = 3;
+
+/// 模糊比對的結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: u32,
+    pub positions: Vec<usize>, // 相符字元在 `text` 中的字元索引，由小到大
+}
+
+/// 模糊比對：`query` 的所有字元依序出現在 `text` 中即相符（不分大小寫），
+/// 每個相符字元得 1 分，連續相符與單字開頭另外加分；空的查詢視為相符、0 分
+pub fn fuzzy_match(text: &str, query: &str) -> Option<FuzzyMatch> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut positions = Vec::new();
+    let mut next = 0;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[next..].iter().position(|&c| same_char(c, q))?;
+        let index = next + offset;
+        score += 1;
+        if index > 0 && positions.last() == Some(&(index - 1)) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if is_word_start(&text, index) {
+            score += WORD_START_BONUS;
+        }
+        positions.push(index);
+        next = index + 1;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+/// 只需要分數時使用
+pub fn fuzzy_score(text: &str, query: &str) -> Option<u32> {
+    fuzzy_match(text, query).map(|m| m.score)
+}
+
+/// 文字中所有與 `query` 相同（不分大小寫）的位置，回傳依序且不重疊的位元組範圍；空的查詢沒有結果
+pub fn find_all(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let mut next = 0;
+    for (start, _) in text.char_indices() {
+        if start < next {
+            continue;
+        }
+        let mut chars = text[start..].char_indices();
+        let mut end = start;
+        let found = query.chars().all(|q| match chars.next() {
+            Some((offset, c)) if same_char(c, q) => {
+                end = start + offset + c.len_utf8();
+                true
+            }
+            _ => false,
+        });
+        if found {
+            matches.push(start..end);
+            next = end;
+        }
+    }
+    matches
+}
+
+fn same_char(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn is_word_start(text: &[char], index: usize) -> bool {
+    let Some(previous) = index.checked_sub(1).map(|i| text[i]) else {
+        return true;
+    };
+    matches!(previous, '_' | '-' | ' ' | '/' | '.') || (previous.is_lowercase() && text[index].is_uppercase())
+}
@@ -0,0 +1,202 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::defs::DefEntry;
+use crate::intern::Symbol;
+use crate::xml_parser::read_xml_file;
+
+/// 全域搜尋索引中的一個條目，名稱與 label 預先轉為小寫
+#[derive(Debug, Clone)]
+struct IndexedDef {
+    def_type: Symbol,
+    index: usize, // 在 `entries[def_type]` 中的索引
+    name: String,
+    label: String,
+}
+
+/// 全域搜尋的一筆結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub def_type: Symbol,
+    pub index: usize,
+    pub rank: SearchRank,
+}
+
+/// 結果的相符程度，數值越小排越前面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SearchRank {
+    Exact,
+    Prefix,
+    Substring,
+    Label,
+}
+
+/// 跨所有 Def 類型的 defName / label 索引，掃描後建立一次
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    defs: Vec<IndexedDef>,
+}
+
+impl SearchIndex {
+    pub fn build(entries: &BTreeMap<Symbol, Vec<DefEntry>>) -> Self {
+        let defs = entries
+            .iter()
+            .flat_map(|(def_type, entries)| {
+                entries.iter().enumerate().map(move |(index, entry)| IndexedDef {
+                    def_type: def_type.clone(),
+                    index,
+                    name: entry.def_name.to_lowercase(),
+                    label: entry.label.as_deref().unwrap_or_default().to_lowercase(),
+                })
+            })
+            .collect();
+        Self { defs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// 依完全相符、前綴、子字串、label 相符的順序排列，同一等級內依 defName 排序
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<(SearchRank, &IndexedDef)> = self
+            .defs
+            .iter()
+            .filter_map(|def| {
+                let rank = if def.name == query {
+                    SearchRank::Exact
+                } else if def.name.starts_with(&query) {
+                    SearchRank::Prefix
+                } else if def.name.contains(&query) {
+                    SearchRank::Substring
+                } else if def.label.contains(&query) {
+                    SearchRank::Label
+                } else {
+                    return None;
+                };
+                Some((rank, def))
+            })
+            .collect();
+        hits.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.def_type.cmp(&b.def_type))
+        });
+        hits.into_iter()
+            .map(|(rank, def)| SearchHit {
+                def_type: def.def_type.clone(),
+                index: def.index,
+                rank,
+            })
+            .collect()
+    }
+}
+
+/// Def 檔案所屬的模組：`Defs` 資料夾的上一層目錄名稱（例如 `Core`、模組資料夾），
+/// 略過 `1.4`、`Common` 等版本資料夾
+pub fn mod_name(file_path: &Path) -> Option<String> {
+    let names: Vec<String> = file_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let defs = names.iter().rposition(|name| name == "Defs")?;
+    names[..defs]
+        .iter()
+        .rev()
+        .find(|name| {
+            let is_version = name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_digit() || c == '.');
+            !is_version && name.as_str() != "Common"
+        })
+        .cloned()
+}
+
+/// 模組 `About/About.xml` 中的資訊
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModAbout {
+    pub root: PathBuf, // 含有 About 資料夾的模組目錄
+    pub name: Option<String>,
+    pub package_id: Option<String>,
+    pub author: Option<String>, // `<author>`，沒有時為 `<authors>` 中的各項以逗號連接
+}
+
+impl ModAbout {
+    /// 顯示名稱：`<name>`，沒有時為模組資料夾名稱
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.root.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default()
+    }
+}
+
+/// 從檔案往上找到含有 `About/About.xml` 的資料夾並讀取其中的資訊；往上都找不到 About.xml 時為 None，
+/// About.xml 無法解析時各欄位為 None
+pub fn find_mod_about(file_path: &Path) -> Option<ModAbout> {
+    let root = file_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("About").join("About.xml").is_file())?;
+    let mut about = ModAbout {
+        root: root.to_path_buf(),
+        name: None,
+        package_id: None,
+        author: None,
+    };
+    if let Ok(content) = read_xml_file(&root.join("About").join("About.xml")) {
+        read_about_fields(&content, &mut about);
+    }
+    Some(about)
+}
+
+/// 只讀取根元素的直接子元素，`<modDependencies>` 等清單中的 `<packageId>` 不算
+fn read_about_fields(content: &str, about: &mut ModAbout) {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+    let mut path: Vec<String> = Vec::new();
+    let mut authors: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => path.push(String::from_utf8_lossy(e.name().as_ref()).to_string()),
+            Ok(Event::End(_)) => {
+                path.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let Ok(text) = e.unescape() else {
+                    continue;
+                };
+                let text = text.trim().to_string();
+                match path.iter().skip(1).map(String::as_str).collect::<Vec<_>>().as_slice() {
+                    ["name"] => about.name = Some(text),
+                    ["packageId"] => about.package_id = Some(text),
+                    ["author"] => about.author = Some(text),
+                    ["authors", "li"] => authors.push(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    if about.author.is_none() && !authors.is_empty() {
+        about.author = Some(authors.join(", "));
+    }
+}
+
+/// 檔案所屬模組的顯示名稱：從檔案往上找到含有 `About/About.xml` 的資料夾，讀取其中的 `<name>`；
+/// About.xml 無法解析或沒有 `<name>` 時使用該資料夾名稱，往上都找不到 About.xml 時為 None
+pub fn about_mod_name(file_path: &Path) -> Option<String> {
+    find_mod_about(file_path).map(|about| about.display_name())
+}
@@ -1,23 +1,206 @@
-use eframe::egui;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::database::ScanMode;
+use crate::defs::{AbstractFilter, DefRootRules, EntrySort};
+use crate::game_view::GameViewConfig;
+use crate::merge::MergeOrder;
+
+/// Def 瀏覽器的篩選預設
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickFilter {
+    pub name: String,
+    pub def_type_pattern: Option<String>,
+    pub path_pattern: Option<String>,
+    #[serde(default)]
+    pub tag_path: Option<String>,
+    #[serde(default)]
+    pub abstract_filter: AbstractFilter,
+}
+
+/// 瀏覽器「最近檢視」中的一個 Def
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentDefEntry {
+    pub def_type: String,
+    pub def_name: String,
+    pub file_path: PathBuf,
+}
+
+/// 瀏覽器「收藏」中的一個 Def
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub def_type: String,
+    pub def_name: String,
+}
 
 /// 共享的應用設置
 #[derive(Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub base_path: String,  // 統一的基礎路徑
+    #[serde(skip)]
+    pub session_base_path: Option<PathBuf>, // 命令列指定的路徑，只在本次執行中取代 `base_path`，不寫入設置檔
+    #[serde(default)]
+    pub quick_filters: Vec<QuickFilter>, // 瀏覽器篩選預設
+    #[serde(default)]
+    pub log_level: LogLevel,  // 日誌檔記錄等級
+    #[serde(default)]
+    pub last_seen_version: String, // 上次顯示過更新內容的版本
+    #[serde(default = "default_rimworld_version")]
+    pub rimworld_version: String, // 目標 RimWorld 版本，驗證時與檔案的版本註解比較
+    #[serde(default = "default_texture_cache_mb")]
+    pub texture_cache_mb: usize, // 貼圖預覽快取的記憶體上限
+    #[serde(default)]
+    pub recent_defs: Vec<RecentDefEntry>, // 瀏覽器最近檢視的 Defs（最新的在前）
+    #[serde(default)]
+    pub game_view: GameViewConfig, // 最終載入結果的載入順序與 DLC
+    #[serde(default)]
+    pub def_roots: DefRootRules, // <Defs> 下哪些元素視為 Def
+    #[serde(default)]
+    pub ignored_def_types: Vec<String>, // 掃描時略過、不出現在任何檢視中的 Def 類型
+    #[serde(default)]
+    pub inheritance_merge_order: MergeOrder, // 展開繼承時合併繼承鏈的順序
+    #[serde(default)]
+    pub scan_mode: ScanMode, // 瀏覽器收錄哪些 XML 檔案
+    #[serde(default)]
+    pub preview_exports: bool, // 「僅預覽」：匯出前列出將寫入的檔案與大小，確認後才寫入
+    #[serde(default)]
+    pub scan_exclusions: Vec<String>, // 掃描時略過的路徑樣式（相對於基礎路徑，例如 `**/Source/**`）
+    #[serde(default = "default_required_tags")]
+    pub required_tags: HashMap<String, Vec<String>>, // Def 類型 -> 驗證時非抽象 Def 必須有的標籤
+    #[serde(default = "default_dark_mode")]
+    pub dark_mode: bool, // 深色或淺色主題
+    #[serde(default)]
+    pub editor_command: String, // 開啟檔案並跳到指定行的命令，例如 `code --goto {file}:{line}`；空白時以預設程式開啟
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>, // 瀏覽器收藏的 Defs，依加入順序
+    #[serde(default = "default_csv_columns")]
+    pub csv_columns: Vec<String>, // 瀏覽器匯出 CSV 時的標籤路徑欄位
+    #[serde(default)]
+    pub entry_sorts: HashMap<String, EntrySort>, // Def 類型 -> 瀏覽器中條目的排序方式，沒有時依 defName
+}
+
+fn default_rimworld_version() -> String {
+    "1.5".to_string()
+}
+
+fn default_texture_cache_mb() -> usize {
+    64
+}
+
+fn default_dark_mode() -> bool {
+    true
+}
+
+fn default_csv_columns() -> Vec<String> {
+    ["label", "statBases/MarketValue", "statBases/Mass"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+}
+
+fn default_required_tags() -> HashMap<String, Vec<String>> {
+    [
+        ("ThingDef", &["defName", "label"][..]),
+        ("RecipeDef", &["defName", "label", "jobString"][..]),
+    ]
+    .into_iter()
+    .map(|(def_type, tags)| (def_type.to_string(), tags.iter().map(|t| t.to_string()).collect()))
+    .collect()
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             base_path: String::new(),
+            session_base_path: None,
+            quick_filters: Vec::new(),
+            log_level: LogLevel::default(),
+            last_seen_version: String::new(),
+            rimworld_version: default_rimworld_version(),
+            texture_cache_mb: default_texture_cache_mb(),
+            recent_defs: Vec::new(),
+            game_view: GameViewConfig::default(),
+            def_roots: DefRootRules::default(),
+            ignored_def_types: Vec::new(),
+            inheritance_merge_order: MergeOrder::default(),
+            scan_mode: ScanMode::default(),
+            preview_exports: false,
+            scan_exclusions: Vec::new(),
+            required_tags: default_required_tags(),
+            dark_mode: default_dark_mode(),
+            editor_command: String::new(),
+            bookmarks: Vec::new(),
+            csv_columns: default_csv_columns(),
+            entry_sorts: HashMap::new(),
         }
     }
 }
 
+/// 將編輯器命令拆成程式與參數，並代入 `{file}` 與 `{line}`；命令為空白時為 None
+///
+/// 以空白分隔，雙引號內的空白不分隔（例如 `"C:\Program Files\Editor\editor.exe" {file}`）；
+/// 先拆分再代入，所以含空白的檔案路徑仍是單一參數
+pub fn editor_command_args(template: &str, file: &Path, line: u64) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+    for c in template.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    let file = file.display().to_string();
+    let line = line.to_string();
+    let args: Vec<String> = args
+        .into_iter()
+        .map(|arg| arg.replace("{file}", &file).replace("{line}", &line))
+        .collect();
+    (!args.is_empty()).then_some(args)
+}
+
 impl AppSettings {
+    /// 掃描時使用的 Def 根元素規則，包含忽略的 Def 類型
+    pub fn def_root_rules(&self) -> DefRootRules {
+        DefRootRules {
+            ignored: self.ignored_def_types.clone(),
+            ..self.def_roots.clone()
+        }
+    }
+
+    /// 目前使用的基礎路徑：命令列指定的路徑優先於設置中的路徑
+    pub fn active_base_path(&self) -> String {
+        match &self.session_base_path {
+            Some(path) => path.display().to_string(),
+            None => self.base_path.clone(),
+        }
+    }
+
+    /// 設定並使用新的基礎路徑，取代命令列指定的路徑
+    pub fn set_base_path(&mut self, path: String) {
+        self.base_path = path;
+        self.session_base_path = None;
+    }
+
     /// 從檔案載入設置
     pub fn load() -> Self {
         if let Ok(config_path) = Self::config_path() {
@@ -30,7 +213,12 @@ impl AppSettings {
         Self::default()
     }
 
-    /// 儲存設置到檔案
+    /// 第一次啟動（尚未儲存過設置檔）
+    pub fn is_first_run() -> bool {
+        Self::config_path().map_or(true, |path| !path.exists())
+    }
+
+        /// 儲存設置到檔案
     pub fn save(&self) {
         if let Ok(config_path) = Self::config_path() {
             if let Some(parent) = config_path.parent() {
@@ -42,92 +230,174 @@ impl AppSettings {
         }
     }
 
-    /// 獲取設置檔案路徑
-    fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    /// 獲取設置檔案路徑（可攜模式時位於執行檔旁）
+    pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match PortableMode::current() {
+            PortableMode::On(dir) => Ok(dir.join("settings.json")),
+            _ => Ok(Self::user_dir()?.join("settings.json")),
+        }
+    }
+
+    /// 獲取資料目錄（當機日誌、日誌檔等；可攜模式時為執行檔旁的 data/）
+    pub fn config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match PortableMode::current() {
+            PortableMode::On(dir) => Ok(dir.join(PORTABLE_DATA_DIR)),
+            _ => Self::user_dir(),
+        }
+    }
+
+    /// 使用者目錄下的設置目錄（APPDATA 或 HOME）
+    fn user_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let mut path = if cfg!(target_os = "windows") {
             PathBuf::from(std::env::var("APPDATA")?)
         } else {
             PathBuf::from(std::env::var("HOME")?)
         };
-        
+
         path.push("RimWorldXMLTools");
-        path.push("settings.json");
         Ok(path)
     }
-}
 
-/// 設置分頁
-pub struct SettingsTab {
-    settings: Arc<Mutex<AppSettings>>,
-    status_message: String,
-}
+    /// 在常見的 Steam 安裝位置尋找 RimWorld 的 Data 目錄
+    pub fn detect_rimworld_data() -> Option<PathBuf> {
+        const GAME_DIR: &str = "steamapps/common/RimWorld";
 
-impl SettingsTab {
-    pub fn new(settings: Arc<Mutex<AppSettings>>) -> Self {
-        Self {
-            settings,
-            status_message: String::new(),
+        let mut steam_roots: Vec<PathBuf> = Vec::new();
+        if cfg!(target_os = "windows") {
+            for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+                if let Ok(dir) = std::env::var(var) {
+                    steam_roots.push(PathBuf::from(dir).join("Steam"));
+                }
+            }
+            // 其他磁碟上的 Steam 資料庫
+            for drive in 'C'..='H' {
+                steam_roots.push(PathBuf::from(format!("{}:\\SteamLibrary", drive)));
+                steam_roots.push(PathBuf::from(format!("{}:\\Steam", drive)));
+            }
+        } else if let Ok(home) = std::env::var("HOME") {
+            let home = PathBuf::from(home);
+            steam_roots.push(home.join(".steam/steam"));
+            steam_roots.push(home.join(".local/share/Steam"));
+            steam_roots.push(home.join("Library/Application Support/Steam"));
         }
+
+        steam_roots.into_iter().find_map(|root| {
+            let game = root.join(GAME_DIR);
+            [game.join("Data"), game.join("RimWorldMac.app/Data")]
+                .into_iter()
+                .find(|data| data.is_dir())
+        })
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
-        ui.heading("🔧 路徑設置");
-        ui.separator();
+        /// 切換為可攜模式：在執行檔旁建立標記檔並寫入目前的設置，回傳新的設置檔路徑
+    pub fn enable_portable(&self) -> Result<PathBuf, String> {
+        let dir = exe_dir().ok_or("無法取得執行檔所在目錄")?;
+        if !is_writable(&dir) {
+            return Err(format!("執行檔所在目錄無法寫入: {}", dir.display()));
+        }
+
+        std::fs::write(dir.join(PORTABLE_FLAG), "")
+            .map_err(|e| format!("無法建立 {}: {}", PORTABLE_FLAG, e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let config_path = dir.join("settings.json");
+        std::fs::write(&config_path, json).map_err(|e| format!("無法寫入設置檔: {}", e))?;
+
+        if let Ok(mut mode) = PORTABLE_MODE.write() {
+            *mode = Some(PortableMode::On(dir));
+        }
+        Ok(config_path)
+    }
+}
 
-        ui.label("在此處設置統一的工作目錄路徑。所有功能將使用此路徑作為基礎目錄。");
-        ui.add_space(10.0);
+/// 可攜模式的標記檔名，放在執行檔旁
+pub const PORTABLE_FLAG: &str = "portable.flag";
 
-        let mut settings = self.settings.lock().unwrap();
-        let mut changed = false;
+/// 可攜模式下存放日誌等檔案的子目錄
+const PORTABLE_DATA_DIR: &str = "data";
 
-        // 統一的基礎路徑
-        ui.group(|ui| {
-            ui.horizontal(|ui| {
-                ui.label("工作目錄:");
-            });
-            
-            ui.horizontal(|ui| {
-                if ui.text_edit_singleline(&mut settings.base_path).changed() {
-                    changed = true;
-                }
+/// 第一次查詢後的可攜模式狀態
+static PORTABLE_MODE: RwLock<Option<PortableMode>> = RwLock::new(None);
 
-                if ui.button("📂 選擇目錄").clicked() {
-                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                        settings.base_path = path.display().to_string();
-                        changed = true;
-                    }
+/// 設置的存放位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortableMode {
+    Off,               // 使用 APPDATA / HOME
+    On(PathBuf),       // 使用執行檔所在目錄
+    ReadOnly(PathBuf), // 要求可攜模式但目錄無法寫入，退回使用者目錄
+}
+
+impl PortableMode {
+    /// 執行檔旁有 portable.flag 或 settings.json 時啟用可攜模式
+    pub fn current() -> PortableMode {
+        if let Some(mode) = PORTABLE_MODE.read().ok().and_then(|m| m.clone()) {
+            return mode;
+        }
+
+        let mode = match exe_dir() {
+            Some(dir)
+                if dir.join(PORTABLE_FLAG).is_file() || dir.join("settings.json").is_file() =>
+            {
+                if is_writable(&dir) {
+                    PortableMode::On(dir)
+                } else {
+                    PortableMode::ReadOnly(dir)
                 }
-            });
-            
-            ui.label("此路徑將用於所有功能：Def 瀏覽器、繼承展開、標籤查找器");
-        });
-
-        ui.add_space(20.0);
-
-        // 操作按鈕
-        ui.horizontal(|ui| {
-            if ui.button("💾 儲存設置").clicked() || changed {
-                settings.save();
-                self.status_message = "✅ 設置已儲存".to_string();
             }
+            _ => PortableMode::Off,
+        };
+        if let Ok(mut cached) = PORTABLE_MODE.write() {
+            *cached = Some(mode.clone());
+        }
+        mode
+    }
 
-            if ui.button("🔄 重置為空").clicked() {
-                *settings = AppSettings::default();
-                settings.save();
-                self.status_message = "✅ 已重置路徑".to_string();
-            }
+    /// 無法使用可攜模式時的警告訊息
+    pub fn warning(&self) -> Option<String> {
+        match self {
+            PortableMode::ReadOnly(dir) => Some(format!(
+                "執行檔所在目錄無法寫入，可攜模式已停用，設置與日誌將改存於使用者目錄:\n{}",
+                dir.display()
+            )),
+            _ => None,
+        }
+    }
+}
 
-            if !self.status_message.is_empty() {
-                ui.colored_label(egui::Color32::from_rgb(0, 200, 0), &self.status_message);
-            }
-        });
-
-        ui.add_space(10.0);
-        ui.separator();
-        
-        // 顯示設置檔案位置
-        if let Ok(config_path) = AppSettings::config_path() {
-            ui.label(format!("💾 設置檔案: {}", config_path.display()));
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+/// 實際建立 data/ 與暫存檔來確認目錄可寫入（唯讀屬性無法反映權限設定）
+fn is_writable(dir: &Path) -> bool {
+    let data_dir = dir.join(PORTABLE_DATA_DIR);
+    if std::fs::create_dir_all(&data_dir).is_err() {
+        return false;
+    }
+    let probe = data_dir.join(".write_test");
+    let writable = std::fs::write(&probe, "").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    writable
+}
+
+/// 日誌等級（設置中的詳細程度）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "錯誤",
+            LogLevel::Warn => "警告",
+            LogLevel::Info => "資訊",
+            LogLevel::Debug => "除錯",
         }
     }
 }
@@ -0,0 +1,573 @@
+use eframe::egui;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::database::{ScanMode, SharedDatabase};
+use crate::defs::{AbstractFilter, DefRootRules};
+use crate::settings::{AppSettings, LogLevel, PortableMode, QuickFilter};
+use crate::status::StatusSink;
+use crate::widgets::show_confirm_dialog;
+
+/// 設置分頁
+pub struct SettingsTab {
+    settings: Arc<Mutex<AppSettings>>,
+    database: SharedDatabase,
+    status: StatusSink,
+    confirm_reset_all: bool, // 顯示「重置所有設置」確認對話框
+    def_roots: Option<(String, String)>, // 編輯中的 Def 根元素允許 / 排除清單（每行一個）
+    ignored_types: Option<BTreeSet<String>>, // 編輯中的忽略 Def 類型
+    scan_mode: Option<(ScanMode, String)>, // 編輯中的掃描範圍與自訂目錄名稱（每行一個）
+    scan_exclusions: Option<String>, // 編輯中的排除樣式（每行一個）
+    required_tags: Option<Vec<(String, String)>>, // 編輯中的必要標籤（Def 類型, 以逗號分隔的標籤）
+}
+
+impl SettingsTab {
+    pub fn new(settings: Arc<Mutex<AppSettings>>, database: SharedDatabase, status: StatusSink) -> Self {
+        Self {
+            settings,
+            database,
+            status,
+            confirm_reset_all: false,
+            def_roots: None,
+            ignored_types: None,
+            scan_mode: None,
+            scan_exclusions: None,
+            required_tags: None,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
+        ui.heading("🔧 路徑設置");
+        ui.separator();
+
+        ui.label("在此處設置統一的工作目錄路徑。所有功能將使用此路徑作為基礎目錄。");
+        ui.add_space(10.0);
+
+        let mut settings = self.settings.lock().unwrap();
+        let mut changed = false;
+
+        // 統一的基礎路徑
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("工作目錄:");
+            });
+            
+            ui.horizontal(|ui| {
+                if ui.text_edit_singleline(&mut settings.base_path).changed() {
+                    settings.session_base_path = None;
+                    changed = true;
+                }
+
+                if ui.button("📂 選擇目錄").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        settings.set_base_path(path.display().to_string());
+                        changed = true;
+                    }
+                }
+            });
+
+            // 命令列指定的路徑只在本次執行中使用，需明確選擇才會寫入設置檔
+            if let Some(session_path) = settings.session_base_path.clone() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 200, 0),
+                        format!("本次執行使用命令列指定的目錄: {}", session_path.display()),
+                    );
+                    if ui.button("設為工作目錄").clicked() {
+                        settings.set_base_path(session_path.display().to_string());
+                        changed = true;
+                    }
+                    if ui.button("改用上方的目錄").clicked() {
+                        settings.session_base_path = None;
+                    }
+                });
+            }
+            
+            ui.label("此路徑將用於所有功能：Def 瀏覽器、繼承展開、標籤查找器");
+        });
+
+        ui.add_space(10.0);
+
+        // 篩選預設管理
+        ui.group(|ui| {
+            ui.label("Def 瀏覽器篩選預設:");
+
+            if settings.quick_filters.is_empty() {
+                ui.weak("尚無預設，可在 Def 瀏覽器的篩選區塊中儲存目前的篩選條件");
+            }
+
+            let count = settings.quick_filters.len();
+            let mut move_up = None;
+            let mut move_down = None;
+            let mut remove = None;
+            for (index, preset) in settings.quick_filters.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.text_edit_singleline(&mut preset.name).changed() {
+                        changed = true;
+                    }
+                    if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                        move_up = Some(index);
+                    }
+                    if ui.add_enabled(index + 1 < count, egui::Button::new("⬇")).clicked() {
+                        move_down = Some(index);
+                    }
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.weak(quick_filter_summary(preset));
+                });
+            }
+
+            if let Some(index) = move_up {
+                settings.quick_filters.swap(index, index - 1);
+                changed = true;
+            }
+            if let Some(index) = move_down {
+                settings.quick_filters.swap(index, index + 1);
+                changed = true;
+            }
+            if let Some(index) = remove {
+                settings.quick_filters.remove(index);
+                changed = true;
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // 目標 RimWorld 版本
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("RimWorld 版本:");
+                if ui
+                    .add(egui::TextEdit::singleline(&mut settings.rimworld_version).desired_width(60.0))
+                    .changed()
+                {
+                    changed = true;
+                }
+            });
+            ui.label("驗證分頁會將檔案中的版本註解（例如 <!-- RimWorld 1.4 -->）與此版本比較");
+        });
+
+        ui.add_space(10.0);
+
+        // 必要標籤：每列一個 Def 類型，標籤以逗號分隔
+        let rows = self.required_tags.get_or_insert_with(|| {
+            let mut rows: Vec<(String, String)> = settings
+                .required_tags
+                .iter()
+                .map(|(def_type, tags)| (def_type.clone(), tags.join(", ")))
+                .collect();
+            rows.sort();
+            rows
+        });
+        let mut apply_required = false;
+        ui.group(|ui| {
+            ui.label("必要標籤:");
+            ui.label("驗證分頁會列出缺少這些標籤的非抽象 Def（父類提供的標籤也算），標籤以逗號分隔");
+            let mut removed = None;
+            egui::Grid::new("required_tags_grid").num_columns(3).show(ui, |ui| {
+                for (i, (def_type, tags)) in rows.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(def_type).desired_width(140.0).hint_text("Def 類型"));
+                    ui.add(egui::TextEdit::singleline(tags).desired_width(260.0).hint_text("defName, label"));
+                    if ui.small_button("🗑").on_hover_text("移除此類型").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(i) = removed {
+                rows.remove(i);
+            }
+            let required = required_tag_map(rows);
+            ui.horizontal(|ui| {
+                if ui.button("➕ 新增類型").clicked() {
+                    rows.push((String::new(), "defName, label".to_string()));
+                }
+                let modified = required != settings.required_tags;
+                if ui.add_enabled(modified, egui::Button::new("✔ 套用")).clicked() {
+                    settings.required_tags = required;
+                    apply_required = true;
+                }
+                if modified {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "尚未套用");
+                }
+            });
+        });
+        if apply_required {
+            settings.save();
+            self.set_status("✅ 已更新必要標籤");
+        }
+
+        ui.add_space(10.0);
+
+        // 貼圖快取
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("貼圖預覽快取上限:");
+                if ui
+                    .add(egui::DragValue::new(&mut settings.texture_cache_mb).range(8..=2048).suffix(" MB"))
+                    .changed()
+                {
+                    changed = true;
+                }
+            });
+            ui.label("超過上限時會釋放最久未顯示的貼圖");
+        });
+
+        ui.add_space(10.0);
+
+        // 外部編輯器
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("編輯器命令:");
+                if ui
+                    .add(
+                        egui::TextEdit::singleline(&mut settings.editor_command)
+                            .desired_width(300.0)
+                            .hint_text("code --goto {file}:{line}"),
+                    )
+                    .changed()
+                {
+                    changed = true;
+                }
+            });
+            ui.label("在 Def 瀏覽器中點擊檔案路徑時執行，{file} 與 {line} 會代入檔案路徑與 Def 所在行；空白時以系統預設程式開啟");
+        });
+
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            if ui.checkbox(&mut settings.preview_exports, "匯出前預覽（僅預覽模式）").changed() {
+                changed = true;
+            }
+            ui.label("匯出 CSV、變更記錄、繼承樹 DOT 時先列出將寫入的檔案與大小，確認後才寫入");
+        });
+
+        ui.add_space(10.0);
+
+        // 掃描範圍：與 Def 根元素規則相同，套用後才重新掃描
+        let (mode, patterns) = self.scan_mode.get_or_insert_with(|| {
+            let patterns = match &settings.scan_mode {
+                ScanMode::Custom(patterns) => patterns.join("\n"),
+                _ => String::new(),
+            };
+            (settings.scan_mode.clone(), patterns)
+        });
+        let mut apply_mode = false;
+        ui.group(|ui| {
+            ui.label("掃描範圍:");
+            ui.label("瀏覽器收錄哪些 XML 檔案中的 Def；展開繼承與標籤查找器一律包含所有 XML");
+            ui.horizontal(|ui| {
+                for option in [ScanMode::DefsDirectoryOnly, ScanMode::AllXmlFiles] {
+                    let label = option.label();
+                    ui.radio_value(mode, option, label);
+                }
+                let custom = matches!(mode, ScanMode::Custom(_));
+                if ui.radio(custom, ScanMode::Custom(Vec::new()).label()).clicked() && !custom {
+                    *mode = ScanMode::Custom(root_names(patterns));
+                }
+            });
+            match mode {
+                ScanMode::DefsDirectoryOnly => {
+                    ui.weak("路徑中含有 Defs 的檔案（RimWorld 的標準結構）");
+                }
+                ScanMode::AllXmlFiles => {
+                    ui.weak("所有根元素為 <Defs> 的 XML，包含直接放在模組根目錄或 data/ 中的檔案");
+                }
+                ScanMode::Custom(names) => {
+                    ui.weak("所在目錄的任一層名稱符合其中一行即收錄（不分大小寫，可用 * 萬用字元）");
+                    if ui
+                        .add(egui::TextEdit::multiline(patterns).desired_rows(3).hint_text("例如 Defs\ndata"))
+                        .changed()
+                    {
+                        *names = root_names(patterns);
+                    }
+                }
+            }
+            ui.horizontal(|ui| {
+                let modified = *mode != settings.scan_mode;
+                if ui.add_enabled(modified, egui::Button::new("✔ 套用並重新掃描")).clicked() {
+                    settings.scan_mode = mode.clone();
+                    apply_mode = true;
+                }
+                if modified {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "尚未套用");
+                }
+            });
+        });
+        if apply_mode {
+            settings.save();
+            self.set_status("✅ 已更新掃描範圍，正在重新掃描");
+        }
+
+        ui.add_space(10.0);
+
+        // 排除樣式：套用後才重新掃描
+        let exclusions = self
+            .scan_exclusions
+            .get_or_insert_with(|| settings.scan_exclusions.join("\n"));
+        let mut apply_exclusions = false;
+        ui.group(|ui| {
+            ui.label("排除的路徑:");
+            ui.label("符合任一行的檔案在掃描時略過（相對於基礎路徑，不分大小寫）；含 / 的樣式比對整個路徑，** 可跨越多層目錄，其他樣式比對任一層名稱");
+            ui.add(
+                egui::TextEdit::multiline(exclusions)
+                    .desired_rows(3)
+                    .hint_text("例如 **/Source/**\n*backup*\nMyMod/1.4/Defs/Old"),
+            );
+            let patterns = root_names(exclusions);
+            ui.horizontal(|ui| {
+                let modified = patterns != settings.scan_exclusions;
+                if ui.add_enabled(modified, egui::Button::new("✔ 套用並重新掃描")).clicked() {
+                    settings.scan_exclusions = patterns;
+                    apply_exclusions = true;
+                }
+                if modified {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "尚未套用");
+                }
+            });
+        });
+        if apply_exclusions {
+            settings.save();
+            self.set_status("✅ 已更新排除的路徑，正在重新掃描");
+        }
+
+        ui.add_space(10.0);
+
+        // Def 根元素規則：套用後才會觸發重新掃描，避免每次輸入都重新掃描
+        let (allow, deny) = self.def_roots.get_or_insert_with(|| {
+            (settings.def_roots.allow.join("\n"), settings.def_roots.deny.join("\n"))
+        });
+        let mut apply_roots = false;
+        ui.group(|ui| {
+            ui.label("Def 根元素:");
+            ui.label("<Defs> 下名稱以 Def 結尾的元素都視為 Def；可額外允許或排除特定元素名稱（每行一個）");
+            ui.columns(2, |columns| {
+                columns[0].label("允許:");
+                columns[0].add(egui::TextEdit::multiline(allow).desired_rows(3).hint_text("例如 CustomThing"));
+                columns[1].label("排除:");
+                columns[1].add(egui::TextEdit::multiline(deny).desired_rows(3).hint_text("例如 ObsoleteDef"));
+            });
+            let rules = DefRootRules {
+                allow: root_names(allow),
+                deny: root_names(deny),
+                ..DefRootRules::default()
+            };
+            ui.horizontal(|ui| {
+                let modified = rules != settings.def_roots;
+                if ui.add_enabled(modified, egui::Button::new("✔ 套用並重新掃描")).clicked() {
+                    settings.def_roots = rules;
+                    apply_roots = true;
+                }
+                if modified {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "尚未套用");
+                }
+            });
+        });
+        if apply_roots {
+            settings.save();
+            self.set_status("✅ 已更新 Def 根元素規則，正在重新掃描");
+        }
+
+        ui.add_space(10.0);
+
+        // 忽略的 Def 類型：清單來自上次掃描找到的類型，加上目前已忽略的類型
+        let ignored = self
+            .ignored_types
+            .get_or_insert_with(|| settings.ignored_def_types.iter().cloned().collect());
+        let mut def_types: BTreeSet<String> = self
+            .database
+            .read()
+            .map(|db| db.entries.keys().map(|t| t.to_string()).collect())
+            .unwrap_or_default();
+        def_types.extend(settings.ignored_def_types.iter().cloned());
+        let mut apply_ignored = false;
+        ui.group(|ui| {
+            ui.label(format!("忽略的 Def 類型（{} 種）:", ignored.len()));
+            ui.label("勾選的類型在掃描時直接略過，不會出現在瀏覽器、展開繼承、驗證與匯出中");
+            if def_types.is_empty() {
+                ui.weak("掃描工作目錄後即可從找到的類型中選擇");
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("ignored_def_types")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for def_type in &def_types {
+                            let mut checked = ignored.contains(def_type);
+                            if ui.checkbox(&mut checked, def_type.as_str()).changed() {
+                                if checked {
+                                    ignored.insert(def_type.clone());
+                                } else {
+                                    ignored.remove(def_type);
+                                }
+                            }
+                        }
+                    });
+                });
+            let current: BTreeSet<&String> = settings.ignored_def_types.iter().collect();
+            let modified = ignored.iter().collect::<BTreeSet<_>>() != current;
+            ui.horizontal(|ui| {
+                if ui.add_enabled(modified, egui::Button::new("✔ 套用並重新掃描")).clicked() {
+                    apply_ignored = true;
+                }
+                if modified {
+                    ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "尚未套用");
+                }
+            });
+        });
+        if apply_ignored {
+            settings.ignored_def_types = ignored.iter().cloned().collect();
+            settings.save();
+            self.set_status("✅ 已更新忽略的 Def 類型，正在重新掃描");
+        }
+
+        ui.add_space(10.0);
+
+        // 日誌等級
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("日誌記錄等級:");
+                egui::ComboBox::from_id_salt("settings_log_level")
+                    .selected_text(settings.log_level.label())
+                    .show_ui(ui, |ui| {
+                        for level in LogLevel::ALL {
+                            if ui
+                                .selectable_value(&mut settings.log_level, level, level.label())
+                                .changed()
+                            {
+                                changed = true;
+                            }
+                        }
+                    });
+            });
+            ui.label("控制寫入日誌檔與日誌面板的訊息詳細程度，回報問題時可改為「除錯」");
+        });
+
+        ui.add_space(10.0);
+
+        // 可攜模式
+        let mut portable_result = None;
+        ui.group(|ui| {
+            match PortableMode::current() {
+                PortableMode::On(dir) => {
+                    ui.label(format!("🔌 可攜模式：設置與日誌存放於 {}", dir.display()));
+                }
+                mode => {
+                    if let Some(warning) = mode.warning() {
+                        ui.colored_label(egui::Color32::from_rgb(255, 200, 0), format!("⚠ {}", warning));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("🔌 切換為可攜模式").clicked() {
+                            portable_result = Some(settings.enable_portable());
+                        }
+                        ui.label("將設置移到執行檔旁，日誌與快取存放於 data/ 子目錄，不寫入使用者目錄");
+                    });
+                }
+            }
+        });
+        match portable_result {
+            Some(Ok(path)) => self.set_status(&format!(
+                "✅ 已切換為可攜模式，設置檔: {}（日誌於重新啟動後改存於 data/）",
+                path.display()
+            )),
+            Some(Err(e)) => self.set_status(&format!("錯誤: 無法切換為可攜模式: {}", e)),
+            None => {}
+        }
+
+        ui.add_space(20.0);
+
+        // 操作按鈕
+        let mut reset_all_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("💾 儲存設置").clicked() || changed {
+                settings.save();
+                self.set_status("✅ 設置已儲存");
+            }
+
+            if ui.button("🔄 重置為空").clicked() {
+                settings.set_base_path(String::new());
+                settings.save();
+                self.set_status("✅ 已重置路徑");
+            }
+
+            if ui.button("🔄 重置所有設置").clicked() {
+                reset_all_clicked = true;
+            }
+        });
+
+        if reset_all_clicked {
+            self.confirm_reset_all = true;
+        }
+
+        if show_confirm_dialog(
+            ui,
+            "⚠ 重置所有設置",
+            "確定要將所有設置恢復為預設值嗎？所有偏好設置（包括篩選預設）都將遺失。",
+            &mut self.confirm_reset_all,
+        ) {
+            *settings = AppSettings::default();
+            settings.save();
+            self.def_roots = None;
+            self.ignored_types = None;
+            self.scan_mode = None;
+            self.scan_exclusions = None;
+            self.required_tags = None;
+            self.set_status("✅ 已將所有設置恢復為預設值");
+        }
+
+        ui.add_space(10.0);
+        ui.separator();
+        
+        // 顯示設置檔案位置
+        if let Ok(config_path) = AppSettings::config_path() {
+            ui.label(format!("💾 設置檔案: {}", config_path.display()));
+        }
+    }
+
+    fn set_status(&self, message: &str) {
+        if let Ok(mut status) = self.status.lock() {
+            status.set_message("設置", message);
+        }
+    }
+}
+
+/// 每行一個元素名稱，忽略空白行
+fn root_names(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 編輯中的必要標籤列轉為設置；略過沒有類型名稱的列
+fn required_tag_map(rows: &[(String, String)]) -> HashMap<String, Vec<String>> {
+    rows.iter()
+        .filter(|(def_type, _)| !def_type.trim().is_empty())
+        .map(|(def_type, tags)| {
+            let tags = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            (def_type.trim().to_string(), tags)
+        })
+        .collect()
+}
+
+/// 預設內容的簡短說明
+fn quick_filter_summary(preset: &QuickFilter) -> String {
+    let mut parts = Vec::new();
+    if let Some(pattern) = &preset.def_type_pattern {
+        parts.push(format!("類型: {}", pattern));
+    }
+    if let Some(pattern) = &preset.path_pattern {
+        parts.push(format!("路徑: {}", pattern));
+    }
+    if preset.abstract_filter != AbstractFilter::All {
+        parts.push(preset.abstract_filter.label().to_string());
+    }
+    parts.join("，")
+}
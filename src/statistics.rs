@@ -0,0 +1,430 @@
+use eframe::egui;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+use crate::browser::open_file_with_default_app;
+use crate::command_palette::Command;
+use crate::database::{is_excluded, SharedDatabase};
+use crate::export::csv_field;
+use crate::export_preview::Exporter;
+use crate::settings::AppSettings;
+use crate::status::StatusSink;
+use crate::xml_parser::{extract_may_require, extract_tag_values, GatedElement};
+
+/// 官方 DLC 與本體的 packageId
+const LUDEON_PACKAGE_IDS: &[&str] = &[
+    "ludeon.rimworld",
+    "ludeon.rimworld.royalty",
+    "ludeon.rimworld.ideology",
+    "ludeon.rimworld.biotech",
+    "ludeon.rimworld.anomaly",
+    "ludeon.rimworld.odyssey",
+];
+
+/// 狀態列中分析工作的名稱
+const ANALYZE_TASK: &str = "分析 MayRequire";
+
+/// 掃描目錄下找到的模組資訊（來自 About/About.xml）
+#[derive(Debug, Clone)]
+struct ModInfo {
+    name: String,
+    package_id: String,
+    root: PathBuf,
+}
+
+/// MayRequire 分析結果
+pub struct MayRequireReport {
+    elements: Vec<GatedElement>,
+    known_ids: HashSet<String>,
+    xml_count: usize,
+}
+
+/// 統計分頁
+pub struct StatisticsTab {
+    base_directory: String,
+    settings: Arc<Mutex<AppSettings>>,
+    database: SharedDatabase,
+    status: StatusSink,
+    exporter: Exporter,
+    is_analyzing: bool,
+    pending_report: Arc<Mutex<Option<MayRequireReport>>>,
+    report: Option<MayRequireReport>,
+    by_package: BTreeMap<String, usize>,
+    by_mod: BTreeMap<String, usize>,
+    by_def_type: BTreeMap<String, usize>,
+    selected_package: Option<String>, // 明細列表的套件篩選
+}
+
+impl StatisticsTab {
+    pub fn new(
+        settings: Arc<Mutex<AppSettings>>,
+        database: SharedDatabase,
+        status: StatusSink,
+        exporter: Exporter,
+    ) -> Self {
+        Self {
+            base_directory: String::new(),
+            settings,
+            database,
+            status,
+            exporter,
+            is_analyzing: false,
+            pending_report: Arc::new(Mutex::new(None)),
+            report: None,
+            by_package: BTreeMap::new(),
+            by_mod: BTreeMap::new(),
+            by_def_type: BTreeMap::new(),
+            selected_package: None,
+        }
+    }
+
+    /// 在後台執行緒中分析 MayRequire 內容
+    pub fn analyze_may_require(&mut self, ctx: egui::Context) {
+        if self.base_directory.is_empty() || self.is_analyzing {
+            return;
+        }
+
+        self.is_analyzing = true;
+        if let Ok(mut status) = self.status.lock() {
+            status.begin_task(ANALYZE_TASK);
+            status.set_loading("統計", "正在分析 MayRequire...");
+        }
+
+        let base_path = PathBuf::from(&self.base_directory);
+        let exclusions = self.settings.lock().map(|s| s.scan_exclusions.clone()).unwrap_or_default();
+        let pending_report = self.pending_report.clone();
+        let status = self.status.clone();
+
+        std::thread::spawn(move || {
+            let mods = find_mods(&base_path);
+
+            let xml_files: Vec<PathBuf> = WalkDir::new(&base_path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.path().is_file()
+                        && e.path().extension().and_then(|s| s.to_str()) == Some("xml")
+                        && !is_excluded(e.path().strip_prefix(&base_path).unwrap_or(e.path()), &exclusions)
+                })
+                .map(|e| e.path().to_path_buf())
+                .collect();
+
+            let processed = AtomicUsize::new(0);
+            let mut elements: Vec<GatedElement> = xml_files
+                .par_iter()
+                .filter_map(|path| {
+                    let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                    if let Ok(mut status) = status.lock() {
+                        status.set_progress(ANALYZE_TASK, done, xml_files.len());
+                    }
+                    extract_may_require(path).ok()
+                })
+                .flatten()
+                .collect();
+
+            for element in &mut elements {
+                element.mod_name = mod_for_file(&mods, &element.file_path)
+                    .map(|m| m.name.clone())
+                    .unwrap_or_else(|| "(未知模組)".to_string());
+            }
+            elements.sort_by(|a, b| {
+                (&a.mod_name, &a.def_type, &a.def_name).cmp(&(&b.mod_name, &b.def_type, &b.def_name))
+            });
+
+            let mut known_ids: HashSet<String> =
+                LUDEON_PACKAGE_IDS.iter().map(|s| s.to_string()).collect();
+            known_ids.extend(mods.iter().map(|m| m.package_id.to_lowercase()));
+
+            if let Ok(mut status) = status.lock() {
+                status.end_task(ANALYZE_TASK);
+            }
+
+            if let Ok(mut report) = pending_report.lock() {
+                *report = Some(MayRequireReport {
+                    elements,
+                    known_ids,
+                    xml_count: xml_files.len(),
+                });
+            }
+
+            ctx.request_repaint();
+        });
+    }
+
+    fn check_report(&mut self) {
+        let Some(report) = self.pending_report.lock().ok().and_then(|mut r| r.take()) else {
+            return;
+        };
+
+        self.by_package.clear();
+        self.by_mod.clear();
+        self.by_def_type.clear();
+        for element in &report.elements {
+            for id in &element.package_ids {
+                *self.by_package.entry(id.clone()).or_insert(0) += 1;
+            }
+            *self.by_mod.entry(element.mod_name.clone()).or_insert(0) += 1;
+            let def_type = if element.def_type.is_empty() {
+                "(無)".to_string()
+            } else {
+                element.def_type.clone()
+            };
+            *self.by_def_type.entry(def_type).or_insert(0) += 1;
+        }
+
+        let unknown = self
+            .by_package
+            .keys()
+            .filter(|id| !report.known_ids.contains(*id))
+            .count();
+        self.set_status(format!(
+            "分析了 {} 個 XML 檔案，找到 {} 個條件元素，{} 個未知套件 ID",
+            report.xml_count,
+            report.elements.len(),
+            unknown
+        ));
+        self.selected_package = None;
+        self.report = Some(report);
+        self.is_analyzing = false;
+    }
+
+    /// 命令面板中的統計命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![
+            Command::new("分析 MayRequire", |tab: &mut Self, ctx| {
+                tab.analyze_may_require(ctx.clone())
+            }),
+            Command::new("匯出 CSV", |tab: &mut Self, _| tab.export_csv()),
+        ]
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        // 每次更新時檢查設置是否變更
+        if let Ok(settings) = self.settings.lock() {
+            let base_path = settings.active_base_path();
+            if base_path != self.base_directory {
+                self.base_directory = base_path;
+            }
+        }
+
+        self.check_report();
+
+        // 頂部控制面板
+        ui.horizontal(|ui| {
+            ui.label("目錄:");
+            ui.add_enabled(false, egui::TextEdit::singleline(&mut self.base_directory));
+
+            if ui.button("🔄 分析 MayRequire").clicked() {
+                self.analyze_may_require(ctx.clone());
+            }
+
+            if self.report.is_some() && ui.button("💾 匯出 CSV").clicked() {
+                self.export_csv();
+            }
+
+        });
+
+        ui.separator();
+
+        self.skipped_roots_ui(ui);
+
+        let Some(report) = &self.report else {
+            ui.label("點擊「分析 MayRequire」以統計受 DLC / 模組條件限制的內容");
+            return;
+        };
+
+        egui::ScrollArea::vertical()
+            .id_salt("statistics_main")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.collapsing(format!("📦 依套件 ID ({})", self.by_package.len()), |ui| {
+                    egui::Grid::new("may_require_by_package")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (id, count) in &self.by_package {
+                                let is_selected = self.selected_package.as_ref() == Some(id);
+                                if ui.selectable_label(is_selected, id).clicked() {
+                                    self.selected_package = if is_selected { None } else { Some(id.clone()) };
+                                }
+                                ui.label(count.to_string());
+                                if !report.known_ids.contains(id) {
+                                    // 拼錯的套件 ID 會讓內容永遠不被載入
+                                    ui.colored_label(egui::Color32::from_rgb(255, 80, 80), "⚠ 未知套件 ID");
+                                } else {
+                                    ui.label("");
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                ui.collapsing(format!("🧩 依模組 ({})", self.by_mod.len()), |ui| {
+                    egui::Grid::new("may_require_by_mod")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (mod_name, count) in &self.by_mod {
+                                ui.label(mod_name);
+                                ui.label(count.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                ui.collapsing(format!("📚 依 Def 類型 ({})", self.by_def_type.len()), |ui| {
+                    egui::Grid::new("may_require_by_def_type")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (def_type, count) in &self.by_def_type {
+                                ui.label(def_type);
+                                ui.label(count.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                ui.separator();
+
+                // 明細列表
+                ui.horizontal(|ui| {
+                    ui.label("📄 條件元素明細");
+                    if let Some(id) = &self.selected_package {
+                        ui.label(format!("（篩選: {}）", id));
+                        if ui.small_button("✖").clicked() {
+                            self.selected_package = None;
+                        }
+                    }
+                });
+
+                for element in report.elements.iter().filter(|e| {
+                    self.selected_package
+                        .as_ref()
+                        .is_none_or(|id| e.package_ids.contains(id))
+                }) {
+                    ui.horizontal_wrapped(|ui| {
+                        for id in &element.package_ids {
+                            if report.known_ids.contains(id) {
+                                ui.label(id);
+                            } else {
+                                ui.colored_label(egui::Color32::from_rgb(255, 80, 80), format!("⚠ {}", id));
+                            }
+                        }
+                        ui.label("|");
+                        if element.def_name.is_empty() {
+                            ui.label(&element.def_type);
+                        } else {
+                            ui.label(format!("{} / {}", element.def_type, element.def_name));
+                        }
+                        ui.weak(&element.element_path);
+                        if ui
+                            .link(element.file_path.display().to_string())
+                            .clicked()
+                        {
+                            open_file_with_default_app(&element.file_path);
+                        }
+                    });
+                }
+            });
+    }
+
+    /// 上次掃描時 <Defs> 下因 Def 根元素規則而略過的元素，可在設置分頁加入允許清單
+    fn skipped_roots_ui(&self, ui: &mut egui::Ui) {
+        let Ok(db) = self.database.read() else {
+            return;
+        };
+        if db.skipped_roots.is_empty() {
+            return;
+        }
+        ui.collapsing(format!("🚫 略過的根元素 ({})", db.skipped_roots.len()), |ui| {
+            ui.weak("這些元素不以 Def 結尾或已被排除，未列入瀏覽器與繼承分頁；可在設置分頁調整 Def 根元素規則");
+            egui::Grid::new("skipped_def_roots")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, count) in &db.skipped_roots {
+                        ui.label(name);
+                        ui.label(count.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+        ui.separator();
+    }
+
+    fn export_csv(&mut self) {
+        let Some(report) = &self.report else {
+            return;
+        };
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("may_require.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut csv = String::from("package_ids,known,attribute,mod,def_type,def_name,element,file\n");
+        for element in &report.elements {
+            let known = element.package_ids.iter().all(|id| report.known_ids.contains(id));
+            let fields = [
+                element.package_ids.join(";"),
+                known.to_string(),
+                element.attribute.clone(),
+                element.mod_name.clone(),
+                element.def_type.clone(),
+                element.def_name.clone(),
+                element.element_path.clone(),
+                element.file_path.display().to_string(),
+            ];
+            let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            csv.push_str(&line.join(","));
+            csv.push('\n');
+        }
+
+        self.set_status(self.exporter.export("統計", &path, csv.into_bytes()));
+    }
+
+    /// 分析與匯出完成時的通知
+    fn set_status(&self, message: String) {
+        if let Ok(mut status) = self.status.lock() {
+            status.notify("統計", message);
+        }
+    }
+}
+
+/// 找出目錄下所有模組（含 About/About.xml 的資料夾）
+fn find_mods(base_path: &Path) -> Vec<ModInfo> {
+    WalkDir::new(base_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name().eq_ignore_ascii_case("About.xml")
+                && e.path()
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .is_some_and(|n| n.eq_ignore_ascii_case("About"))
+        })
+        .filter_map(|e| {
+            let root = e.path().parent()?.parent()?.to_path_buf();
+            let package_id = extract_tag_values(e.path(), "packageId")
+                .ok()?
+                .into_iter()
+                .next()?;
+            let name = extract_tag_values(e.path(), "name")
+                .ok()
+                .and_then(|v| v.into_iter().next())
+                .unwrap_or_else(|| package_id.clone());
+            Some(ModInfo { name, package_id, root })
+        })
+        .collect()
+}
+
+/// 找出檔案所屬的模組（取最深的根目錄）
+fn mod_for_file<'a>(mods: &'a [ModInfo], file: &Path) -> Option<&'a ModInfo> {
+    mods.iter()
+        .filter(|m| file.starts_with(&m.root))
+        .max_by_key(|m| m.root.components().count())
+}
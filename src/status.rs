@@ -0,0 +1,197 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 背景工作（掃描、搜尋、匯出）
+#[derive(Debug, Clone)]
+pub struct BackgroundTask {
+    pub name: String,
+    pub done: usize,
+    pub total: usize, // 0 表示尚未知道總量
+}
+
+impl BackgroundTask {
+    pub fn fraction(&self) -> Option<f32> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(self.done as f32 / self.total as f32)
+        }
+    }
+}
+
+/// 狀態訊息的種類，決定顯示的顏色與是否自動消失
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Info,
+    Loading,
+    Success,
+    Error,
+    Warning,
+}
+
+impl StatusKind {
+    /// 依訊息內容判斷種類：「錯誤」開頭為錯誤、含 ⚠ 為警告，其他為 `default`
+    pub fn from_message(message: &str, default: StatusKind) -> Self {
+        if message.starts_with("錯誤") {
+            StatusKind::Error
+        } else if message.contains('⚠') {
+            StatusKind::Warning
+        } else {
+            default
+        }
+    }
+
+    /// 錯誤與警告需要手動關閉
+    pub fn is_persistent(self) -> bool {
+        matches!(self, StatusKind::Error | StatusKind::Warning)
+    }
+}
+
+/// Info 與 Success 訊息顯示的時間
+pub const STATUS_DURATION: Duration = Duration::from_secs(5);
+
+/// 狀態列顯示的訊息
+#[derive(Debug, Clone)]
+pub struct StatusMessage {
+    pub kind: StatusKind,
+    pub text: String,
+    pub source: String, // 訊息來源（分頁名稱）
+    pub timestamp: Instant,
+}
+
+impl StatusMessage {
+    pub fn new(kind: StatusKind, source: &str, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            source: source.to_string(),
+            timestamp: Instant::now(),
+        }
+    }
+
+    /// Info 與 Success 訊息在 `STATUS_DURATION` 後自動消失
+    pub fn is_expired(&self) -> bool {
+        matches!(self.kind, StatusKind::Info | StatusKind::Success)
+            && self.timestamp.elapsed() > STATUS_DURATION
+    }
+}
+
+/// 全域狀態列的共享狀態
+#[derive(Default)]
+pub struct StatusState {
+    pub message: Option<StatusMessage>, // 最近一次操作的訊息
+    pub tasks: Vec<BackgroundTask>,     // 執行中的背景工作
+    pub toasts: Vec<Toast>,             // 等待顯示的通知
+}
+
+pub type StatusSink = Arc<Mutex<StatusState>>;
+
+impl StatusState {
+    /// 一般訊息；「錯誤」開頭或含 ⚠ 的訊息視為錯誤或警告
+    pub fn set_message(&mut self, source: &str, message: impl Into<String>) {
+        let message = message.into();
+        let kind = StatusKind::from_message(&message, StatusKind::Info);
+        self.message = Some(StatusMessage::new(kind, source, message));
+    }
+
+    /// 背景工作進行中的訊息，保留到被下一個訊息取代
+    pub fn set_loading(&mut self, source: &str, message: impl Into<String>) {
+        self.message = Some(StatusMessage::new(StatusKind::Loading, source, message));
+    }
+
+    /// 設定訊息並發出通知；用於完成事件，切換到其他分頁時也看得到
+    pub fn notify(&mut self, source: &str, message: impl Into<String>) {
+        let message = message.into();
+        let kind = StatusKind::from_message(&message, StatusKind::Success);
+        self.toasts.push(Toast::new(kind.into(), format!("[{}] {}", source, message)));
+        self.message = Some(StatusMessage::new(kind, source, message));
+    }
+
+    /// 開始一個背景工作；同名的工作會被重新開始
+    pub fn begin_task(&mut self, name: &str) {
+        self.tasks.retain(|t| t.name != name);
+        self.tasks.push(BackgroundTask {
+            name: name.to_string(),
+            done: 0,
+            total: 0,
+        });
+    }
+
+    pub fn set_progress(&mut self, name: &str, done: usize, total: usize) {
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.name == name) {
+            task.done = done;
+            task.total = total;
+        }
+    }
+
+    pub fn end_task(&mut self, name: &str) {
+        self.tasks.retain(|t| t.name != name);
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.tasks.is_empty()
+    }
+}
+
+/// 一般通知顯示的時間
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// 淡出所需的時間（秒）
+const FADE_SECONDS: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Success,
+    Warning,
+    Error,
+}
+
+impl From<StatusKind> for ToastLevel {
+    /// 與狀態列的配色一致；一般與進行中的訊息以成功顯示
+    fn from(kind: StatusKind) -> Self {
+        match kind {
+            StatusKind::Error => ToastLevel::Error,
+            StatusKind::Warning => ToastLevel::Warning,
+            StatusKind::Info | StatusKind::Loading | StatusKind::Success => ToastLevel::Success,
+        }
+    }
+}
+
+/// 背景工作完成時的通知
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+    pub duration: Option<Duration>, // None 表示需要手動關閉
+    pub elapsed: Duration, // 已顯示的時間（滑鼠停留時不累計）
+}
+
+impl Toast {
+    /// 錯誤與警告（例如有檔案無法解析）需要手動關閉，其他通知會自動淡出
+    pub fn new(level: ToastLevel, message: impl Into<String>) -> Self {
+        let duration = match level {
+            ToastLevel::Success => Some(TOAST_DURATION),
+            ToastLevel::Warning | ToastLevel::Error => None,
+        };
+        Self {
+            level,
+            message: message.into(),
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn opacity(&self) -> f32 {
+        match self.duration {
+            Some(duration) => {
+                let remaining = duration.saturating_sub(self.elapsed).as_secs_f32();
+                (remaining / FADE_SECONDS).clamp(0.0, 1.0)
+            }
+            None => 1.0,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.duration.is_some_and(|d| self.elapsed >= d)
+    }
+}
@@ -0,0 +1,217 @@
+use eframe::egui;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// 解碼後的最大邊長，較大的圖片會先縮小，避免快取塞滿原尺寸貼圖
+const MAX_DIMENSION: u32 = 256;
+
+/// 背景解碼工作
+type LoadJob = Box<dyn FnOnce() -> Result<egui::ColorImage, String> + Send>;
+
+/// 解碼結果（鍵、圖片或錯誤訊息）
+type LoadResult = (String, Result<egui::ColorImage, String>);
+
+/// 圖片的載入狀態
+#[derive(Clone)]
+pub enum TextureState {
+    Loading,
+    Ready(egui::TextureHandle),
+    Failed(String),
+}
+
+/// 工作執行緒共用的佇列
+#[derive(Default)]
+struct JobQueue {
+    jobs: Mutex<VecDeque<(String, LoadJob)>>,
+    available: Condvar,
+}
+
+struct CachedTexture {
+    state: TextureState,
+    bytes: usize,   // 已載入貼圖佔用的記憶體
+    last_used: u64, // 最後一次被要求的幀
+}
+
+struct LoaderState {
+    queue: Arc<JobQueue>,
+    results: Receiver<LoadResult>,
+    cache: HashMap<String, CachedTexture>,
+    pending: HashMap<String, u64>, // 排隊或解碼中的鍵 -> 最後一次被要求的幀
+    frame: u64,
+    total_bytes: usize,
+}
+
+/// 非同步圖片載入器：解碼在背景執行緒進行，UI 先顯示載入中的轉圈，
+/// 已載入的貼圖以 LRU 方式保留在記憶體預算內；各分頁共用同一個載入器
+#[derive(Clone)]
+pub struct TextureLoader(Arc<Mutex<LoaderState>>);
+
+impl Default for TextureLoader {
+    fn default() -> Self {
+        let queue = Arc::new(JobQueue::default());
+        let (sender, results) = channel();
+        let workers = std::thread::available_parallelism().map_or(2, |n| n.get().clamp(1, 4));
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let sender = sender.clone();
+            std::thread::spawn(move || worker(&queue, &sender));
+        }
+
+        Self(Arc::new(Mutex::new(LoaderState {
+            queue,
+            results,
+            cache: HashMap::new(),
+            pending: HashMap::new(),
+            frame: 0,
+            total_bytes: 0,
+        })))
+    }
+}
+
+fn worker(queue: &JobQueue, sender: &Sender<LoadResult>) {
+    loop {
+        let (key, job) = {
+            let Ok(mut jobs) = queue.jobs.lock() else {
+                return;
+            };
+            loop {
+                if let Some(job) = jobs.pop_front() {
+                    break job;
+                }
+                jobs = match queue.available.wait(jobs) {
+                    Ok(jobs) => jobs,
+                    Err(_) => return,
+                };
+            }
+        };
+        if sender.send((key, job())).is_err() {
+            return;
+        }
+    }
+}
+
+impl TextureLoader {
+    /// 每幀開始時呼叫：接收解碼完成的圖片、取消上一幀沒有再要求的工作（已捲出畫面），
+    /// 並在超過預算時釋放最久未使用的貼圖
+    pub fn begin_frame(&self, ctx: &egui::Context, budget_mb: usize) {
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        let state = &mut *state;
+        state.frame += 1;
+
+        while let Ok((key, result)) = state.results.try_recv() {
+            let Some(last_used) = state.pending.remove(&key) else {
+                continue; // 已取消
+            };
+            let (texture, bytes) = match result {
+                Ok(image) => {
+                    let bytes = image.pixels.len() * 4;
+                    let handle = ctx.load_texture(&key, image, egui::TextureOptions::LINEAR);
+                    (TextureState::Ready(handle), bytes)
+                }
+                Err(e) => (TextureState::Failed(e), 0),
+            };
+            state.total_bytes += bytes;
+            state.cache.insert(key, CachedTexture { state: texture, bytes, last_used });
+        }
+
+        // 上一幀沒有再要求的工作不再需要
+        let current = state.frame;
+        let stale: HashSet<String> = state
+            .pending
+            .iter()
+            .filter(|(_, &last)| last + 1 < current)
+            .map(|(key, _)| key.clone())
+            .collect();
+        if !stale.is_empty() {
+            if let Ok(mut jobs) = state.queue.jobs.lock() {
+                jobs.retain(|(key, _)| !stale.contains(key));
+            }
+            state.pending.retain(|key, _| !stale.contains(key));
+        }
+
+        // 依最後使用時間釋放，正在顯示的貼圖不會被釋放
+        let budget = budget_mb.saturating_mul(1024 * 1024);
+        while state.total_bytes > budget {
+            let Some(oldest) = state
+                .cache
+                .iter()
+                .filter(|(_, t)| t.bytes > 0 && t.last_used + 1 < current)
+                .min_by_key(|(_, t)| t.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(texture) = state.cache.remove(&oldest) {
+                state.total_bytes -= texture.bytes;
+            }
+        }
+    }
+
+    /// 取得圖片；尚未載入時以 `load` 排入背景解碼（`load` 只在需要時才呼叫）
+    pub fn get(
+        &self,
+        key: &str,
+        load: impl FnOnce() -> Result<egui::ColorImage, String> + Send + 'static,
+    ) -> TextureState {
+        let Ok(mut state) = self.0.lock() else {
+            return TextureState::Loading;
+        };
+        let frame = state.frame;
+
+        if let Some(texture) = state.cache.get_mut(key) {
+            texture.last_used = frame;
+            return texture.state.clone();
+        }
+        if let Some(last) = state.pending.get_mut(key) {
+            *last = frame;
+            return TextureState::Loading;
+        }
+
+        state.pending.insert(key.to_string(), frame);
+        if let Ok(mut jobs) = state.queue.jobs.lock() {
+            jobs.push_back((key.to_string(), Box::new(load)));
+            state.queue.available.notify_one();
+        }
+        TextureState::Loading
+    }
+}
+
+/// 解碼 PNG 等圖片（貼圖或模組的 Preview.png），過大時縮小到 `MAX_DIMENSION`
+pub fn decode_image(path: &Path) -> Result<egui::ColorImage, String> {
+    let image = image::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let image = if image.width() > MAX_DIMENSION || image.height() > MAX_DIMENSION {
+        image.thumbnail(MAX_DIMENSION, MAX_DIMENSION)
+    } else {
+        image
+    };
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()))
+}
+
+/// 在指定區域繪製圖片：載入中顯示轉圈，失敗時顯示錯誤圖示
+pub fn paint_texture(ui: &egui::Ui, rect: egui::Rect, texture: &TextureState) {
+    match texture {
+        TextureState::Loading => egui::Spinner::new().paint_at(ui, rect),
+        TextureState::Ready(handle) => {
+            // 保持長寬比置中
+            let size = handle.size_vec2();
+            let scale = (rect.width() / size.x).min(rect.height() / size.y);
+            let fitted = egui::Rect::from_center_size(rect.center(), size * scale);
+            egui::Image::new(handle).paint_at(ui, fitted);
+        }
+        TextureState::Failed(_) => {
+            ui.painter().text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "✖",
+                egui::FontId::proportional(rect.height() * 0.8),
+                ui.visuals().weak_text_color(),
+            );
+        }
+    }
+}
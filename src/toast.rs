@@ -0,0 +1,65 @@
+use eframe::egui;
+use std::time::Duration;
+
+use crate::status::{Toast, ToastLevel};
+
+/// 通知等級的顏色，與狀態列的配色一致
+fn level_color(level: ToastLevel) -> egui::Color32 {
+    match level {
+        ToastLevel::Success => egui::Color32::from_rgb(0, 200, 0),
+        ToastLevel::Warning => egui::Color32::from_rgb(255, 200, 0),
+        ToastLevel::Error => egui::Color32::from_rgb(255, 80, 80),
+    }
+}
+
+/// 在右下角堆疊顯示通知；點擊關閉，滑鼠停留時暫停計時
+pub fn show_toasts(ctx: &egui::Context, toasts: &mut Vec<Toast>) {
+    if toasts.is_empty() {
+        return;
+    }
+
+    let dt = Duration::from_secs_f32(ctx.input(|i| i.stable_dt).min(0.1));
+    let mut dismissed = None;
+
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -40.0))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            ui.set_max_width(320.0);
+            for (index, toast) in toasts.iter_mut().enumerate() {
+                let opacity = toast.opacity();
+                let response = egui::Frame::popup(ui.style())
+                    .multiply_with_opacity(opacity)
+                    .show(ui, |ui| {
+                        ui.set_opacity(opacity);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(level_color(toast.level), "●");
+                            ui.label(&toast.message);
+                            if toast.duration.is_none() {
+                                ui.weak("✖");
+                            }
+                        });
+                    })
+                    .response
+                    .interact(egui::Sense::click())
+                    .on_hover_text("點擊關閉");
+
+                if response.clicked() {
+                    dismissed = Some(index);
+                }
+                if !response.hovered() {
+                    toast.elapsed += dt;
+                }
+            }
+        });
+
+    if let Some(index) = dismissed {
+        toasts.remove(index);
+    }
+    toasts.retain(|t| !t.is_expired());
+
+    // 淡出動畫需要持續重繪
+    if toasts.iter().any(|t| t.duration.is_some()) {
+        ctx.request_repaint();
+    }
+}
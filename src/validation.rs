@@ -0,0 +1,136 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::defs::DefEntry;
+use crate::intern::Symbol;
+use crate::merge::DefData;
+
+/// 驗證問題的種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationKind {
+    VersionMismatch,    // 檔案的版本註解與目標 RimWorld 版本不符
+    MissingRequiredTag, // 非抽象的 Def 缺少設置中指定的必要標籤
+}
+
+impl ValidationKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ValidationKind::VersionMismatch => "版本不符",
+            ValidationKind::MissingRequiredTag => "缺少必要標籤",
+        }
+    }
+}
+
+/// 驗證時發現的問題
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub kind: ValidationKind,
+    pub file: PathBuf,
+    pub def_name: Option<String>, // 針對整個檔案的問題為 None
+    pub message: String,
+}
+
+/// 檢查掃描結果，依檔案路徑排序回傳所有問題
+pub fn validate(all_defs: &HashMap<Symbol, DefData>, rimworld_version: &str) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    // 版本註解屬於整個檔案，每個檔案只回報一次
+    let target = major_minor(rimworld_version);
+    if !target.is_empty() {
+        let file_versions: BTreeMap<&PathBuf, &str> = all_defs
+            .values()
+            .filter_map(|def| Some((&def.file_path, def.file_version.as_deref()?)))
+            .collect();
+        for (file, version) in file_versions {
+            if major_minor(version) != target {
+                issues.push(ValidationIssue {
+                    kind: ValidationKind::VersionMismatch,
+                    file: file.clone(),
+                    def_name: None,
+                    message: format!("檔案標示為 RimWorld {}，目前目標版本為 {}", version, rimworld_version),
+                });
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| a.file.cmp(&b.file));
+    issues
+}
+
+/// 檢查非抽象的 Def 是否有設置中該類型的所有必要標籤（Def 根元素的直接子元素），每個 Def 回報一次
+///
+/// 父類提供的標籤也算，例如手術的 `jobString` 通常寫在抽象父類中
+pub fn check_required_tags(
+    entries: &BTreeMap<Symbol, Vec<DefEntry>>,
+    all_defs: &HashMap<Symbol, DefData>,
+    required_tags: &HashMap<String, Vec<String>>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for (def_type, entries) in entries {
+        let Some(required) = required_tags.get(def_type.as_str()).filter(|tags| !tags.is_empty()) else {
+            continue;
+        };
+        for entry in entries.iter().filter(|e| !e.is_abstract) {
+            let mut present = root_child_tags(&entry.xml_content);
+            let mut parent = all_defs
+                .get(&entry.def_name)
+                .filter(|d| d.file_path == entry.file_path)
+                .and_then(|d| d.parent_name.as_ref());
+            let mut visited = HashSet::new();
+            while let Some(parent_def) = parent.and_then(|name| all_defs.get(name)) {
+                if !visited.insert(&parent_def.def_name) {
+                    break;
+                }
+                present.extend(parent_def.raw_nodes.iter().map(|node| node.tag.to_string()));
+                parent = parent_def.parent_name.as_ref();
+            }
+
+            let missing: Vec<&str> = required
+                .iter()
+                .map(|tag| tag.trim())
+                .filter(|tag| !tag.is_empty() && !present.contains(*tag))
+                .collect();
+            if !missing.is_empty() {
+                issues.push(ValidationIssue {
+                    kind: ValidationKind::MissingRequiredTag,
+                    file: entry.file_path.clone(),
+                    def_name: Some(entry.def_name.to_string()),
+                    message: format!("{} 缺少必要標籤: {}", def_type, missing.join(", ")),
+                });
+            }
+        }
+    }
+    issues.sort_by(|a, b| a.file.cmp(&b.file));
+    issues
+}
+
+/// Def XML 根元素的直接子元素名稱
+fn root_child_tags(xml: &str) -> HashSet<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut tags = HashSet::new();
+    let mut depth = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                if depth == 1 {
+                    tags.insert(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                }
+                depth += 1;
+            }
+            Ok(Event::Empty(e)) if depth == 1 => {
+                tags.insert(String::from_utf8_lossy(e.name().as_ref()).to_string());
+            }
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    tags
+}
+
+/// 只比較主版本與次版本（1.4.3580 視為 1.4）
+fn major_minor(version: &str) -> String {
+    version.trim().split('.').take(2).collect::<Vec<_>>().join(".")
+}
@@ -0,0 +1,120 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::browser::open_file_with_default_app;
+use crate::command_palette::Command;
+use crate::database::SharedDatabase;
+use crate::scanner::ScanRequest;
+use crate::settings::AppSettings;
+use crate::validation::{check_required_tags, validate, ValidationIssue};
+use crate::widgets::empty_state;
+
+/// 驗證分頁：掃描完成或目標版本變更時重新檢查
+pub struct ValidatorTab {
+    database: SharedDatabase,
+    settings: Arc<Mutex<AppSettings>>,
+    scan_request: ScanRequest,
+    last_generation: u64,     // 上次檢查時的資料庫版本
+    checked_version: String,  // 上次檢查時的目標 RimWorld 版本
+    checked_required: HashMap<String, Vec<String>>, // 上次檢查時的必要標籤
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidatorTab {
+    pub fn new(
+        settings: Arc<Mutex<AppSettings>>,
+        database: SharedDatabase,
+        scan_request: ScanRequest,
+    ) -> Self {
+        Self {
+            database,
+            settings,
+            scan_request,
+            last_generation: 0,
+            checked_version: String::new(),
+            checked_required: HashMap::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// 請求掃描協調器重新掃描，完成後會自動重新檢查
+    pub fn rescan(&mut self) {
+        self.scan_request.request();
+    }
+
+    /// 命令面板中的驗證命令
+    pub fn commands() -> Vec<Command<Self>> {
+        vec![Command::new("重新掃描並檢查", |tab: &mut Self, _| tab.rescan())]
+    }
+
+    fn refresh(&mut self) {
+        let (version, required) = self
+            .settings
+            .lock()
+            .map(|s| (s.rimworld_version.clone(), s.required_tags.clone()))
+            .unwrap_or_default();
+        let Ok(db) = self.database.read() else {
+            return;
+        };
+        if db.generation == self.last_generation
+            && version == self.checked_version
+            && required == self.checked_required
+        {
+            return;
+        }
+
+        let mut issues = validate(&db.data, &version);
+        issues.extend(check_required_tags(&db.entries, &db.data, &required));
+        issues.sort_by(|a, b| a.file.cmp(&b.file));
+        self.issues = issues;
+        self.last_generation = db.generation;
+        self.checked_version = version;
+        self.checked_required = required;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context) {
+        self.refresh();
+
+        ui.horizontal(|ui| {
+            if ui.button("🔄 掃描 Defs").clicked() {
+                self.rescan();
+            }
+            ui.label(format!("目標 RimWorld 版本: {}", self.checked_version));
+            ui.weak("（可在設置分頁中變更）");
+        });
+
+        ui.separator();
+
+        if self.last_generation == 0 {
+            let refreshing = self.database.read().is_ok_and(|db| db.refreshing);
+            empty_state(ui, &self.settings, refreshing);
+            return;
+        }
+        if self.issues.is_empty() {
+            ui.label("✅ 沒有發現問題");
+            return;
+        }
+
+        ui.label(format!("⚠ 發現 {} 個問題", self.issues.len()));
+
+        egui::ScrollArea::vertical()
+            .id_salt("validation_issues")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::Grid::new("validation_issue_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for issue in &self.issues {
+                            ui.colored_label(egui::Color32::from_rgb(255, 200, 0), issue.kind.label());
+                            ui.label(issue.def_name.as_deref().unwrap_or("—"));
+                            ui.label(&issue.message);
+                            if ui.link(issue.file.display().to_string()).clicked() {
+                                open_file_with_default_app(&issue.file);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,422 @@
+use eframe::egui::{self, Ui};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::search::find_all;
+use crate::settings::AppSettings;
+use crate::status::{StatusKind, StatusMessage, STATUS_DURATION};
+
+/// 進行中的訊息剛出現時以藍色閃爍提示的時間
+const LOADING_FLASH: Duration = Duration::from_millis(600);
+
+/// 行號欄左右的留白
+const GUTTER_PADDING: f32 = 6.0;
+
+/// 尋找結果的底色：所有相符處與目前的相符處
+const MATCH_BACKGROUND: egui::Color32 = egui::Color32::from_rgba_premultiplied(90, 80, 0, 90);
+const CURRENT_MATCH_BACKGROUND: egui::Color32 = egui::Color32::from_rgba_premultiplied(200, 110, 0, 200);
+
+/// 載入骨架一次明暗變化的秒數
+const SKELETON_PULSE_SECONDS: f32 = 1.0;
+
+/// 顯示置中的確認對話框；按下「確認」時回傳 true，按下任一按鈕都會關閉對話框
+pub fn show_confirm_dialog(ui: &mut Ui, title: &str, message: &str, shown: &mut bool) -> bool {
+    if !*shown {
+        return false;
+    }
+
+    let mut confirmed = false;
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ui.ctx(), |ui| {
+            ui.label(message);
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button("確認").clicked() {
+                    confirmed = true;
+                    *shown = false;
+                }
+                if ui.button("取消").clicked() {
+                    *shown = false;
+                }
+            });
+        });
+    confirmed
+}
+
+/// 分頁尚無資料時的置中提示（尚未設定目錄、掃描中、尚未掃描）
+///
+/// 尚未設定目錄時可直接選擇目錄（設置變更後掃描協調器會自動掃描），或前往設置分頁
+pub fn empty_state(ui: &mut Ui, settings: &Arc<Mutex<AppSettings>>, scanning: bool) {
+    let base_directory = settings.lock().map(|s| s.active_base_path()).unwrap_or_default();
+    ui.vertical_centered(|ui| {
+        ui.add_space(ui.available_height() * 0.25);
+        if base_directory.is_empty() {
+            egui::Frame::group(ui.style())
+                .inner_margin(egui::Margin::same(24.0))
+                .rounding(8.0)
+                .show(ui, |ui| {
+                    ui.set_max_width(360.0);
+                    ui.vertical_centered(|ui| {
+                        ui.label(egui::RichText::new("🗂").size(48.0));
+                        ui.heading("尚未載入任何 Defs");
+                        ui.add_space(4.0);
+                        ui.label("選擇 RimWorld 的 Data 資料夾即可開始，掃描器會自動找出所有 XML Def 檔案。");
+                        ui.add_space(12.0);
+                        let button = egui::Button::new(egui::RichText::new("📂 選擇目錄").size(16.0))
+                            .min_size(egui::vec2(160.0, 32.0));
+                        if ui.add(button).clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                if let Ok(mut settings) = settings.lock() {
+                                    settings.set_base_path(path.display().to_string());
+                                    settings.save();
+                                }
+                            }
+                        }
+                        ui.add_space(4.0);
+                        if ui.link("或在設置中設定路徑").clicked() {
+                            request_open_settings(ui.ctx());
+                        }
+                    });
+                });
+        } else if scanning {
+            ui.add(egui::Spinner::new().size(32.0));
+            ui.weak("正在掃描 Defs…");
+        } else {
+            ui.heading("尚未掃描 — 按 🔄 開始");
+            ui.weak("或按 F5 掃描目前的工作目錄");
+        }
+    });
+}
+
+/// 第一次掃描時代替列表顯示的灰色佔位列，明暗交替表示內容即將出現
+///
+/// 左側為類型列表、右側為詳細資訊，`list_width` 與實際列表的寬度相同
+pub fn loading_skeleton(ui: &mut Ui, list_width: f32) {
+    let id = ui.id().with("loading_skeleton");
+    let bright = ui.ctx().data(|d| d.get_temp::<bool>(id)).unwrap_or(true);
+    let target = if bright { 1.0 } else { 0.35 };
+    let opacity = ui.ctx().animate_value_with_time(id, target, SKELETON_PULSE_SECONDS);
+    if (opacity - target).abs() < 0.01 {
+        ui.ctx().data_mut(|d| d.insert_temp(id, !bright));
+    }
+    ui.ctx().request_repaint();
+
+    let row_height = ui.spacing().interact_size.y;
+    let bar = |ui: &mut Ui, width: f32, height: f32| {
+        egui::Frame::none()
+            .fill(egui::Color32::from_gray(50).gamma_multiply(opacity))
+            .rounding(3.0)
+            .show(ui, |ui| {
+                ui.set_min_size(egui::vec2(width, height));
+                ui.set_max_size(egui::vec2(width, height));
+            });
+    };
+
+    ui.horizontal_top(|ui| {
+        ui.vertical(|ui| {
+            ui.set_width(list_width);
+            bar(ui, list_width, row_height);
+            ui.add_space(8.0);
+            // 長度不一的列看起來較像真實的類型名稱
+            for i in 0..12 {
+                bar(ui, list_width * (0.45 + 0.08 * (i * 7 % 6) as f32), row_height * 0.8);
+            }
+        });
+        ui.separator();
+        ui.vertical(|ui| {
+            let width = ui.available_width();
+            bar(ui, width * 0.4, row_height * 1.2);
+            ui.add_space(8.0);
+            for i in 0..4 {
+                bar(ui, width * (0.5 + 0.1 * (i % 3) as f32), row_height * 0.8);
+            }
+            ui.add_space(8.0);
+            bar(ui, width, row_height * 8.0);
+        });
+    });
+}
+
+/// 分頁要求切換到設置分頁（由主程式在同一幀取出）
+fn open_settings_id() -> egui::Id {
+    egui::Id::new("request_open_settings")
+}
+
+pub fn request_open_settings(ctx: &egui::Context) {
+    ctx.data_mut(|d| d.insert_temp(open_settings_id(), true));
+}
+
+pub fn take_open_settings_request(ctx: &egui::Context) -> bool {
+    ctx.data_mut(|d| d.remove_temp::<bool>(open_settings_id())).unwrap_or(false)
+}
+
+/// 唯讀的程式碼檢視，左側顯示行號
+///
+/// 自動換行時一個邏輯行可能排成多個視覺行：先排版內容，再依排版結果的每一列繪製行號，
+/// 換行接續的列不顯示行號，行號才不會與內容錯位
+pub struct LineNumberedCode<'a> {
+    text: &'a str,
+    word_wrap: bool,
+    links: &'a [Range<usize>],
+    highlights: &'a [Range<usize>],
+    current: Option<usize>,
+    scroll_to_current: bool,
+}
+
+impl<'a> LineNumberedCode<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            word_wrap: true,
+            links: &[],
+            highlights: &[],
+            current: None,
+            scroll_to_current: false,
+        }
+    }
+
+    pub fn word_wrap(mut self, word_wrap: bool) -> Self {
+        self.word_wrap = word_wrap;
+        self
+    }
+
+    /// 以超連結樣式（底線）顯示的位元組範圍，需依順序且不重疊
+    pub fn links(mut self, links: &'a [Range<usize>]) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// 以底色標示的位元組範圍（尋找結果），需依順序且不重疊；`current` 為其中以較深底色顯示的索引
+    pub fn highlights(mut self, highlights: &'a [Range<usize>], current: Option<usize>) -> Self {
+        self.highlights = highlights;
+        self.current = current;
+        self
+    }
+
+    /// 顯示後將外層的 `ScrollArea` 捲動到目前的標示範圍
+    pub fn scroll_to_current(mut self, scroll: bool) -> Self {
+        self.scroll_to_current = scroll;
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) -> egui::text_edit::TextEditOutput {
+        let font = egui::TextStyle::Monospace.resolve(ui.style());
+        let digits = (self.text.matches('\n').count() + 1).to_string().len();
+        let gutter_width = ui.fonts(|f| f.glyph_width(&font, '0')) * digits as f32 + GUTTER_PADDING * 2.0;
+
+        let word_wrap = self.word_wrap;
+        let links = self.links;
+        let highlights = self.highlights;
+        let current = self.current;
+        let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+            let format = egui::TextFormat::simple(
+                egui::TextStyle::Monospace.resolve(ui.style()),
+                ui.visuals().text_color(),
+            );
+            let link_color = ui.visuals().hyperlink_color;
+            let link_format = egui::TextFormat {
+                color: link_color,
+                underline: egui::Stroke::new(1.0, link_color),
+                ..format.clone()
+            };
+            // 連結與標示範圍可能重疊，依所有範圍的邊界切段後分別決定樣式
+            let mut bounds: Vec<usize> = links
+                .iter()
+                .chain(highlights)
+                .filter(|range| range.end <= text.len())
+                .flat_map(|range| [range.start, range.end])
+                .chain([0, text.len()])
+                .collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+            let mut job = egui::text::LayoutJob::default();
+            for segment in bounds.windows(2) {
+                let (start, end) = (segment[0], segment[1]);
+                let mut segment_format = match covering(links, start, end) {
+                    Some(_) => link_format.clone(),
+                    None => format.clone(),
+                };
+                if let Some(index) = covering(highlights, start, end) {
+                    segment_format.background = if Some(index) == current {
+                        CURRENT_MATCH_BACKGROUND
+                    } else {
+                        MATCH_BACKGROUND
+                    };
+                }
+                job.append(&text[start..end], 0.0, segment_format);
+            }
+            job.wrap.max_width = if word_wrap { wrap_width } else { f32::INFINITY };
+            ui.fonts(|f| f.layout_job(job))
+        };
+
+        let output = ui.horizontal_top(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            // 第一輪：保留行號欄的位置，排版並顯示內容
+            let (gutter, _) = ui.allocate_exact_size(egui::vec2(gutter_width, 0.0), egui::Sense::hover());
+            let output = egui::TextEdit::multiline(&mut { self.text })
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter)
+                .show(ui);
+
+            // 第二輪：只在開始新邏輯行的列繪製行號
+            let painter = ui.painter();
+            let color = ui.visuals().weak_text_color();
+            let mut line = 0;
+            let mut starts_line = true;
+            for row in &output.galley.rows {
+                if starts_line {
+                    line += 1;
+                    painter.text(
+                        egui::pos2(gutter.right() - GUTTER_PADDING, output.galley_pos.y + row.rect.top()),
+                        egui::Align2::RIGHT_TOP,
+                        line.to_string(),
+                        font.clone(),
+                        color,
+                    );
+                }
+                starts_line = row.ends_with_newline;
+            }
+            output
+        })
+        .inner;
+
+        if self.scroll_to_current {
+            if let Some(range) = self.current.and_then(|i| self.highlights.get(i)) {
+                let index = self.text[..range.start].chars().count();
+                let rect = output
+                    .galley
+                    .pos_from_ccursor(egui::text::CCursor::new(index))
+                    .translate(output.galley_pos.to_vec2());
+                ui.scroll_to_rect(rect, Some(egui::Align::Center));
+            }
+        }
+        output
+    }
+}
+
+/// 包含 `start..end` 整段的範圍索引；`ranges` 需依順序且不重疊
+fn covering(ranges: &[Range<usize>], start: usize, end: usize) -> Option<usize> {
+    let index = ranges.partition_point(|range| range.end <= start);
+    ranges
+        .get(index)
+        .filter(|range| range.start <= start && end <= range.end)
+        .map(|_| index)
+}
+
+/// 程式碼檢視上方的尋找列：不分大小寫標示所有相符處，顯示「n / m」並可跳到上一個／下一個
+#[derive(Default)]
+pub struct FindBar {
+    query: String,
+    matches: Vec<Range<usize>>,
+    current: usize,
+    scroll: bool, // 下次顯示內容時捲動到目前的相符處
+}
+
+impl FindBar {
+    /// 顯示尋找列並重新比對 `text`；內容或查詢改變時回到第一個相符處
+    pub fn ui(&mut self, ui: &mut Ui, text: &str) {
+        let matches = find_all(text, &self.query);
+        if matches != self.matches {
+            self.matches = matches;
+            self.current = 0;
+            self.scroll = !self.matches.is_empty();
+        }
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("🔍 在 XML 中尋找")
+                    .desired_width(180.0),
+            );
+            // Enter 跳到下一個、Shift+Enter 跳到上一個，並保留輸入焦點
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let shift = ui.input(|i| i.modifiers.shift);
+            if submitted {
+                response.request_focus();
+            }
+
+            let found = !self.matches.is_empty();
+            if found {
+                ui.label(format!("{} / {}", self.current + 1, self.matches.len()));
+            } else if !self.query.is_empty() {
+                ui.weak("沒有相符");
+            }
+            let previous = ui.add_enabled(found, egui::Button::new("⬆").small()).on_hover_text("上一個（Shift+Enter）");
+            let next = ui.add_enabled(found, egui::Button::new("⬇").small()).on_hover_text("下一個（Enter）");
+            if found && (previous.clicked() || (submitted && shift)) {
+                self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+                self.scroll = true;
+            }
+            if found && (next.clicked() || (submitted && !shift)) {
+                self.current = (self.current + 1) % self.matches.len();
+                self.scroll = true;
+            }
+        });
+    }
+
+    /// 加上相符處的標示；有待處理的跳轉時一併捲動
+    pub fn apply<'a>(&'a mut self, code: LineNumberedCode<'a>) -> LineNumberedCode<'a> {
+        let scroll = std::mem::take(&mut self.scroll);
+        code.highlights(&self.matches, Some(self.current)).scroll_to_current(scroll)
+    }
+}
+
+/// 模糊搜尋結果的標籤：相符的字元（字元索引）以強調樣式顯示
+pub fn highlighted_text(ui: &Ui, text: &str, positions: &[usize]) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let mut run = String::new();
+    let mut run_matched = false;
+    let mut flush = |run: &mut String, matched: bool| {
+        if run.is_empty() {
+            return;
+        }
+        let text = egui::RichText::new(std::mem::take(run));
+        let text = if matched { text.strong() } else { text };
+        text.append_to(&mut job, ui.style(), egui::FontSelection::Default, egui::Align::Center);
+    };
+    for (index, c) in text.chars().enumerate() {
+        let matched = positions.contains(&index);
+        if matched != run_matched {
+            flush(&mut run, run_matched);
+            run_matched = matched;
+        }
+        run.push(c);
+    }
+    flush(&mut run, run_matched);
+    job
+}
+
+/// 狀態列的訊息：顏色依種類決定，Info 與 Success 數秒後自動消失，錯誤與警告需按 ✕ 關閉
+pub fn show_status(ui: &mut Ui, status: &mut Option<StatusMessage>) {
+    if status.as_ref().is_some_and(|m| m.is_expired()) {
+        *status = None;
+    }
+    let Some(message) = status else {
+        return;
+    };
+
+    let age = message.timestamp.elapsed();
+    let color = match message.kind {
+        StatusKind::Loading if age < LOADING_FLASH => egui::Color32::from_rgb(100, 180, 255),
+        StatusKind::Loading => egui::Color32::from_rgb(255, 165, 0),
+        StatusKind::Info => ui.visuals().text_color(),
+        StatusKind::Success => egui::Color32::from_rgb(0, 200, 0),
+        StatusKind::Warning => egui::Color32::from_rgb(255, 200, 0),
+        StatusKind::Error => egui::Color32::from_rgb(255, 80, 80),
+    };
+    ui.colored_label(color, format!("[{}] {}", message.source, message.text));
+
+    if message.kind.is_persistent() {
+        if ui.small_button("✕").on_hover_text("關閉訊息").clicked() {
+            *status = None;
+        }
+    } else if message.kind == StatusKind::Loading && age < LOADING_FLASH {
+        ui.ctx().request_repaint_after(LOADING_FLASH - age);
+    } else if message.kind != StatusKind::Loading {
+        ui.ctx().request_repaint_after(STATUS_DURATION.saturating_sub(age));
+    }
+}
@@ -1,13 +1,209 @@
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::intern::Symbol;
+
+/// 解析錯誤的種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnsupportedEncoding, // 宣告了 UTF-8 / US-ASCII 以外的編碼
+    InvalidUtf8,         // 內容不是有效的 UTF-8
+    Syntax,              // XML 語法錯誤；錯誤之前的內容仍會被使用
+    Panic,               // 解析時發生 panic
+}
+
+/// 掃描時無法（完整）解析的檔案
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub file: PathBuf,
+    pub message: String,
+    pub line: Option<usize>, // 錯誤的大約行號（從 1 開始）
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file.display(), self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 掃描期間收集的解析錯誤
+#[derive(Debug, Clone, Default)]
+pub struct ErrorLog {
+    pub errors: Vec<ParseError>,
+}
+
+impl ErrorLog {
+    pub fn push(&mut self, error: ParseError) {
+        self.errors.push(error);
+    }
+
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// 檢查檔案開頭的 XML 宣告，回傳不支援的編碼名稱
+///
+/// 沒有宣告編碼時視為 UTF-8
+pub fn unsupported_encoding(bytes: &[u8]) -> Option<String> {
+    // UTF-16 的 BOM
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some("UTF-16".to_string());
+    }
+
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]);
+    let head = head.trim_start();
+    if !head.starts_with("<?xml") {
+        return None;
+    }
+    let declaration = &head[..head.find("?>")?];
+    let start = declaration.find("encoding=")? + "encoding=".len();
+    let rest = &declaration[start..];
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = rest[1..].split(quote).next()?.trim();
+
+    match value.to_ascii_uppercase().as_str() {
+        "UTF-8" | "UTF8" | "US-ASCII" | "ASCII" => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+/// XML 語法錯誤的位置（行、欄從 1 開始）
+#[derive(Debug, Clone)]
+pub struct XmlSyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// `offset`（位元組位置）所在的文字節點內容（去除前後空白），位於標籤內時為 None
+pub fn text_value_at(xml: &str, offset: usize) -> Option<&str> {
+    if offset >= xml.len() || !xml.is_char_boundary(offset) {
+        return None;
+    }
+    let start = xml[..offset].rfind(['<', '>'])?;
+    if xml.as_bytes()[start] != b'>' {
+        return None;
+    }
+    let end = offset + xml[offset..].find('<')?;
+    let value = xml[start + 1..end].trim();
+    (!value.is_empty()).then_some(value)
+}
+
+/// 所有文字節點內容（去除前後空白）的位元組範圍，依出現順序；規則與 `text_value_at` 相同
+pub fn text_value_ranges(xml: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut rest = 0;
+    while let Some(gt) = xml[rest..].find('>') {
+        let start = rest + gt + 1;
+        let Some(lt) = xml[start..].find('<') else {
+            break;
+        };
+        let end = start + lt;
+        let raw = &xml[start..end];
+        let value_start = start + (raw.len() - raw.trim_start().len());
+        let value_end = start + raw.trim_end().len();
+        if value_start < value_end {
+            ranges.push(value_start..value_end);
+        }
+        rest = end;
+    }
+    ranges
+}
+
+/// 完整讀過一次 XML，回傳第一個語法錯誤
+pub fn find_syntax_error(content: &str) -> Option<XmlSyntaxError> {
+    let mut reader = Reader::from_str(content);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(syntax_error_at(content.as_bytes(), reader.buffer_position() as usize, &e)),
+        }
+    }
+}
+
+/// 將 quick-xml 回報的位元組位置換算為行、欄
+fn syntax_error_at(content: &[u8], position: usize, error: &quick_xml::Error) -> XmlSyntaxError {
+    let before = &content[..position.min(content.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+    XmlSyntaxError {
+        line,
+        column,
+        message: error.to_string(),
+    }
+}
+
+/// 讀取檔案內容；宣告了不支援的編碼時回傳 `ParseError`
+pub fn read_xml_file(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    if let Some(encoding) = unsupported_encoding(&bytes) {
+        tracing::warn!(file = %path.display(), %encoding, "不支援的編碼，略過檔案");
+        return Err(Box::new(ParseError {
+            kind: ParseErrorKind::UnsupportedEncoding,
+            file: path.to_path_buf(),
+            message: format!("不支援的編碼 {}，已略過此檔案", encoding),
+            line: None,
+        }));
+    }
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+        tracing::warn!(file = %path.display(), line, "不是有效的 UTF-8，略過檔案");
+        Box::new(ParseError {
+            kind: ParseErrorKind::InvalidUtf8,
+            file: path.to_path_buf(),
+            message: "內容不是有效的 UTF-8，已略過此檔案".to_string(),
+            line: Some(line),
+        }) as Box<dyn std::error::Error>
+    })
+}
+
+/// 從 XML 註解中找出 RimWorld 版本號，例如 `RimWorld 1.4 compatible` 或 `ver="1.4"`
+pub fn find_version_comment(comment: &str) -> Option<String> {
+    let lower = comment.to_lowercase();
+    ["rimworld", "version", "ver"].iter().find_map(|keyword| {
+        let start = lower.find(keyword)? + keyword.len();
+        // 關鍵字與版本號之間只允許空白、引號、等號等分隔字元
+        let rest = lower[start..].trim_start_matches(|c: char| " \t=:\"'v".contains(c));
+        let version: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        let version = version.trim_end_matches('.');
+        (version.contains('.') && version.starts_with(|c: char| c.is_ascii_digit()))
+            .then(|| version.to_string())
+    })
+}
 
 pub fn extract_tag_values(
     path: &std::path::Path,
     tag_name: &str,
 ) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+    let _span = tracing::debug_span!("extract_tag_values", file = %path.display()).entered();
+    tracing::debug!("開始解析");
+
     let tag_name = tag_name.trim().to_lowercase();
 
     let file = File::open(path)?;
@@ -61,7 +257,11 @@ pub fn extract_tag_values(
                 }
             }
             Ok(Event::Eof) => break,
-            Err(_) => break, // 忽略解析錯誤
+            Err(e) => {
+                // 忽略解析錯誤
+                tracing::warn!(position = reader.buffer_position(), error = %e, "XML 解析錯誤");
+                break;
+            }
             _ => {}
         }
         buf.clear();
@@ -69,3 +269,189 @@ pub fn extract_tag_values(
 
     Ok(values)
 }
+
+/// 單一檔案的標籤索引
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileTagIndex {
+    pub values: HashMap<Symbol, HashSet<Symbol>>, // 元素路徑（小寫，例如 defs/thingdef/label）-> 直接出現在其中的文本值
+    pub names: HashMap<Symbol, Symbol>,           // 標籤名稱（小寫）-> 第一次出現時的原始大小寫
+    #[serde(skip)]
+    pub syntax_error: Option<XmlSyntaxError>, // 讀到語法錯誤時停止，之前的標籤仍保留
+}
+
+impl FileTagIndex {
+    /// 標籤（小寫）內部（含子孫節點）出現過的文本
+    pub fn tag_values(&self, tag: &str) -> HashSet<&str> {
+        self.values
+            .iter()
+            .filter(|(path, _)| path.split('/').any(|segment| segment == tag))
+            .flat_map(|(_, values)| values.iter().map(Symbol::as_str))
+            .collect()
+    }
+}
+
+/// 建立檔案的標籤索引：元素路徑 -> 直接出現在其中的文本，路徑上的每個標籤都能查到這些文本
+///
+/// 與 `extract_tag_values` 的規則相同，但一次處理所有標籤；
+/// 依路徑而非每個祖先標籤各存一份，整個掃描的索引在合併前才不會佔用數倍的記憶體
+pub fn index_tag_values(path: &Path) -> Result<FileTagIndex, Box<dyn std::error::Error>> {
+    let _span = tracing::debug_span!("index_tag_values", file = %path.display()).entered();
+    tracing::debug!("開始解析");
+
+    let content = read_xml_file(path)?;
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(true);
+
+    let mut index = FileTagIndex::default();
+    let mut paths: Vec<Symbol> = Vec::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = Symbol::new(&String::from_utf8_lossy(e.name().as_ref()));
+                let lower = Symbol::new(&name.to_lowercase());
+                let path = match paths.last() {
+                    Some(parent) => Symbol::new(&format!("{}/{}", parent, lower)),
+                    None => lower.clone(),
+                };
+                index.names.entry(lower).or_insert(name);
+                paths.push(path);
+            }
+            Ok(Event::Text(e)) => {
+                if let (Ok(text), Some(path)) = (e.unescape(), paths.last()) {
+                    let trimmed = text.trim();
+                    if !trimmed.is_empty() {
+                        index.values.entry(path.clone()).or_default().insert(Symbol::new(trimmed));
+                    }
+                }
+            }
+            Ok(Event::End(_)) => {
+                paths.pop();
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!(position = reader.buffer_position(), error = %e, "XML 解析錯誤");
+                index.syntax_error = Some(syntax_error_at(content.as_bytes(), reader.buffer_position() as usize, &e));
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(index)
+}
+
+/// 帶有 MayRequire / MayRequireAnyOf 屬性的元素
+#[derive(Debug, Clone)]
+pub struct GatedElement {
+    pub attribute: String,        // MayRequire 或 MayRequireAnyOf
+    pub package_ids: Vec<String>, // 已轉為小寫
+    pub element_path: String,     // 例如 ThingDef/comps/li
+    pub def_type: String,
+    pub def_name: String,
+    pub file_path: PathBuf,
+    pub mod_name: String,         // 由呼叫端填入
+}
+
+/// 找出檔案中所有帶有 MayRequire / MayRequireAnyOf 的元素
+pub fn extract_may_require(path: &Path) -> Result<Vec<GatedElement>, Box<dyn std::error::Error>> {
+    let _span = tracing::debug_span!("extract_may_require", file = %path.display()).entered();
+    tracing::debug!("開始解析");
+
+    let file = File::open(path)?;
+    let file = BufReader::new(file);
+    let mut reader = Reader::from_reader(file);
+    reader.config_mut().trim_text(true);
+
+    let mut results = Vec::new();
+    let mut pending: Vec<GatedElement> = Vec::new(); // 目前 Def 內的元素，等 Def 結束後補上 defName
+    let mut stack: Vec<String> = Vec::new();
+    let mut def_name = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                stack.push(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                if stack.len() == 2 {
+                    def_name.clear();
+                }
+                collect_gated(e, &stack, path, &mut def_name, &mut pending);
+            }
+            Ok(Event::Empty(ref e)) => {
+                stack.push(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                collect_gated(e, &stack, path, &mut def_name, &mut pending);
+                stack.pop();
+                if stack.len() < 2 {
+                    flush_gated(&mut pending, &def_name, &mut results);
+                }
+            }
+            Ok(Event::Text(e)) if stack.len() == 3 && stack[2] == "defName" => {
+                if let Ok(text) = e.unescape() {
+                    def_name = text.trim().to_string();
+                }
+            }
+            Ok(Event::End(_)) => {
+                stack.pop();
+                if stack.len() < 2 {
+                    flush_gated(&mut pending, &def_name, &mut results);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                // 忽略解析錯誤
+                tracing::warn!(position = reader.buffer_position(), error = %e, "XML 解析錯誤");
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    flush_gated(&mut pending, &def_name, &mut results);
+    Ok(results)
+}
+
+fn collect_gated(
+    e: &quick_xml::events::BytesStart,
+    stack: &[String],
+    path: &Path,
+    def_name: &mut String,
+    pending: &mut Vec<GatedElement>,
+) {
+    for attr in e.attributes().filter_map(|a| a.ok()) {
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let value = String::from_utf8_lossy(&attr.value).to_string();
+
+        // Abstract Def 以 Name 屬性作為名稱
+        if stack.len() == 2 && key == "Name" && def_name.is_empty() {
+            *def_name = value.clone();
+        }
+
+        if key == "MayRequire" || key == "MayRequireAnyOf" {
+            let package_ids = value
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect();
+            pending.push(GatedElement {
+                attribute: key,
+                package_ids,
+                element_path: stack.join("/"),
+                def_type: stack.get(1).cloned().unwrap_or_default(),
+                def_name: String::new(),
+                file_path: path.to_path_buf(),
+                mod_name: String::new(),
+            });
+        }
+    }
+}
+
+fn flush_gated(pending: &mut Vec<GatedElement>, def_name: &str, results: &mut Vec<GatedElement>) {
+    for mut element in pending.drain(..) {
+        element.def_name = def_name.to_string();
+        results.push(element);
+    }
+}
@@ -0,0 +1,1218 @@
+//! 核心函式庫的整合測試，使用 tests/fixtures/Defs 中的 XML
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use rimworld_xml_tools::database::{
+    build_reverse_references, find_def, find_duplicate_defs, is_excluded, ScanMode, ScanOptions, SharedDefDatabase,
+};
+use rimworld_xml_tools::defs::{
+    def_name_list, find_texture, format_xml, parse_defs_from_str, texture_candidates, DefRootRules, EntrySort,
+};
+use rimworld_xml_tools::export::{csv_field, def_xpath, entries_to_csv, entries_to_defs_xml, node_to_patch_add};
+use rimworld_xml_tools::file_writer::{export_file, DryRunWriter, FileWriter, RealWriter};
+use rimworld_xml_tools::fs_abstraction::{
+    detect_encoding_info, encoding_info, LineEnding, TextEncoding,
+};
+use rimworld_xml_tools::game_view::{build_game_view, default_load_order, GameViewConfig};
+use rimworld_xml_tools::inheritance_logic::{diff_from_parent, FieldChange};
+use rimworld_xml_tools::intern::{self, Symbol};
+use rimworld_xml_tools::journal::Journal;
+use rimworld_xml_tools::merge::{
+    concrete_descendant_counts, expand_def, flatten_nodes, merged_nodes, parse_def_data, parse_def_data_from_str,
+    parse_xml_nodes, DefData, MergeOrder,
+};
+use rimworld_xml_tools::patches::{is_patch_file, parse_patches_from_str};
+use rimworld_xml_tools::query::DefQuery;
+use rimworld_xml_tools::scan_cache::clear_cache;
+use rimworld_xml_tools::search::{find_all, fuzzy_match, fuzzy_score};
+use rimworld_xml_tools::search_index::{about_mod_name, find_mod_about, mod_name, SearchRank};
+use rimworld_xml_tools::settings::{editor_command_args, AppSettings};
+use rimworld_xml_tools::status::{StatusKind, StatusState, ToastLevel};
+use rimworld_xml_tools::validation::{check_required_tags, validate, ValidationKind};
+use rimworld_xml_tools::xml_parser::{
+    extract_tag_values, find_version_comment, index_tag_values, text_value_at, text_value_ranges, ParseErrorKind,
+};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Defs")
+}
+
+fn scan_fixtures() -> SharedDefDatabase {
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let mut db = SharedDefDatabase::default();
+    db.scan(&fixtures(), &DefRootRules::default(), &ScanMode::default(), &status);
+    db
+}
+
+#[test]
+fn expands_inheritance_chain() {
+    let db = scan_fixtures();
+    let gun = &db.data["Gun_Test"];
+    let expanded = expand_def(gun, &db.data, MergeOrder::RootFirst);
+
+    assert_eq!(expanded.chain, ["BaseWeapon", "BaseGun", "Gun_Test"]);
+    assert!(expanded.xml.starts_with("<ThingDef>\n  <defName>Gun_Test</defName>\n"));
+    assert!(expanded.xml.contains("<category>Item</category>"));
+    assert!(expanded.xml.contains("<label>test gun</label>"));
+    // 子類的值覆蓋父類
+    assert!(expanded.xml.contains("<techLevel>Spacer</techLevel>"));
+    assert!(!expanded.xml.contains("Industrial"));
+    assert_eq!(gun.depth, 2);
+}
+
+#[test]
+fn keeps_missing_parent_in_chain() {
+    let db = scan_fixtures();
+    let expanded = expand_def(&db.data["Orphan_Test"], &db.data, MergeOrder::RootFirst);
+
+    assert_eq!(expanded.chain, ["MissingBase", "Orphan_Test"]);
+    assert!(expanded.xml.contains("<defName>Orphan_Test</defName>"));
+}
+
+#[test]
+fn counts_concrete_descendants_of_abstract_defs() {
+    let db = scan_fixtures();
+    let counts = concrete_descendant_counts(&db.data, None);
+
+    assert_eq!(counts["BaseWeapon"], 1);
+    assert_eq!(counts["BaseGun"], 1);
+    assert!(!counts.contains_key("Gun_Test"));
+
+    let other_type = Symbol::new("RecipeDef");
+    assert!(concrete_descendant_counts(&db.data, Some(&other_type)).is_empty());
+}
+
+#[test]
+fn prunes_interned_strings_no_longer_used() {
+    let kept = Symbol::new("PruneTest_Kept");
+    drop(Symbol::new("PruneTest_Dropped"));
+
+    assert!(intern::prune_unused() >= 1);
+    // 仍在使用的字串留在池中，再次駐留得到同一份
+    let again = Symbol::new("PruneTest_Kept");
+    assert!(std::ptr::eq(kept.as_str(), again.as_str()));
+    // 節點中的名稱與文本只佔一個指標
+    assert_eq!(std::mem::size_of::<Option<Symbol>>(), std::mem::size_of::<usize>());
+}
+
+#[test]
+fn merges_list_items_without_duplicates() {
+    let db = scan_fixtures();
+    let expanded = expand_def(&db.data["Gun_Test"], &db.data, MergeOrder::RootFirst);
+
+    for item in ["Weapon", "Gun", "TestOnly"] {
+        let li = format!("<li>{}</li>", item);
+        assert_eq!(expanded.xml.matches(&li).count(), 1, "{}", li);
+    }
+}
+
+#[test]
+fn keeps_duplicate_def_names_in_browser_entries() {
+    let db = scan_fixtures();
+    let duplicates: Vec<_> = db.entries["ThingDef"]
+        .iter()
+        .filter(|e| e.def_name == "Duplicate_Test")
+        .collect();
+
+    assert_eq!(duplicates.len(), 2);
+    assert!(duplicates.iter().any(|e| e.xml_content.contains("first")));
+    assert!(duplicates.iter().any(|e| e.xml_content.contains("second")));
+    // 繼承展開只保留其中一個
+    assert!(db.data.contains_key("Duplicate_Test"));
+}
+
+#[test]
+fn selects_browser_files_by_scan_mode() {
+    let defs = Path::new("Mod/1.5/Defs/Things.xml");
+    let root = Path::new("Mod/Things.xml");
+    let data = Path::new("Mod/Data/Items/Things.xml");
+
+    let default = ScanMode::default();
+    assert!(default.includes(defs));
+    assert!(!default.includes(root));
+    assert!(!default.includes(data));
+
+    assert!(ScanMode::AllXmlFiles.includes(root));
+    assert!(ScanMode::AllXmlFiles.includes(data));
+
+    // 只比對目錄名稱，不分大小寫，可用萬用字元
+    let custom = ScanMode::Custom(vec!["data".to_string(), "1.*".to_string()]);
+    assert!(custom.includes(data));
+    assert!(custom.includes(defs));
+    assert!(!custom.includes(root));
+    assert!(!ScanMode::Custom(vec!["Things*".to_string()]).includes(root));
+    assert!(!ScanMode::Custom(vec!["Dat".to_string()]).includes(data));
+
+    // 不在 Defs 目錄中的 XML 只要根元素是 <Defs> 就會收錄
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let mut db = SharedDefDatabase::default();
+    let load_order = fixtures().join("../load_order");
+    db.scan(&load_order, &DefRootRules::default(), &ScanMode::AllXmlFiles, &status);
+    let mut all = SharedDefDatabase::default();
+    all.scan(&load_order, &DefRootRules::default(), &ScanMode::default(), &status);
+    assert_eq!(db.total_entries(), all.total_entries());
+    assert!(db.errors.is_empty());
+}
+
+#[test]
+fn finds_defs_by_name_across_types() {
+    let db = scan_fixtures();
+
+    let gun = find_def(&db, "Gun_Test").expect("Gun_Test");
+    assert_eq!(gun.def_type.as_str(), "ThingDef");
+    assert_eq!(gun.def_name, "Gun_Test");
+    // 同名時回傳第一個
+    let duplicate = find_def(&db, "Duplicate_Test").expect("Duplicate_Test");
+    assert!(duplicate.xml_content.contains("first"));
+    // 只有 Name 的抽象定義以 Name 為名稱
+    let base = find_def(&db, "BaseGun").expect("BaseGun");
+    assert!(base.is_abstract);
+    assert_eq!(base.name.as_deref(), Some("BaseGun"));
+    assert_eq!(base.parent_name.as_deref(), Some("BaseWeapon"));
+    assert_eq!(gun.name, None);
+    assert_eq!(gun.parent_name.as_deref(), Some("BaseGun"));
+    assert!(find_def(&db, "Missing_Test").is_none());
+}
+
+#[test]
+fn records_def_line_ranges() {
+    let db = scan_fixtures();
+    let mut ranges: Vec<_> = db.entries["ThingDef"]
+        .iter()
+        .filter(|e| e.def_name == "Duplicate_Test")
+        .map(|e| (e.start_line, e.end_line))
+        .collect();
+    ranges.sort();
+
+    assert_eq!(ranges, vec![(3, 9), (11, 17)]);
+}
+
+#[test]
+fn ranks_global_search_results() {
+    let db = scan_fixtures();
+    let ranked = |query: &str| -> Vec<(String, SearchRank)> {
+        db.search_index
+            .search(query)
+            .into_iter()
+            .map(|hit| (db.entries[&hit.def_type][hit.index].def_name.to_string(), hit.rank))
+            .collect()
+    };
+
+    let hits = ranked("duplicate_test");
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|(_, rank)| *rank == SearchRank::Exact));
+
+    assert_eq!(
+        ranked("gun"),
+        vec![
+            ("Gun_Test".to_string(), SearchRank::Prefix),
+            ("BaseGun".to_string(), SearchRank::Substring)
+        ]
+    );
+    assert_eq!(ranked("test gun"), vec![("Gun_Test".to_string(), SearchRank::Label)]);
+    // 前綴相符排在子字串與 label 相符之前
+    let hits = ranked("o");
+    assert_eq!(hits[0], ("Orphan_Test".to_string(), SearchRank::Prefix));
+    assert!(hits.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    assert_eq!(hits.last().unwrap().1, SearchRank::Label);
+}
+
+#[test]
+fn finds_mod_name_from_def_path() {
+    assert_eq!(
+        mod_name(Path::new("Data/Core/Defs/ThingDefs_Items/Items.xml")).as_deref(),
+        Some("Core")
+    );
+    assert_eq!(
+        mod_name(Path::new("Mods/MyMod/1.5/Defs/Things.xml")).as_deref(),
+        Some("MyMod")
+    );
+    assert_eq!(mod_name(Path::new("Things.xml")), None);
+}
+
+#[test]
+fn reads_mod_name_from_about_xml() {
+    let load_order = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/load_order");
+    assert_eq!(
+        about_mod_name(&load_order.join("ExampleMod/Defs/Steel.xml")).as_deref(),
+        Some("Example Mod")
+    );
+    assert_eq!(about_mod_name(&fixtures().join("inheritance.xml")), None);
+}
+
+#[test]
+fn reads_mod_about_fields_from_top_level_elements() {
+    let dir = temp_dir("mod-about");
+    std::fs::create_dir_all(dir.join("MyMod/About")).unwrap();
+    std::fs::write(
+        dir.join("MyMod/About/About.xml"),
+        r#"<ModMetaData>
+  <name>My Mod</name>
+  <packageId>me.mymod</packageId>
+  <authors><li>Alice</li><li>Bob</li></authors>
+  <modDependencies>
+    <li><packageId>brrainz.harmony</packageId><displayName>Harmony</displayName></li>
+  </modDependencies>
+</ModMetaData>"#,
+    )
+    .unwrap();
+
+    let about = find_mod_about(&dir.join("MyMod/1.5/Defs/Things.xml")).unwrap();
+    assert_eq!(about.root, dir.join("MyMod"));
+    assert_eq!(about.name.as_deref(), Some("My Mod"));
+    assert_eq!(about.package_id.as_deref(), Some("me.mymod"));
+    assert_eq!(about.author.as_deref(), Some("Alice, Bob"));
+    assert!(find_mod_about(&dir.join("Other/Defs/Things.xml")).is_none());
+}
+
+#[test]
+fn skips_files_with_unsupported_encoding() {
+    let db = scan_fixtures();
+
+    assert_eq!(db.errors.len(), 1);
+    let error = &db.errors.errors[0];
+    assert_eq!(error.kind, ParseErrorKind::UnsupportedEncoding);
+    assert!(error.file.ends_with("latin1.xml"));
+    assert!(!db.data.keys().any(|name| name.starts_with("Latin1")));
+    assert_eq!(db.xml_count, 3);
+}
+
+#[test]
+fn reports_syntax_errors_with_line() {
+    let dir = temp_dir("syntax-error").join("Defs");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("Broken.xml"),
+        "<Defs>\n  <ThingDef>\n    <defName>Broken_A</defName>\n  </ThingDef>\n  <ThingDef>\n    <defName>Broken_B</label>\n  </ThingDef>\n</Defs>\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("Binary.xml"), b"<Defs>\n<ThingDef>\xff</ThingDef>\n</Defs>").unwrap();
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let mut db = SharedDefDatabase::default();
+    db.scan(&dir, &DefRootRules::default(), &ScanMode::default(), &status);
+
+    let mut errors = db.errors.errors.clone();
+    errors.sort_by(|a, b| a.file.cmp(&b.file));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, ParseErrorKind::InvalidUtf8);
+    assert_eq!(errors[0].line, Some(2));
+    assert_eq!(errors[1].kind, ParseErrorKind::Syntax);
+    assert_eq!(errors[1].line, Some(6));
+    // 錯誤之前的 Def 仍保留
+    assert!(find_def(&db, "Broken_A").is_some());
+}
+
+#[test]
+fn extracts_finder_tag_values() {
+    let path = fixtures().join("inheritance.xml");
+    let values = extract_tag_values(&path, "TRADETAGS").unwrap();
+
+    let mut values: Vec<_> = values.into_iter().collect();
+    values.sort();
+    assert_eq!(values, ["Gun", "TestOnly", "Weapon"]);
+}
+
+#[test]
+fn indexes_all_tag_values() {
+    let path = fixtures().join("inheritance.xml");
+    let index = index_tag_values(&path).unwrap();
+
+    assert_eq!(index.names["tradetags"], "tradeTags");
+    assert_eq!(index.tag_values("tradetags").len(), 3);
+    assert!(index.tag_values("techlevel").contains("Industrial"));
+    assert!(index.tag_values("techlevel").contains("Spacer"));
+    // 祖先標籤也能查到子孫節點的文本
+    assert!(index.tag_values("thingdef").contains("Gun_Test"));
+}
+
+#[test]
+fn merges_tag_index_for_every_ancestor() {
+    let db = scan_fixtures();
+
+    assert_eq!(db.tag_values("techLevel", false), ["Industrial", "Spacer"]);
+    assert!(db.tag_values("tradeTags", false).contains(&"TestOnly".to_string()));
+    // 祖先標籤（包括 Def 類型與根元素）也能查到子孫節點的文本
+    assert!(db.tag_values("ThingDef", false).contains(&"Spacer".to_string()));
+    assert!(db.tag_values("Defs", false).contains(&"Gun_Test".to_string()));
+}
+
+#[test]
+fn merge_order_changes_nested_fields() {
+    let xml = r#"<Defs>
+  <ThingDef Name="Grandparent" Abstract="True">
+    <statBases><MarketValue>10</MarketValue><Mass>2</Mass></statBases>
+    <tradeTags><li>Grand</li></tradeTags>
+  </ThingDef>
+  <ThingDef Name="Parent" ParentName="Grandparent" Abstract="True">
+    <statBases><MarketValue>20</MarketValue></statBases>
+  </ThingDef>
+  <ThingDef ParentName="Parent">
+    <defName>Child</defName>
+    <tradeTags><li>Own</li></tradeTags>
+  </ThingDef>
+</Defs>"#;
+    let all_defs: HashMap<Symbol, DefData> =
+        parse_def_data_from_str(xml, Path::new("Order.xml"), &DefRootRules::default())
+            .into_iter()
+            .map(|def| (def.def_name.clone(), def))
+            .collect();
+    let child = &all_defs["Child"];
+
+    // 由父到子：中間父類的 statBases 整個取代祖父類的，Mass 消失
+    let root_first = expand_def(child, &all_defs, MergeOrder::RootFirst).xml;
+    assert!(root_first.contains("<MarketValue>20</MarketValue>"));
+    assert!(!root_first.contains("<Mass>"));
+    assert!(root_first.find("Grand").unwrap() < root_first.find("Own").unwrap());
+
+    // 由子到父：祖父類只補上中間父類沒有定義的 Mass，列表中子類的項目在前
+    let leaf_first = expand_def(child, &all_defs, MergeOrder::LeafFirst).xml;
+    assert!(leaf_first.contains("<MarketValue>20</MarketValue>"));
+    assert!(leaf_first.contains("<Mass>2</Mass>"));
+    assert!(!leaf_first.contains("<MarketValue>10</MarketValue>"));
+    assert!(leaf_first.find("Own").unwrap() < leaf_first.find("Grand").unwrap());
+}
+
+#[test]
+fn queries_defs_with_builder() {
+    let db = scan_fixtures();
+    let names = |entries: Vec<&rimworld_xml_tools::defs::DefEntry>| {
+        let mut names: Vec<String> = entries.iter().map(|e| e.def_name.to_string()).collect();
+        names.sort();
+        names
+    };
+
+    let concrete = DefQuery::new(&db).of_type("ThingDef").abstract_only(false).execute();
+    assert_eq!(names(concrete), ["Duplicate_Test", "Duplicate_Test", "Gun_Test", "Orphan_Test"]);
+    // 只有 Name 的抽象定義也是瀏覽器條目
+    assert_eq!(names(DefQuery::new(&db).abstract_only(true).execute()), ["BaseGun", "BaseWeapon"]);
+    let bases = DefQuery::new(&db).of_type("ThingDef").abstract_only(true).has_parent("BaseWeapon").execute();
+    assert_eq!(names(bases.clone()), ["BaseGun"]);
+    assert_eq!(bases[0].name.as_deref(), Some("BaseGun"));
+    let tagged = DefQuery::new(&db).with_tag("tradeTags", "TestOnly").execute();
+    assert_eq!(names(tagged), ["Gun_Test"]);
+    let orphans = DefQuery::new(&db).has_parent("MissingBase").with_tag("label", "test gun").execute();
+    assert!(orphans.is_empty());
+    assert_eq!(names(DefQuery::new(&db).has_parent("BaseGun").execute()), ["Gun_Test"]);
+
+    // 條件只看 Def 本身，不含繼承的值
+    assert_eq!(names(DefQuery::new(&db).with_tag("category", "Item").execute()), ["BaseWeapon"]);
+    assert!(DefQuery::new(&db).of_type("RecipeDef").execute().is_empty());
+}
+
+#[test]
+fn parses_abstract_defs_by_name() {
+    let defs = parse_def_data(&fixtures().join("inheritance.xml"), &DefRootRules::default()).unwrap();
+
+    let base = defs.iter().find(|d| d.def_name == "BaseWeapon").unwrap();
+    assert!(base.is_abstract);
+    assert_eq!(base.parent_name, None);
+    let gun = defs.iter().find(|d| d.def_name == "BaseGun").unwrap();
+    assert_eq!(gun.parent_name.as_deref(), Some("BaseWeapon"));
+}
+
+#[test]
+fn applies_def_root_rules() {
+    let xml = r#"<Defs>
+  <ThingDef><defName>Normal</defName><thingDef>Nested</thingDef></ThingDef>
+  <CustomThing><defName>Custom</defName></CustomThing>
+  <ObsoleteDef><defName>Obsolete</defName></ObsoleteDef>
+</Defs>"#;
+    let path = Path::new("Custom.xml");
+    let names = |defs: Vec<DefData>| defs.into_iter().map(|d| d.def_name).collect::<Vec<_>>();
+
+    let default = DefRootRules::default();
+    assert_eq!(names(parse_def_data_from_str(xml, path, &default)), ["Normal", "Obsolete"]);
+    let file = parse_defs_from_str(xml, path, None, &default);
+    assert_eq!(file.entries.len(), 2);
+    assert_eq!(file.skipped_roots, ["CustomThing"]);
+
+    let rules = DefRootRules {
+        allow: vec!["CustomThing".to_string()],
+        deny: vec![" ObsoleteDef ".to_string()],
+        ..DefRootRules::default()
+    };
+    assert_eq!(names(parse_def_data_from_str(xml, path, &rules)), ["Normal", "Custom"]);
+    let file = parse_defs_from_str(xml, path, None, &rules);
+    let entries: Vec<&str> = file.entries.iter().map(|e| e.def_name.as_str()).collect();
+    assert_eq!(entries, ["Normal", "Custom"]);
+    assert_eq!(file.skipped_roots, ["ObsoleteDef"]);
+}
+
+#[test]
+fn reads_top_level_label_and_description() {
+    let xml = r#"<Defs>
+  <ThingDef>
+    <defName>MeleeWeapon_BreachAxe</defName>
+    <label>breach axe</label>
+    <description>A heavy axe.</description>
+    <tools><li><label>blade</label></li></tools>
+  </ThingDef>
+  <ThingDef><defName>Unlabeled</defName></ThingDef>
+</Defs>"#;
+    let file = parse_defs_from_str(xml, Path::new("Axes.xml"), None, &DefRootRules::default());
+    assert_eq!(file.entries[0].label.as_deref(), Some("breach axe"));
+    assert_eq!(file.entries[0].description.as_deref(), Some("A heavy axe."));
+    assert_eq!(file.entries[1].label, None);
+    assert_eq!(file.entries[1].description, None);
+}
+
+#[test]
+fn sorts_entries_by_label_path_and_modified_time() {
+    let old = std::time::SystemTime::UNIX_EPOCH;
+    let new = old + std::time::Duration::from_secs(60);
+    let mut entries = parse_defs_from_str(
+        "<Defs><ThingDef><defName>A_Old</defName><label>zebra</label></ThingDef></Defs>",
+        Path::new("b/Old.xml"),
+        Some(old),
+        &DefRootRules::default(),
+    )
+    .entries;
+    entries.extend(
+        parse_defs_from_str(
+            r#"<Defs>
+  <ThingDef><defName>B_New</defName><label>Apple</label></ThingDef>
+  <ThingDef><defName>C_New</defName></ThingDef>
+</Defs>"#,
+            Path::new("a/New.xml"),
+            Some(new),
+            &DefRootRules::default(),
+        )
+        .entries,
+    );
+    let sorted = |sort: EntrySort| {
+        let mut indices: Vec<usize> = (0..entries.len()).collect();
+        sort.sort(&entries, &mut indices);
+        indices.iter().map(|&i| entries[i].def_name.as_str()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(sorted(EntrySort::DefName), ["A_Old", "B_New", "C_New"]);
+    // 沒有 label 的排在最後，不分大小寫
+    assert_eq!(sorted(EntrySort::Label), ["B_New", "A_Old", "C_New"]);
+    assert_eq!(sorted(EntrySort::FilePath), ["B_New", "C_New", "A_Old"]);
+    // 剛修改的排在最前面
+    assert_eq!(sorted(EntrySort::Modified), ["B_New", "C_New", "A_Old"]);
+}
+
+#[test]
+fn lists_def_names_once_in_sorted_order() {
+    let entries = parse_defs_from_str(
+        r#"<Defs>
+  <ThingDef><defName>Dup</defName><label>zebra</label></ThingDef>
+  <ThingDef><defName>Dup</defName><label>apple</label></ThingDef>
+  <ThingDef><defName>Other</defName><label>mango</label></ThingDef>
+</Defs>"#,
+        Path::new("Defs/Dup.xml"),
+        None,
+        &DefRootRules::default(),
+    )
+    .entries;
+    let mut indices: Vec<usize> = (0..entries.len()).collect();
+    EntrySort::Label.sort(&entries, &mut indices);
+    assert_eq!(indices, [1, 2, 0]);
+
+    // 同名的 Def 不相鄰時也只列一次
+    assert_eq!(def_name_list(&entries, &indices, false), "Dup\nOther");
+    assert_eq!(def_name_list(&entries, &indices, true), "<li>Dup</li>\n<li>Other</li>");
+}
+
+#[test]
+fn filters_entries_by_contained_tag_path() {
+    let xml = r#"<Defs>
+  <ThingDef>
+    <defName>Gun_Test</defName>
+    <description>Mentions techLevel and comps in text only.</description>
+    <statBases><Mass>2</Mass></statBases>
+    <comps><li Class="CompProperties_Forbiddable" /></comps>
+  </ThingDef>
+</Defs>"#;
+    let file = parse_defs_from_str(xml, Path::new("Guns.xml"), None, &DefRootRules::default());
+    let entry = &file.entries[0];
+    assert!(entry.tag_paths.contains(&Symbol::new("comps/li")));
+    assert!(entry.has_tag_path("comps"));
+    assert!(entry.has_tag_path("statbases/mass"));
+    assert!(entry.has_tag_path("Mass"));
+    assert!(entry.has_tag_path("/statBases"));
+    assert!(!entry.has_tag_path("/Mass"));
+    // 描述文字中出現的名稱不算
+    assert!(!entry.has_tag_path("techLevel"));
+    assert!(entry.has_tag_path(""));
+}
+
+#[test]
+fn skips_ignored_def_types() {
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let rules = DefRootRules {
+        ignored: vec!["ThingDef".to_string()],
+        ..DefRootRules::default()
+    };
+    let mut db = SharedDefDatabase::default();
+    db.scan(&fixtures(), &rules, &ScanMode::default(), &status);
+
+    // 忽略的類型不出現在任何檢視中，也不算作略過的根元素
+    assert!(db.entries.is_empty());
+    assert!(db.data.is_empty());
+    assert!(db.skipped_roots.is_empty());
+    assert!(db.skipped_suffix().contains("1 種類型已忽略"));
+}
+
+#[test]
+fn detects_file_encoding() {
+    let info = encoding_info(b"\xEF\xBB\xBF<Defs>\r\n</Defs>\r\n");
+    assert_eq!(info.encoding, TextEncoding::Utf8Bom);
+    assert_eq!(info.line_ending, LineEnding::Crlf);
+    assert_eq!((info.size, info.char_count), (20, 17));
+
+    let info = encoding_info("<a>中文</a>\n<b/>\r".as_bytes());
+    assert_eq!(info.encoding, TextEncoding::Utf8);
+    assert_eq!(info.line_ending, LineEnding::Mixed);
+    assert_eq!(info.char_count, 15);
+
+    let utf16: Vec<u8> = "<?xml?>\n<Defs/>".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let info = encoding_info(&utf16);
+    assert_eq!(info.encoding, TextEncoding::Utf16Le);
+    assert_eq!((info.line_ending, info.char_count), (LineEnding::Lf, 15));
+    let mut bom_be = vec![0xFE, 0xFF];
+    bom_be.extend("<a/>".encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(encoding_info(&bom_be).encoding, TextEncoding::Utf16Be);
+    assert_eq!(encoding_info(&bom_be).line_ending, LineEnding::None);
+
+    let latin1 = detect_encoding_info(&fixtures().join("latin1.xml")).unwrap();
+    assert_eq!(latin1.encoding, TextEncoding::Unknown);
+}
+
+#[test]
+fn finds_text_value_at_offset() {
+    let xml = "<researchPrerequisites>\n  <li> MicroelectronicsBasics </li>\n</researchPrerequisites>";
+    let offset = xml.find("electronics").unwrap();
+    assert_eq!(text_value_at(xml, offset), Some("MicroelectronicsBasics"));
+    // 標籤名稱與標籤之間的空白不是文字節點
+    assert_eq!(text_value_at(xml, xml.find("Prerequisites").unwrap()), None);
+    assert_eq!(text_value_at(xml, xml.find("\n").unwrap()), None);
+    assert_eq!(text_value_at(xml, xml.len()), None);
+}
+
+#[test]
+fn parses_xml_into_node_tree() {
+    let xml = r#"<ThingDef ParentName="BaseGun">
+  <defName>Gun_Test</defName>
+  <comps>
+    <li Class="CompProperties_Forbiddable" />
+    <li><compClass>CompQuality</compClass></li>
+  </comps>
+</ThingDef>"#;
+    let nodes = parse_xml_nodes(xml);
+
+    assert_eq!(nodes.len(), 1);
+    let root = &nodes[0];
+    assert_eq!(root.tag, "ThingDef");
+    assert_eq!(root.attributes[0].1, "BaseGun");
+    assert_eq!(root.children[0].text.as_deref(), Some("Gun_Test"));
+    let comps = &root.children[1].children;
+    assert_eq!(comps.len(), 2);
+    assert_eq!(comps[0].attributes[0].1, "CompProperties_Forbiddable");
+    assert_eq!(comps[1].children[0].text.as_deref(), Some("CompQuality"));
+}
+
+#[test]
+fn flattens_nodes_into_tag_paths() {
+    let xml = r#"<ThingDef ParentName="BaseGun">
+  <defName>Gun_Test</defName>
+  <statBases><MarketValue>120</MarketValue></statBases>
+  <comps>
+    <li Class="CompProperties_Forbiddable" />
+    <li><compClass>CompQuality</compClass></li>
+  </comps>
+  <tradeTags />
+</ThingDef>"#;
+    let rows = flatten_nodes(&parse_xml_nodes(xml));
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(path, value)| (path.as_str(), value.as_str())).collect();
+    assert_eq!(
+        rows,
+        [
+            ("@ParentName", "BaseGun"),
+            ("defName", "Gun_Test"),
+            ("statBases/MarketValue", "120"),
+            ("comps/li[0]/@Class", "CompProperties_Forbiddable"),
+            ("comps/li[1]/compClass", "CompQuality"),
+        ]
+    );
+}
+
+#[test]
+fn lists_text_value_ranges() {
+    let xml = "<products>\n  <li> ComponentIndustrial </li>\n  <Steel>75</Steel>\n</products>";
+    let values: Vec<&str> = text_value_ranges(xml).into_iter().map(|r| &xml[r]).collect();
+    assert_eq!(values, ["ComponentIndustrial", "75"]);
+}
+
+#[test]
+fn finds_version_comments() {
+    assert_eq!(find_version_comment(" RimWorld 1.4 compatible ").as_deref(), Some("1.4"));
+    assert_eq!(find_version_comment(r#" ver="1.5" "#).as_deref(), Some("1.5"));
+    assert_eq!(find_version_comment(" RimWorld mod, version v1.3.2 ").as_deref(), Some("1.3.2"));
+    assert_eq!(find_version_comment(" 武器定義 "), None);
+}
+
+#[test]
+fn reports_version_mismatch_once_per_file() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- RimWorld 1.4 compatible -->
+<Defs>
+  <ThingDef><defName>Old_A</defName></ThingDef>
+  <ThingDef><defName>Old_B</defName></ThingDef>
+</Defs>"#;
+    let all_defs: HashMap<Symbol, DefData> =
+        parse_def_data_from_str(xml, Path::new("Old.xml"), &DefRootRules::default())
+            .into_iter()
+            .map(|def| (def.def_name.clone(), def))
+            .collect();
+
+    assert_eq!(all_defs["Old_A"].file_version.as_deref(), Some("1.4"));
+    assert!(validate(&all_defs, "1.4.3901").is_empty());
+
+    let issues = validate(&all_defs, "1.5");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, ValidationKind::VersionMismatch);
+    assert!(issues[0].file.ends_with("Old.xml"));
+}
+
+#[test]
+fn diffs_child_fields_against_merged_parent() {
+    let db = scan_fixtures();
+    let parent = &db.data["BaseGun"];
+    let effective_parent = DefData {
+        raw_nodes: merged_nodes(parent, &db.data, MergeOrder::RootFirst).into_values().collect(),
+        ..parent.clone()
+    };
+    let diffs = diff_from_parent(&db.data["Gun_Test"], &effective_parent);
+    let change = |tag: &str| diffs.iter().find(|d| d.tag == tag).map(|d| d.change);
+
+    assert_eq!(change("defName"), None);
+    assert_eq!(change("label"), Some(FieldChange::Added));
+    assert_eq!(change("tradeTags"), Some(FieldChange::Extended));
+    assert_eq!(change("techLevel"), Some(FieldChange::Inherited));
+    // 祖父類的欄位也會列出
+    let category = diffs.iter().find(|d| d.tag == "category").unwrap();
+    assert_eq!(category.change, FieldChange::Inherited);
+    assert_eq!(category.parent_value.as_deref(), Some("Item"));
+}
+
+/// 每個測試使用獨立的暫存目錄
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rwxt-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn finds_textures_by_suffix_and_lists_candidates() {
+    let dir = temp_dir("textures");
+    let def_file = dir.join("Defs/ThingDefs/Guns.xml");
+    std::fs::create_dir_all(dir.join("Textures/Things/Gun")).unwrap();
+    std::fs::write(dir.join("Textures/Things/Gun_east.png"), b"").unwrap();
+
+    assert_eq!(find_texture(&def_file, "Things/Gun"), Some(dir.join("Textures/Things/Gun_east.png")));
+    assert_eq!(find_texture(&def_file, "Things/Typo"), None);
+    let candidates = texture_candidates(&def_file, "Things/Typo");
+    assert_eq!(candidates.len(), 5);
+    assert_eq!(candidates[0], dir.join("Textures/Things/Typo.png"));
+    assert_eq!(candidates[4], dir.join("Textures/Things/Typo/*.png"));
+}
+
+#[test]
+fn exports_entries_to_csv() {
+    let db = scan_fixtures();
+    let gun = find_def(&db, "Gun_Test").unwrap();
+    let columns = vec!["label".to_string(), "tradeTags".to_string(), "statBases/Mass".to_string()];
+    let csv = entries_to_csv(std::slice::from_ref(gun), &columns);
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "defName,file,label,tradeTags,statBases/Mass");
+    assert_eq!(lines[1], format!("Gun_Test,{},test gun,TestOnly,", gun.file_path.display()));
+    assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+}
+
+#[test]
+fn builds_def_xpath_from_root_tag() {
+    let db = scan_fixtures();
+    let gun = find_def(&db, "Gun_Test").unwrap();
+    assert_eq!(def_xpath(gun.def_type.as_str(), &gun.def_name), r#"Defs/ThingDef[defName="Gun_Test"]"#);
+}
+
+#[test]
+fn combines_entries_into_single_defs_root() {
+    let db = scan_fixtures();
+    let gun = find_def(&db, "Gun_Test").unwrap();
+    let base = find_def(&db, "Duplicate_Test").unwrap();
+    let xml = entries_to_defs_xml(&[base, gun], &db.base_path);
+
+    assert!(xml.starts_with("<?xml"));
+    assert_eq!(xml.matches("<Defs>").count(), 1);
+    assert!(xml.trim_end().ends_with("</Defs>"));
+    let file = gun.file_path.strip_prefix(&db.base_path).unwrap().display().to_string();
+    assert!(xml.contains(&format!("<!-- {} -->", file)));
+    // 依傳入順序，每個 Def 縮排一層
+    let base_at = xml.find("<defName>Duplicate_Test</defName>").unwrap();
+    let gun_at = xml.find("<defName>Gun_Test</defName>").unwrap();
+    assert!(base_at < gun_at);
+    assert!(xml.lines().filter(|line| line.contains("<ThingDef")).all(|line| line.starts_with("  <")));
+}
+
+#[test]
+fn flattens_nested_patch_operations() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<Patch>
+  <Operation Class="PatchOperationReplace">
+    <xpath>Defs/ThingDef[defName="Gun_Revolver"]/statBases/Mass</xpath>
+    <value><Mass>2</Mass></value>
+  </Operation>
+  <Operation Class="PatchOperationFindMod">
+    <mods>
+      <li>Combat Extended</li>
+    </mods>
+    <match Class="PatchOperationSequence">
+      <operations>
+        <li Class="PatchOperationAdd">
+          <xpath>Defs/ThingDef[defName="Gun_Revolver"]/comps</xpath>
+          <value><li Class="CompProperties_Forbiddable" /></value>
+        </li>
+        <li Class="PatchOperationRemove">
+          <xpath>Defs/ThingDef[defName="Gun_Revolver"]/tools</xpath>
+        </li>
+      </operations>
+    </match>
+  </Operation>
+</Patch>"#;
+    let path = Path::new("Mod/Patches/Guns.xml");
+    assert!(is_patch_file(path));
+    assert!(!is_patch_file(Path::new("Mod/Defs/Guns.xml")));
+
+    let patches = parse_patches_from_str(xml, path);
+    let operations: Vec<&str> = patches.iter().map(|p| p.operation.as_str()).collect();
+    assert_eq!(
+        operations,
+        [
+            "PatchOperationReplace",
+            "PatchOperationFindMod",
+            "PatchOperationSequence",
+            "PatchOperationAdd",
+            "PatchOperationRemove"
+        ]
+    );
+    assert_eq!(patches[0].xpath.as_deref(), Some(r#"Defs/ThingDef[defName="Gun_Revolver"]/statBases/Mass"#));
+    assert_eq!(patches[0].start_line, 3);
+    assert_eq!(patches[1].mods, ["Combat Extended"]);
+    assert_eq!(patches[1].display_name(), "mods: Combat Extended");
+    assert_eq!(patches[2].parent.as_deref(), Some("PatchOperationFindMod › match"));
+    assert_eq!(patches[3].parent.as_deref(), Some("PatchOperationSequence › operations"));
+    assert!(patches[3].xml_content.starts_with("<li Class=\"PatchOperationAdd\">"));
+    assert!(patches[3].xml_content.contains("CompProperties_Forbiddable"));
+    assert_eq!(patches[4].start_line, 17);
+}
+
+#[test]
+fn restores_journaled_files() {
+    let dir = temp_dir("journal-restore");
+    let journal = Journal::new(dir.join("journal"), u64::MAX);
+    let existing = dir.join("Existing.xml");
+    let created = dir.join("Created.xml");
+    std::fs::write(&existing, "<Defs>before</Defs>").unwrap();
+
+    let pending = journal.begin("取代", &[existing.clone(), created.clone()]).unwrap();
+    std::fs::write(&existing, "<Defs>after</Defs>").unwrap();
+    std::fs::write(&created, "<Defs />").unwrap();
+    let entry = pending.commit().unwrap();
+    assert_eq!(journal.entries().len(), 1);
+
+    let warnings = journal.restore(&entry.id).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(std::fs::read_to_string(&existing).unwrap(), "<Defs>before</Defs>");
+    assert!(!created.exists());
+    assert!(journal.entries().is_empty());
+}
+
+#[test]
+fn refuses_to_restore_externally_modified_files() {
+    let dir = temp_dir("journal-conflict");
+    let journal = Journal::new(dir.join("journal"), u64::MAX);
+    let path = dir.join("Defs.xml");
+    std::fs::write(&path, "before").unwrap();
+
+    let pending = journal.begin("格式化", std::slice::from_ref(&path)).unwrap();
+    std::fs::write(&path, "after").unwrap();
+    let entry = pending.commit().unwrap();
+    std::fs::write(&path, "edited elsewhere").unwrap();
+
+    let warnings = journal.restore(&entry.id).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "edited elsewhere");
+}
+
+#[test]
+fn dry_run_writer_writes_nothing() {
+    let dir = temp_dir("dry-run");
+    let existing = dir.join("existing.csv");
+    let created = dir.join("created.dot");
+    std::fs::write(&existing, "old").unwrap();
+
+    let writer = DryRunWriter::default();
+    assert!(export_file(&writer, &created, b"digraph {}").starts_with("試執行"));
+    writer.write(&existing, b"new content").unwrap();
+
+    assert!(!created.exists());
+    assert_eq!(std::fs::read_to_string(&existing).unwrap(), "old");
+    let planned = writer.planned();
+    assert_eq!(planned.len(), 2);
+    assert_eq!((planned[0].size, planned[0].exists), (10, false));
+    assert_eq!((planned[1].size, planned[1].exists), (11, true));
+
+    assert!(export_file(&RealWriter, &created, b"digraph {}").starts_with("✅"));
+    assert_eq!(std::fs::read_to_string(&created).unwrap(), "digraph {}");
+}
+
+#[test]
+fn evicts_oldest_journal_entries() {
+    let dir = temp_dir("journal-evict");
+    let journal = Journal::new(dir.join("journal"), 10);
+    let path = dir.join("Defs.xml");
+
+    for content in ["first version", "second version", "third version"] {
+        std::fs::write(&path, content).unwrap();
+        let pending = journal.begin("取代", std::slice::from_ref(&path)).unwrap();
+        std::fs::write(&path, "changed").unwrap();
+        pending.commit().unwrap();
+    }
+
+    // 超過上限時只保留最新的一筆
+    let entries = journal.entries();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].files.len(), 1);
+}
+
+#[test]
+fn builds_final_loaded_defs_in_load_order() {
+    let base = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/load_order");
+    let load_order = default_load_order(&base);
+    assert!(load_order[0].ends_with("Core"));
+
+    let config = GameViewConfig {
+        load_order,
+        dlcs: Vec::new(),
+    };
+    let view = build_game_view(&config, &DefRootRules::default(), &AtomicBool::new(false), |_, _| {}).unwrap();
+    let things = &view.defs["ThingDef"];
+    let def = |name: &str| things.iter().find(|d| d.def_name == name);
+
+    // 後載入的模組覆蓋本體的定義，並保留繼承的欄位
+    let steel = def("Steel").unwrap();
+    assert_eq!(steel.defined_by, "Example Mod");
+    assert_eq!(steel.overridden, vec!["Core".to_string()]);
+    assert!(steel.xml.contains("better steel"));
+    assert!(steel.xml.contains("<category>Item</category>"));
+
+    // 未啟用的 DLC 的 Def 與節點被移除
+    assert!(def("Royal_Item").is_none());
+    assert_eq!(view.removed_by_may_require, 1);
+    let plasteel = def("Plasteel").unwrap();
+    assert!(!plasteel.xml.contains("Biotech_Category"));
+    assert_eq!(plasteel.patches.len(), 1);
+    assert!(def("BaseResource").is_none());
+}
+
+#[test]
+fn cancelled_game_view_build_returns_nothing() {
+    let base = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/load_order");
+    let config = GameViewConfig {
+        load_order: default_load_order(&base),
+        dlcs: Vec::new(),
+    };
+    assert!(build_game_view(&config, &DefRootRules::default(), &AtomicBool::new(true), |_, _| {}).is_none());
+}
+
+#[test]
+fn formats_attributes_containing_angle_brackets() {
+    let formatted = format_xml(r#"<ThingDef><comps><li Class="A>B" /></comps></ThingDef>"#);
+
+    assert_eq!(
+        formatted,
+        "<ThingDef>\n  <comps>\n    <li Class=\"A>B\" />\n  </comps>\n</ThingDef>\n"
+    );
+}
+
+#[test]
+fn classifies_status_messages() {
+    let mut status = StatusState::default();
+    let kind = |status: &StatusState| status.message.as_ref().map(|m| m.kind);
+
+    status.set_loading("掃描", "正在掃描 Defs...");
+    assert_eq!(kind(&status), Some(StatusKind::Loading));
+    status.set_message("設置", "已儲存");
+    assert_eq!(kind(&status), Some(StatusKind::Info));
+    status.notify("掃描", "找到 3 個 Defs");
+    assert_eq!(kind(&status), Some(StatusKind::Success));
+    status.notify("掃描", "找到 3 個 Defs，⚠ 1 個檔案無法解析而被略過");
+    assert_eq!(kind(&status), Some(StatusKind::Warning));
+    status.set_message("標籤查找器", "錯誤: 請輸入標籤名稱");
+    assert_eq!(kind(&status), Some(StatusKind::Error));
+    assert!(!status.message.as_ref().unwrap().is_expired());
+
+    let levels: Vec<ToastLevel> = status.toasts.iter().map(|t| t.level).collect();
+    assert_eq!(levels, vec![ToastLevel::Success, ToastLevel::Warning]);
+}
+
+#[test]
+fn reports_missing_required_tags() {
+    let db = scan_fixtures();
+    let required = HashMap::from([(
+        "ThingDef".to_string(),
+        vec!["defName".to_string(), "label".to_string(), "techLevel".to_string()],
+    )]);
+    let issues = check_required_tags(&db.entries, &db.data, &required);
+
+    // Gun_Test 的 techLevel 來自父類，不算缺少
+    assert!(issues.iter().all(|i| i.kind == ValidationKind::MissingRequiredTag));
+    assert!(issues.iter().all(|i| i.def_name.as_deref() != Some("Gun_Test")));
+    let orphan = issues
+        .iter()
+        .find(|i| i.def_name.as_deref() == Some("Orphan_Test"))
+        .expect("Orphan_Test");
+    assert!(orphan.message.ends_with("label, techLevel"));
+    // 同名的 Def 各自檢查
+    assert_eq!(
+        issues.iter().filter(|i| i.def_name.as_deref() == Some("Duplicate_Test")).count(),
+        2
+    );
+    assert_eq!(issues.len(), 3);
+
+    assert!(check_required_tags(&db.entries, &db.data, &HashMap::new()).is_empty());
+}
+
+#[test]
+fn fuzzy_matches_subsequences() {
+    let found = fuzzy_match("Gun_Test", "gnts").unwrap();
+    assert_eq!(found.positions, vec![0, 2, 4, 6]);
+    assert!(fuzzy_match("Gun_Test", "tg").is_none());
+    assert_eq!(fuzzy_score("Gun_Test", ""), Some(0));
+
+    // 連續相符與單字開頭的分數較高
+    let consecutive = fuzzy_score("Gun_Test", "test").unwrap();
+    let scattered = fuzzy_score("Tall_Easel_Strut", "test").unwrap();
+    assert!(consecutive > scattered);
+    assert!(fuzzy_score("MealSimple", "ms").unwrap() > fuzzy_score("Mushroom", "ms").unwrap());
+}
+
+#[test]
+fn finds_all_case_insensitive_matches() {
+    let xml = "<defName>Gun_Test</defName>\n<label>測試 gun</label>";
+    let matches = find_all(xml, "GUN");
+    assert_eq!(matches.len(), 2);
+    assert!(matches.iter().all(|range| xml[range.clone()].eq_ignore_ascii_case("gun")));
+    let start = xml.find("測試").unwrap();
+    assert_eq!(find_all(xml, "測試"), vec![start..start + "測試".len()]);
+    // 相符處不重疊
+    assert_eq!(find_all("aaaa", "aa"), vec![0..2, 2..4]);
+    assert!(find_all(xml, "").is_empty());
+}
+
+#[test]
+fn builds_patch_add_for_nested_node() {
+    let db = scan_fixtures();
+    let merged = merged_nodes(&db.data["Gun_Test"], &db.data, MergeOrder::RootFirst);
+    let trade_tags = &merged["tradeTags"];
+    let gun = trade_tags.children.iter().find(|li| li.text.as_deref() == Some("Gun")).unwrap();
+
+    assert_eq!(
+        node_to_patch_add("Gun_Test", "ThingDef", &["tradeTags".to_string()], gun),
+        "<Operation Class=\"PatchOperationAdd\">\n  \
+         <xpath>/Defs/ThingDef[defName=\"Gun_Test\"]/tradeTags</xpath>\n  \
+         <value>\n    <li>Gun</li>\n  </value>\n\
+         </Operation>"
+    );
+
+    let patch = node_to_patch_add("Gun_Test", "ThingDef", &[], trade_tags);
+    assert!(patch.contains("<xpath>/Defs/ThingDef[defName=\"Gun_Test\"]</xpath>"));
+    assert!(patch.contains("    <tradeTags>\n      <li>"));
+}
+
+#[test]
+fn folds_tag_values_case_insensitively() {
+    let mut db = SharedDefDatabase::default();
+    db.tag_index.insert(
+        Symbol::new("stuffcategories"),
+        ["Metallic", "metallic", "Stony", "Woody"].iter().map(|v| Symbol::new(v)).collect(),
+    );
+
+    assert_eq!(db.tag_values("stuffCategories", false), ["Metallic", "Stony", "Woody", "metallic"]);
+    assert_eq!(db.tag_values("stuffCategories", true), ["Metallic", "Stony", "Woody"]);
+    assert!(db.tag_values("missing", true).is_empty());
+}
+
+#[test]
+fn cancelled_scan_returns_none() {
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let mut db = SharedDefDatabase::default();
+    let cancel = AtomicBool::new(true);
+    let scanned = db.scan_cancellable(&fixtures(), &DefRootRules::default(), &ScanMode::default(), &status, &ScanOptions { cancel: Some(&cancel), ..ScanOptions::default() });
+    assert_eq!(scanned, None);
+
+    let cancel = AtomicBool::new(false);
+    let scanned = db.scan_cancellable(&fixtures(), &DefRootRules::default(), &ScanMode::default(), &status, &ScanOptions { cancel: Some(&cancel), ..ScanOptions::default() });
+    assert!(scanned.is_some_and(|count| count > 0));
+    assert!(find_def(&db, "Gun_Test").is_some());
+}
+
+#[test]
+fn matches_exclusion_patterns_against_relative_paths() {
+    let patterns = ["**/Source/**".to_string(), "*backup*".to_string(), "MyMod/Defs/Old".to_string()];
+    assert!(is_excluded(Path::new("MyMod/Source/Defs/Things.xml"), &patterns));
+    assert!(is_excluded(Path::new("Other/Defs/Things_BACKUP.xml"), &patterns));
+    assert!(is_excluded(Path::new("mymod/defs/old"), &patterns));
+    assert!(!is_excluded(Path::new("MyMod/Defs/Old/Things.xml"), &patterns));
+    assert!(!is_excluded(Path::new("MyMod/Defs/Things.xml"), &patterns));
+
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let exclude = ["latin1.xml".to_string()];
+    let mut db = SharedDefDatabase::default();
+    let options = ScanOptions { exclude: &exclude, ..ScanOptions::default() };
+    db.scan_cancellable(&fixtures(), &DefRootRules::default(), &ScanMode::default(), &status, &options);
+    assert_eq!(db.excluded_files, 1);
+    assert!(find_def(&db, "Gun_Test").is_some());
+    assert!(db.entries.values().flatten().all(|e| !e.def_name.starts_with("Latin1")));
+}
+
+#[test]
+fn reuses_cached_scan_results() {
+    let dir = temp_dir("scan-cache");
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let cancel = AtomicBool::new(false);
+    let scan = || {
+        let mut db = SharedDefDatabase::default();
+        let options = ScanOptions { cancel: Some(&cancel), cache_dir: Some(&dir), ..ScanOptions::default() };
+        let count = db.scan_cancellable(&fixtures(), &DefRootRules::default(), &ScanMode::default(), &status, &options);
+        (db, count)
+    };
+
+    let (fresh, fresh_count) = scan();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    let (cached, cached_count) = scan();
+    assert_eq!(fresh_count, cached_count);
+    let names = |db: &SharedDefDatabase| {
+        db.entries.values().flatten().map(|e| (e.def_name.clone(), e.xml_content.clone())).collect::<Vec<_>>()
+    };
+    assert_eq!(names(&fresh), names(&cached));
+    assert_eq!(cached.data.len(), fresh.data.len());
+
+    clear_cache(&dir).unwrap();
+    assert!(!dir.exists());
+    // 目錄不存在時也不算錯誤
+    clear_cache(&dir).unwrap();
+}
+
+#[test]
+fn finds_duplicate_def_names() {
+    let db = scan_fixtures();
+    let duplicates: Vec<&str> = db.duplicates.iter().map(|d| d.def_name.as_str()).collect();
+    assert_eq!(duplicates, ["Duplicate_Test"]);
+    assert_eq!(db.duplicates[0].indices.len(), 2);
+    assert_eq!(find_duplicate_defs(&db.entries), db.duplicates);
+    assert!(db.skipped_suffix().contains("1 個重複的 defName"));
+}
+
+#[test]
+fn builds_reverse_references() {
+    let xml = r#"<Defs>
+  <ThingDef><defName>Steel</defName><description>Made of Steel.</description></ThingDef>
+  <ThingDef><defName>Gun</defName><costList><Steel>50</Steel></costList></ThingDef>
+  <RecipeDef>
+    <defName>Make_Gun</defName>
+    <products><Gun>1</Gun></products>
+    <ingredients><li><filter><thingDefs><li>Steel</li><li>Steel</li></thingDefs></filter></li></ingredients>
+    <recipeUsers><li>Gun</li></recipeUsers>
+  </RecipeDef>
+</Defs>"#;
+    let file = parse_defs_from_str(xml, Path::new("Defs/Test.xml"), None, &DefRootRules::default());
+    let mut entries: BTreeMap<Symbol, Vec<_>> = BTreeMap::new();
+    for entry in file.entries {
+        entries.entry(entry.def_type.clone()).or_default().push(entry);
+    }
+    let name_index: HashMap<Symbol, (Symbol, usize)> = entries
+        .iter()
+        .flat_map(|(def_type, entries)| {
+            entries.iter().enumerate().map(|(i, e)| (e.def_name.clone(), (def_type.clone(), i)))
+        })
+        .collect();
+
+    let references = build_reverse_references(&entries, &name_index);
+    let recipe = (Symbol::new("RecipeDef"), 0);
+    // 標籤名稱（<Steel>50</Steel>）與描述中的文字不算參照，同一條目只記一次
+    assert_eq!(references["Steel"], vec![recipe.clone()]);
+    assert_eq!(references["Gun"], [recipe]);
+    assert!(!references.contains_key("Make_Gun"));
+}
+
+#[test]
+fn keeps_abstract_names_apart_from_def_names() {
+    let dir = temp_dir("name-namespace");
+    std::fs::create_dir_all(dir.join("Defs")).unwrap();
+    std::fs::write(
+        dir.join("Defs/Steel.xml"),
+        r#"<Defs>
+  <ThingDef Name="Steel" Abstract="True"><category>Item</category></ThingDef>
+  <ThingDef ParentName="Steel"><defName>Steel</defName></ThingDef>
+  <ThingDef Name="BaseGun" Abstract="True"><category>Item</category></ThingDef>
+  <ThingDef><defName>Gun</defName><costList><li>Steel</li><li>BaseGun</li></costList></ThingDef>
+</Defs>"#,
+    )
+    .unwrap();
+    let status = Arc::new(Mutex::new(StatusState::default()));
+    let mut db = SharedDefDatabase::default();
+    db.scan(&dir, &DefRootRules::default(), &ScanMode::default(), &status);
+
+    // 抽象的 Name="Steel" 與 <defName>Steel</defName> 不算重複
+    assert!(db.duplicates.is_empty());
+    let steel = find_def(&db, "Steel").unwrap();
+    assert!(steel.has_def_name && !steel.is_abstract);
+    // 沒有同名的 defName 時仍可找到抽象基底
+    assert!(find_def(&db, "BaseGun").is_some_and(|e| e.is_abstract));
+    // 文字等於抽象基底的 Name 不算參照
+    let gun = (Symbol::new("ThingDef"), db.entries["ThingDef"].iter().position(|e| e.def_name == "Gun").unwrap());
+    assert_eq!(db.referenced_by["Steel"], [gun]);
+    assert!(!db.referenced_by.contains_key("BaseGun"));
+}
+
+#[test]
+fn expands_editor_command() {
+    let file = Path::new("/mods/My Mod/Defs/Things.xml");
+    assert_eq!(
+        editor_command_args("code --goto {file}:{line}", file, 42).unwrap(),
+        ["code", "--goto", "/mods/My Mod/Defs/Things.xml:42"]
+    );
+    assert_eq!(
+        editor_command_args(r#""C:\Program Files\Editor\editor.exe" -n{line} {file}"#, file, 7).unwrap(),
+        [r"C:\Program Files\Editor\editor.exe", "-n7", "/mods/My Mod/Defs/Things.xml"]
+    );
+    assert_eq!(editor_command_args("   ", file, 1), None);
+}
+
+#[test]
+fn keeps_session_base_path_out_of_saved_settings() {
+    let mut settings = AppSettings {
+        base_path: "/saved".to_string(),
+        ..AppSettings::default()
+    };
+    settings.session_base_path = Some(PathBuf::from("/from-cli"));
+    assert_eq!(settings.active_base_path(), "/from-cli");
+
+    let json = serde_json::to_string(&settings).unwrap();
+    assert!(!json.contains("from-cli"));
+    let reloaded: AppSettings = serde_json::from_str(&json).unwrap();
+    assert_eq!(reloaded.active_base_path(), "/saved");
+
+    settings.set_base_path("/picked".to_string());
+    assert_eq!(settings.session_base_path, None);
+    assert_eq!(settings.active_base_path(), "/picked");
+}